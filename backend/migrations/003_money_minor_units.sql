@@ -0,0 +1,26 @@
+-- Store money as integer minor units (hundredths) instead of REAL.
+-- SQLite cannot change a column's type in place, so each money column is
+-- rebuilt as INTEGER, backfilled, and renamed back to its original name.
+
+-- accounts.balance / accounts.available_balance
+ALTER TABLE accounts ADD COLUMN balance_minor INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE accounts ADD COLUMN available_balance_minor INTEGER DEFAULT 0;
+UPDATE accounts SET
+    balance_minor = CAST(ROUND(balance * 100) AS INTEGER),
+    available_balance_minor = CAST(ROUND(available_balance * 100) AS INTEGER);
+ALTER TABLE accounts DROP COLUMN balance;
+ALTER TABLE accounts DROP COLUMN available_balance;
+ALTER TABLE accounts RENAME COLUMN balance_minor TO balance;
+ALTER TABLE accounts RENAME COLUMN available_balance_minor TO available_balance;
+
+-- transactions.amount
+ALTER TABLE transactions ADD COLUMN amount_minor INTEGER NOT NULL DEFAULT 0;
+UPDATE transactions SET amount_minor = CAST(ROUND(amount * 100) AS INTEGER);
+ALTER TABLE transactions DROP COLUMN amount;
+ALTER TABLE transactions RENAME COLUMN amount_minor TO amount;
+
+-- balances_history.balance
+ALTER TABLE balances_history ADD COLUMN balance_minor INTEGER NOT NULL DEFAULT 0;
+UPDATE balances_history SET balance_minor = CAST(ROUND(balance * 100) AS INTEGER);
+ALTER TABLE balances_history DROP COLUMN balance;
+ALTER TABLE balances_history RENAME COLUMN balance_minor TO balance;
@@ -0,0 +1,10 @@
+-- Track the currency of every account and transaction.
+-- Existing rows predate multi-currency support and were all entered in USD.
+ALTER TABLE accounts ADD COLUMN currency TEXT NOT NULL DEFAULT 'USD';
+ALTER TABLE transactions ADD COLUMN currency TEXT NOT NULL DEFAULT 'USD';
+
+UPDATE transactions SET currency = (
+    SELECT accounts.currency FROM accounts WHERE accounts.id = transactions.account_id
+);
+
+CREATE INDEX idx_accounts_currency ON accounts(currency);
@@ -0,0 +1,14 @@
+-- Dated exchange rates: one unit of from_currency equals `rate` units of to_currency.
+-- Rates are entered by hand or imported from a file.
+CREATE TABLE exchange_rates (
+    id TEXT PRIMARY KEY DEFAULT (lower(hex(randomblob(16)))),
+    from_currency TEXT NOT NULL,
+    to_currency TEXT NOT NULL,
+    rate REAL NOT NULL CHECK (rate > 0),
+    rate_date DATE NOT NULL,
+    source TEXT NOT NULL DEFAULT 'manual',
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (from_currency, to_currency, rate_date)
+);
+
+CREATE INDEX idx_exchange_rates_pair_date ON exchange_rates(from_currency, to_currency, rate_date);
@@ -0,0 +1,3 @@
+-- User-chosen display name for accounts. Kept separate from `name` so that
+-- SimpleFin-linked accounts can be renamed without the next sync undoing it.
+ALTER TABLE accounts ADD COLUMN display_name TEXT;
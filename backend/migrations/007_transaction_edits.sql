@@ -0,0 +1,14 @@
+-- User edits to transactions that sync must never overwrite.
+-- display_description overrides the provider's description, the original is kept for matching.
+ALTER TABLE transactions ADD COLUMN display_description TEXT;
+ALTER TABLE transactions ADD COLUMN note TEXT;
+-- Set once the user picks a category; automatic categorization leaves locked rows alone
+ALTER TABLE transactions ADD COLUMN category_locked BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- SimpleFin IDs of transactions the user deleted, so sync does not import them again
+CREATE TABLE transaction_tombstones (
+    simplefin_id TEXT PRIMARY KEY,
+    account_id TEXT NOT NULL,
+    deleted_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (account_id) REFERENCES accounts (id) ON DELETE CASCADE
+);
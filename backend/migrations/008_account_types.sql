@@ -0,0 +1,8 @@
+-- Account types become a closed set; map free-form values onto it
+UPDATE accounts SET account_type = LOWER(TRIM(account_type));
+
+UPDATE accounts SET account_type = 'credit'
+WHERE account_type IN ('credit card', 'credit_card', 'creditcard');
+
+UPDATE accounts SET account_type = 'other'
+WHERE account_type NOT IN ('checking', 'savings', 'credit', 'investment', 'loan', 'cash', 'other');
@@ -0,0 +1,82 @@
+-- Categories replace the free-form transactions.category string
+CREATE TABLE categories (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    parent_id TEXT,
+    kind TEXT NOT NULL DEFAULT 'expense' CHECK (kind IN ('income', 'expense', 'transfer')),
+    color TEXT,
+    icon TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (parent_id) REFERENCES categories (id)
+);
+
+-- Sibling names are unique regardless of case
+CREATE UNIQUE INDEX idx_categories_parent_name
+    ON categories (COALESCE(parent_id, ''), name COLLATE NOCASE);
+CREATE INDEX idx_categories_parent_id ON categories (parent_id);
+
+-- Split legacy strings such as "Food:Groceries" into one row per path prefix.
+-- Paths are compared case-insensitively, so "Groceries" and "groceries" collapse.
+CREATE TEMP TABLE legacy_category_paths AS
+WITH RECURSIVE segments (source, path, parent_path, name, depth, rest) AS (
+    SELECT DISTINCT category, '', NULL, NULL, 0, category || ':'
+    FROM transactions
+    WHERE category IS NOT NULL AND TRIM(category) <> ''
+    UNION ALL
+    SELECT
+        source,
+        CASE WHEN path = '' THEN '' ELSE path || ':' END
+            || TRIM(SUBSTR(rest, 1, INSTR(rest, ':') - 1)),
+        path,
+        TRIM(SUBSTR(rest, 1, INSTR(rest, ':') - 1)),
+        depth + 1,
+        SUBSTR(rest, INSTR(rest, ':') + 1)
+    FROM segments
+    WHERE rest <> '' AND TRIM(SUBSTR(rest, 1, INSTR(rest, ':') - 1)) <> ''
+    UNION ALL
+    -- Skip empty segments, e.g. the middle of "Food::Groceries"
+    SELECT source, path, parent_path, name, depth, SUBSTR(rest, INSTR(rest, ':') + 1)
+    FROM segments
+    WHERE rest <> '' AND TRIM(SUBSTR(rest, 1, INSTR(rest, ':') - 1)) = ''
+)
+SELECT source, path, parent_path, name, depth, rest FROM segments WHERE depth > 0;
+
+-- One category per case-insensitive path; the first spelling in sort order wins
+CREATE TEMP TABLE legacy_categories AS
+SELECT
+    LOWER(path) AS path_key,
+    LOWER(MIN(parent_path)) AS parent_key,
+    MIN(name) AS name,
+    MIN(depth) AS depth,
+    lower(hex(randomblob(4))) || '-' || lower(hex(randomblob(2))) || '-4'
+        || substr(lower(hex(randomblob(2))), 2) || '-'
+        || substr('89ab', 1 + (abs(random()) % 4), 1)
+        || substr(lower(hex(randomblob(2))), 2) || '-'
+        || lower(hex(randomblob(6))) AS id
+FROM legacy_category_paths
+GROUP BY LOWER(path);
+
+INSERT INTO categories (id, name, parent_id)
+SELECT c.id, c.name, parent.id
+FROM legacy_categories c
+LEFT JOIN legacy_categories parent ON parent.path_key = c.parent_key
+ORDER BY c.depth;
+
+ALTER TABLE transactions ADD COLUMN category_id TEXT
+    REFERENCES categories (id) ON DELETE SET NULL;
+
+-- The deepest segment of each legacy string is the transaction's category
+UPDATE transactions SET category_id = (
+    SELECT c.id
+    FROM legacy_category_paths p
+    JOIN legacy_categories c ON c.path_key = LOWER(p.path)
+    WHERE p.source = transactions.category AND p.rest = ''
+)
+WHERE category IS NOT NULL;
+
+ALTER TABLE transactions DROP COLUMN category;
+
+CREATE INDEX idx_transactions_category_id ON transactions (category_id);
+
+DROP TABLE legacy_category_paths;
+DROP TABLE legacy_categories;
@@ -0,0 +1,36 @@
+-- User-defined rules that categorize and annotate transactions.
+-- All conditions that are set must match; rules run in ascending priority.
+CREATE TABLE rules (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    priority INTEGER NOT NULL DEFAULT 0,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    -- Conditions. Patterns are case-insensitive regular expressions and the
+    -- amount bounds apply to the magnitude in minor units.
+    description_pattern TEXT,
+    payee_pattern TEXT,
+    memo_pattern TEXT,
+    min_amount INTEGER,
+    max_amount INTEGER,
+    account_id TEXT,
+    sign TEXT CHECK (sign IN ('negative', 'positive')),
+    -- Actions
+    set_category_id TEXT,
+    set_payee TEXT,
+    add_tags TEXT NOT NULL DEFAULT '[]',
+    set_flagged BOOLEAN,
+    set_excluded BOOLEAN,
+    stop_processing BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (account_id) REFERENCES accounts (id) ON DELETE CASCADE,
+    FOREIGN KEY (set_category_id) REFERENCES categories (id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_rules_priority ON rules (priority);
+
+-- Fields rules can set on a transaction. The provider's payee is kept for matching.
+ALTER TABLE transactions ADD COLUMN payee_override TEXT;
+ALTER TABLE transactions ADD COLUMN tags TEXT NOT NULL DEFAULT '[]';
+ALTER TABLE transactions ADD COLUMN flagged BOOLEAN NOT NULL DEFAULT FALSE;
+-- Excluded transactions are left out of reports, e.g. transfers between own accounts
+ALTER TABLE transactions ADD COLUMN excluded BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,28 @@
+-- How a transaction got its category. Only manual and rule categories train
+-- the classifier, so its own suggestions never feed back into it.
+ALTER TABLE transactions ADD COLUMN category_source TEXT
+    CHECK (category_source IN ('manual', 'rule', 'suggestion'));
+UPDATE transactions SET category_source = 'manual' WHERE category_id IS NOT NULL;
+
+-- Category this transaction currently contributes to the classifier counts,
+-- so the model can be updated incrementally when categories change
+ALTER TABLE transactions ADD COLUMN trained_category_id TEXT
+    REFERENCES categories (id) ON DELETE SET NULL;
+
+-- Naive Bayes counts: training transactions per category and token occurrences per category
+CREATE TABLE classifier_categories (
+    category_id TEXT PRIMARY KEY,
+    documents INTEGER NOT NULL DEFAULT 0,
+    tokens INTEGER NOT NULL DEFAULT 0,
+    FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE CASCADE
+);
+
+CREATE TABLE classifier_tokens (
+    token TEXT NOT NULL,
+    category_id TEXT NOT NULL,
+    count INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (token, category_id),
+    FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_classifier_tokens_category_id ON classifier_tokens (category_id);
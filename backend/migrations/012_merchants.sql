@@ -0,0 +1,23 @@
+-- Canonical merchants. Transactions are assigned one from their payee or
+-- description when they are created; existing rows are assigned at startup.
+CREATE TABLE merchants (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE UNIQUE INDEX idx_merchants_name ON merchants (name COLLATE NOCASE);
+
+-- Case-insensitive regex patterns matched against a transaction's payee and
+-- description. An alias match takes precedence over the cleaned-up name.
+CREATE TABLE merchant_aliases (
+    merchant_id TEXT NOT NULL,
+    pattern TEXT NOT NULL,
+    PRIMARY KEY (merchant_id, pattern),
+    FOREIGN KEY (merchant_id) REFERENCES merchants (id) ON DELETE CASCADE
+);
+
+ALTER TABLE transactions ADD COLUMN merchant_id TEXT
+    REFERENCES merchants (id) ON DELETE SET NULL;
+
+CREATE INDEX idx_transactions_merchant_id ON transactions (merchant_id);
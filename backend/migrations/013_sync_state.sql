@@ -0,0 +1,10 @@
+-- High-water marks for incremental sync. Each sync requests transactions from
+-- the last successful sync minus an overlap window; a connection or account
+-- without one gets a full historical backfill.
+CREATE TABLE sync_state (
+    -- SimpleFin server URL, without credentials
+    connection TEXT PRIMARY KEY,
+    last_synced_at TEXT NOT NULL
+);
+
+ALTER TABLE accounts ADD COLUMN last_synced_at TEXT;
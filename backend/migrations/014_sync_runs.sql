@@ -0,0 +1,15 @@
+-- One row per SimpleFin sync, kept so the frontend can show when data was
+-- last refreshed and why a sync failed. Runs still marked running at startup
+-- were interrupted by a shutdown and are marked failed.
+CREATE TABLE sync_runs (
+    id TEXT PRIMARY KEY,
+    trigger TEXT NOT NULL CHECK (trigger IN ('startup', 'scheduled', 'manual')),
+    status TEXT NOT NULL CHECK (status IN ('running', 'succeeded', 'failed')),
+    started_at TEXT NOT NULL,
+    finished_at TEXT,
+    error TEXT,
+    -- SyncStats as JSON, set when the run succeeds
+    stats TEXT
+);
+
+CREATE INDEX idx_sync_runs_started_at ON sync_runs (started_at);
@@ -0,0 +1,18 @@
+-- Sync schedule changed through the API, and whether scheduling is paused.
+-- There is a single row. The schedule columns are only used when
+-- `customized` is set; otherwise the SYNC_* environment variables apply.
+CREATE TABLE sync_schedule (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    customized INTEGER NOT NULL DEFAULT 0,
+    interval_minutes INTEGER,
+    -- Five-field cron expression in the server's local time
+    cron TEXT,
+    quiet_hours_start TEXT,
+    quiet_hours_end TEXT,
+    -- Set when SimpleFin rejects the credentials; cleared by resuming
+    -- scheduling or by a successful sync
+    paused_at TEXT,
+    pause_reason TEXT
+);
+
+INSERT INTO sync_schedule (id) VALUES (1);
@@ -0,0 +1,4 @@
+-- Messages from SimpleFin's `errors` list that concern an account, as of the
+-- last sync that returned the account. All messages of a sync are also kept
+-- in its `sync_runs` stats.
+ALTER TABLE accounts ADD COLUMN sync_error TEXT;
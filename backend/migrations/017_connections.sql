@@ -0,0 +1,9 @@
+-- SimpleFin connections claimed through the API. `access_url` contains the
+-- credentials and is never returned; `server_url` is the same URL without them.
+CREATE TABLE connections (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    access_url TEXT NOT NULL,
+    server_url TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
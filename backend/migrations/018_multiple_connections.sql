@@ -0,0 +1,15 @@
+-- Every enabled connection is synced. The high-water mark moves from
+-- sync_state onto the connection; sync_state is only read once more, when
+-- the SIMPLEFIN_ACCESS_URL connection is first stored.
+ALTER TABLE connections ADD COLUMN enabled INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE connections ADD COLUMN last_synced_at TEXT;
+
+UPDATE connections SET last_synced_at = (
+    SELECT last_synced_at FROM sync_state WHERE sync_state.connection = connections.server_url
+);
+
+-- Connection an account was last synced through
+ALTER TABLE accounts ADD COLUMN connection_id TEXT
+    REFERENCES connections (id) ON DELETE SET NULL;
+
+CREATE INDEX idx_accounts_connection_id ON accounts (connection_id);
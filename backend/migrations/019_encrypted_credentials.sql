@@ -0,0 +1,4 @@
+-- Access URLs are encrypted with the credentials key. Rows stored before
+-- this migration hold plaintext until the server next starts, which
+-- encrypts them.
+ALTER TABLE connections RENAME COLUMN access_url TO encrypted_access_url;
@@ -0,0 +1,48 @@
+-- Accounts, transactions and holdings can come from any bank-data provider,
+-- not only SimpleFin. An account's external ID is unique within its
+-- provider; a transaction's or holding's within its account.
+ALTER TABLE connections ADD COLUMN provider TEXT NOT NULL DEFAULT 'simplefin';
+
+DROP INDEX idx_accounts_simplefin_id_unique;
+DROP INDEX idx_accounts_simplefin_id;
+ALTER TABLE accounts RENAME COLUMN simplefin_id TO external_id;
+ALTER TABLE accounts ADD COLUMN provider TEXT;
+UPDATE accounts SET provider = 'simplefin' WHERE external_id IS NOT NULL;
+CREATE UNIQUE INDEX idx_accounts_external_id ON accounts (provider, external_id)
+    WHERE external_id IS NOT NULL;
+
+DROP INDEX idx_transactions_simplefin_id_unique;
+DROP INDEX idx_transactions_simplefin_id;
+ALTER TABLE transactions RENAME COLUMN simplefin_id TO external_id;
+CREATE UNIQUE INDEX idx_transactions_external_id ON transactions (account_id, external_id)
+    WHERE external_id IS NOT NULL;
+
+-- External IDs of transactions the user deleted, or of pending transactions
+-- merged into their posted version, so sync does not import them again
+CREATE TABLE transaction_tombstones_new (
+    account_id TEXT NOT NULL,
+    external_id TEXT NOT NULL,
+    deleted_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (account_id, external_id),
+    FOREIGN KEY (account_id) REFERENCES accounts (id) ON DELETE CASCADE
+);
+INSERT INTO transaction_tombstones_new (account_id, external_id, deleted_at)
+    SELECT account_id, simplefin_id, deleted_at FROM transaction_tombstones;
+DROP TABLE transaction_tombstones;
+ALTER TABLE transaction_tombstones_new RENAME TO transaction_tombstones;
+
+-- Current positions of investment accounts, replaced on every sync
+CREATE TABLE holdings (
+    id TEXT PRIMARY KEY,
+    account_id TEXT NOT NULL,
+    external_id TEXT NOT NULL,
+    symbol TEXT,
+    description TEXT NOT NULL,
+    shares REAL NOT NULL,
+    market_value INTEGER NOT NULL,
+    cost_basis INTEGER,
+    currency TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    UNIQUE (account_id, external_id),
+    FOREIGN KEY (account_id) REFERENCES accounts (id) ON DELETE CASCADE
+);
@@ -0,0 +1,47 @@
+-- Store each amount in the minor units of its own currency instead of
+-- hundredths for every currency: yen for JPY, fils for BHD. The currency lists
+-- match money::minor_digits. Rule thresholds are not tied to a currency and
+-- keep two fractional digits.
+
+-- Zero-digit currencies: divide by 100, rounding half away from zero
+UPDATE accounts SET
+    balance = CAST(ROUND(balance / 100.0) AS INTEGER),
+    available_balance = CAST(ROUND(available_balance / 100.0) AS INTEGER)
+WHERE currency IN ('BIF', 'CLP', 'DJF', 'GNF', 'ISK', 'JPY', 'KMF', 'KRW', 'PYG', 'RWF',
+                   'UGX', 'UYI', 'VND', 'VUV', 'XAF', 'XOF', 'XPF');
+UPDATE transactions SET amount = CAST(ROUND(amount / 100.0) AS INTEGER)
+WHERE currency IN ('BIF', 'CLP', 'DJF', 'GNF', 'ISK', 'JPY', 'KMF', 'KRW', 'PYG', 'RWF',
+                   'UGX', 'UYI', 'VND', 'VUV', 'XAF', 'XOF', 'XPF');
+UPDATE holdings SET
+    market_value = CAST(ROUND(market_value / 100.0) AS INTEGER),
+    cost_basis = CAST(ROUND(cost_basis / 100.0) AS INTEGER)
+WHERE currency IN ('BIF', 'CLP', 'DJF', 'GNF', 'ISK', 'JPY', 'KMF', 'KRW', 'PYG', 'RWF',
+                   'UGX', 'UYI', 'VND', 'VUV', 'XAF', 'XOF', 'XPF');
+UPDATE balances_history SET balance = CAST(ROUND(balance / 100.0) AS INTEGER)
+WHERE account_id IN (
+    SELECT id FROM accounts
+    WHERE currency IN ('BIF', 'CLP', 'DJF', 'GNF', 'ISK', 'JPY', 'KMF', 'KRW', 'PYG', 'RWF',
+                       'UGX', 'UYI', 'VND', 'VUV', 'XAF', 'XOF', 'XPF')
+);
+
+-- Three-digit currencies: multiply by 10
+UPDATE accounts SET balance = balance * 10, available_balance = available_balance * 10
+WHERE currency IN ('BHD', 'IQD', 'JOD', 'KWD', 'LYD', 'OMR', 'TND');
+UPDATE transactions SET amount = amount * 10
+WHERE currency IN ('BHD', 'IQD', 'JOD', 'KWD', 'LYD', 'OMR', 'TND');
+UPDATE holdings SET market_value = market_value * 10, cost_basis = cost_basis * 10
+WHERE currency IN ('BHD', 'IQD', 'JOD', 'KWD', 'LYD', 'OMR', 'TND');
+UPDATE balances_history SET balance = balance * 10
+WHERE account_id IN (
+    SELECT id FROM accounts WHERE currency IN ('BHD', 'IQD', 'JOD', 'KWD', 'LYD', 'OMR', 'TND')
+);
+
+-- Four-digit currencies: multiply by 100
+UPDATE accounts SET balance = balance * 100, available_balance = available_balance * 100
+WHERE currency IN ('CLF', 'UYW');
+UPDATE transactions SET amount = amount * 100
+WHERE currency IN ('CLF', 'UYW');
+UPDATE holdings SET market_value = market_value * 100, cost_basis = cost_basis * 100
+WHERE currency IN ('CLF', 'UYW');
+UPDATE balances_history SET balance = balance * 100
+WHERE account_id IN (SELECT id FROM accounts WHERE currency IN ('CLF', 'UYW'));
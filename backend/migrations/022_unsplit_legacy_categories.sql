@@ -0,0 +1,41 @@
+-- Legacy category strings with no name between the separators, such as ":",
+-- got no category from 009. database::create_pool copies them into
+-- legacy_unsplit_categories before 009 drops transactions.category; each keeps
+-- its raw text as the name of a top-level category. Databases migrated past
+-- 009 before this fix have no such table, and those strings are gone.
+CREATE TABLE IF NOT EXISTS legacy_unsplit_categories (
+    transaction_id TEXT NOT NULL,
+    category TEXT NOT NULL
+);
+
+-- One category per case-insensitive name; the first spelling in sort order wins
+INSERT INTO categories (id, name, parent_id)
+SELECT
+    lower(hex(randomblob(4))) || '-' || lower(hex(randomblob(2))) || '-4'
+        || substr(lower(hex(randomblob(2))), 2) || '-'
+        || substr('89ab', 1 + (abs(random()) % 4), 1)
+        || substr(lower(hex(randomblob(2))), 2) || '-'
+        || lower(hex(randomblob(6))),
+    MIN(TRIM(category)),
+    NULL
+FROM legacy_unsplit_categories
+WHERE NOT EXISTS (
+    SELECT 1 FROM categories c
+    WHERE c.parent_id IS NULL AND c.name = TRIM(category) COLLATE NOCASE
+)
+GROUP BY LOWER(TRIM(category));
+
+-- Legacy categories count as manual, as in 011; transactions categorized
+-- since are left alone
+UPDATE transactions SET
+    category_id = (
+        SELECT c.id
+        FROM legacy_unsplit_categories l
+        JOIN categories c ON c.parent_id IS NULL AND c.name = TRIM(l.category) COLLATE NOCASE
+        WHERE l.transaction_id = transactions.id
+    ),
+    category_source = 'manual'
+WHERE category_id IS NULL
+  AND id IN (SELECT transaction_id FROM legacy_unsplit_categories);
+
+DROP TABLE legacy_unsplit_categories;
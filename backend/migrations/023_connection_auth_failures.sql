@@ -0,0 +1,5 @@
+-- Set when the provider rejected a connection's credentials. Scheduled syncs
+-- skip the connection until it syncs again, e.g. from a manual sync, or is
+-- retried by re-enabling it or resuming scheduling.
+ALTER TABLE connections ADD COLUMN auth_failed_at TEXT;
+ALTER TABLE connections ADD COLUMN auth_error TEXT;
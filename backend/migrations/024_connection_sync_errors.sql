@@ -0,0 +1,4 @@
+-- Messages from the provider that name no account, as of the connection's
+-- last sync; cleared by a sync without any. Account messages stay on
+-- accounts.sync_error.
+ALTER TABLE connections ADD COLUMN sync_error TEXT;
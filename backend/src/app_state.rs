@@ -1,19 +1,56 @@
 use sqlx::SqlitePool;
 use std::sync::Arc;
 
+use crate::connections;
+use crate::credentials::CredentialCipher;
+use crate::error::AppError;
+use crate::exchange_rates::CurrencyConverter;
+use crate::scheduler::SyncScheduler;
 use crate::sync::SyncService;
 
 #[derive(Clone)]
 pub struct AppState {
     pub pool: SqlitePool,
-    pub sync_service: Option<Arc<SyncService>>,
+    pub sync_service: Arc<SyncService>,
+    pub scheduler: Arc<SyncScheduler>,
+    /// Encrypts connection credentials before they are stored
+    pub cipher: Arc<CredentialCipher>,
+    /// Default currency for converted totals when a request does not name one
+    pub base_currency: String,
 }
 
 impl AppState {
-    pub fn new(pool: SqlitePool, sync_service: Option<Arc<SyncService>>) -> Self {
+    pub fn new(
+        pool: SqlitePool,
+        sync_service: Arc<SyncService>,
+        scheduler: Arc<SyncScheduler>,
+        cipher: Arc<CredentialCipher>,
+        base_currency: String,
+    ) -> Self {
         Self {
             pool,
             sync_service,
+            scheduler,
+            cipher,
+            base_currency,
         }
     }
+
+    /// Fails while no connection is enabled, as there is nothing to sync from.
+    pub async fn require_provider(&self) -> Result<(), AppError> {
+        if !connections::any_enabled(&self.pool).await? {
+            return Err(AppError::ServiceUnavailable(
+                "no bank data provider is configured; add a SimpleFin connection first".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Converter into the requested base currency, or the configured default.
+    pub fn converter(&self, base_currency: Option<&str>) -> CurrencyConverter {
+        CurrencyConverter::new(
+            self.pool.clone(),
+            base_currency.unwrap_or(&self.base_currency),
+        )
+    }
 }
\ No newline at end of file
@@ -0,0 +1,102 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BackfillStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// Progress of a historical backfill. Each date window is stored as soon as
+/// it is fetched, so a failed or cancelled job keeps what it already imported.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BackfillJob {
+    pub id: String,
+    pub status: BackfillStatus,
+    #[schema(value_type = String, format = DateTime)]
+    pub from: DateTime<Utc>,
+    #[schema(value_type = String, format = DateTime)]
+    pub to: DateTime<Utc>,
+    /// Local account IDs; empty means every account of every enabled connection
+    pub account_ids: Vec<String>,
+    pub windows_total: u32,
+    pub windows_completed: u32,
+    pub accounts_created: u32,
+    pub transactions_created: u32,
+    pub error: Option<String>,
+    #[schema(value_type = String, format = DateTime)]
+    pub started_at: DateTime<Utc>,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+struct Entry {
+    job: BackfillJob,
+    cancel: Arc<AtomicBool>,
+}
+
+/// In-memory registry of backfill jobs. Jobs do not survive a restart.
+#[derive(Clone, Default)]
+pub struct BackfillJobs {
+    jobs: Arc<Mutex<HashMap<String, Entry>>>,
+}
+
+impl BackfillJobs {
+    /// Registers a new job and returns the flag that requests its cancellation.
+    pub fn insert(&self, job: BackfillJob) -> Arc<AtomicBool> {
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.jobs.lock().unwrap().insert(
+            job.id.clone(),
+            Entry {
+                job,
+                cancel: cancel.clone(),
+            },
+        );
+        cancel
+    }
+
+    pub fn get(&self, id: &str) -> Option<BackfillJob> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .get(id)
+            .map(|entry| entry.job.clone())
+    }
+
+    /// Every job, newest first.
+    pub fn list(&self) -> Vec<BackfillJob> {
+        let mut jobs: Vec<BackfillJob> = self
+            .jobs
+            .lock()
+            .unwrap()
+            .values()
+            .map(|entry| entry.job.clone())
+            .collect();
+        jobs.sort_by_key(|job| std::cmp::Reverse(job.started_at));
+        jobs
+    }
+
+    pub fn update(&self, id: &str, f: impl FnOnce(&mut BackfillJob)) {
+        if let Some(entry) = self.jobs.lock().unwrap().get_mut(id) {
+            f(&mut entry.job);
+        }
+    }
+
+    /// Asks a running job to stop after its current window. Returns the job
+    /// as it is now, or `None` if there is no such job.
+    pub fn cancel(&self, id: &str) -> Option<BackfillJob> {
+        let jobs = self.jobs.lock().unwrap();
+        let entry = jobs.get(id)?;
+        if entry.job.status == BackfillStatus::Running {
+            entry.cancel.store(true, Ordering::Relaxed);
+        }
+        Some(entry.job.clone())
+    }
+}
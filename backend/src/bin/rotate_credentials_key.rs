@@ -0,0 +1,54 @@
+//! Re-encrypts stored connection credentials with a new key.
+//!
+//! The current key must be given explicitly: as the path of its key file in
+//! the first argument, or in `CREDENTIALS_KEY` or `CREDENTIALS_KEY_FILE`. It is
+//! never generated, since credentials cannot be decrypted with a fresh key.
+//! The new key comes from `NEW_CREDENTIALS_KEY`,
+//! base64-encoded, or from the file named by `NEW_CREDENTIALS_KEY_FILE`,
+//! which is generated if it does not exist. Stop the server first, and point
+//! it at the new key before starting it again.
+
+use std::env;
+use std::path::Path;
+
+use budget_tracker_backend::{connections, credentials::CredentialCipher, database};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenv::dotenv().ok();
+
+    let database_url =
+        env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite:budget_tracker.db".to_string());
+
+    let current = match env::args().nth(1) {
+        Some(path) => CredentialCipher::from_file(&path)?,
+        None => CredentialCipher::from_env_vars()?.ok_or(
+            "Pass the current key file as an argument, or set CREDENTIALS_KEY or CREDENTIALS_KEY_FILE",
+        )?,
+    };
+    let new = match (
+        env::var("NEW_CREDENTIALS_KEY"),
+        env::var("NEW_CREDENTIALS_KEY_FILE"),
+    ) {
+        (Ok(key), _) => CredentialCipher::from_base64(&key)?,
+        (_, Ok(path)) if Path::new(&path).exists() => CredentialCipher::from_file(&path)?,
+        (_, Ok(path)) => {
+            let cipher = CredentialCipher::create_file(&path)?;
+            println!("Generated a new key in {}", path);
+            cipher
+        }
+        _ => {
+            return Err("Set NEW_CREDENTIALS_KEY or NEW_CREDENTIALS_KEY_FILE to the new key".into());
+        }
+    };
+
+    let pool = database::create_pool(&database_url).await?;
+    let rotated = connections::rotate_key(&pool, &current, &new).await?;
+
+    println!(
+        "Re-encrypted the credentials of {} connections. Configure the server with the new key before starting it.",
+        rotated
+    );
+
+    Ok(())
+}
@@ -0,0 +1,148 @@
+use anyhow::Result;
+use sqlx::SqliteConnection;
+use std::collections::HashMap;
+
+use crate::classifier;
+use crate::models::Transaction;
+
+/// Separator between the names in a category path such as `Food:Groceries`,
+/// as in the free-form category strings that categories replaced.
+pub const PATH_SEPARATOR: &str = ":";
+
+/// Returns true if `candidate` is `ancestor` itself or one of its subcategories.
+pub async fn is_in_subtree(
+    conn: &mut SqliteConnection,
+    ancestor: &str,
+    candidate: &str,
+) -> Result<bool> {
+    let found = sqlx::query_scalar::<_, i64>(
+        r#"
+        WITH RECURSIVE ancestors (id, parent_id) AS (
+            SELECT id, parent_id FROM categories WHERE id = ?
+            UNION ALL
+            SELECT c.id, c.parent_id FROM categories c JOIN ancestors a ON c.id = a.parent_id
+        )
+        SELECT COUNT(*) FROM ancestors WHERE id = ?
+        "#,
+    )
+    .bind(candidate)
+    .bind(ancestor)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(found > 0)
+}
+
+/// Merges `source` into `target`: transactions, rules and classifier counts
+/// are re-pointed, subcategories move under `target` and `source` is deleted.
+///
+/// A subcategory whose name already exists under `target` is merged into that
+/// one in turn, so the sibling-name uniqueness rule always holds. The caller
+/// provides the transaction that makes this atomic.
+pub async fn merge(conn: &mut SqliteConnection, source: &str, target: &str) -> Result<()> {
+    let mut pending = vec![(source.to_string(), target.to_string())];
+    let mut merged = Vec::new();
+
+    while let Some((source, target)) = pending.pop() {
+        sqlx::query("UPDATE transactions SET category_id = ? WHERE category_id = ?")
+            .bind(&target)
+            .bind(&source)
+            .execute(&mut *conn)
+            .await?;
+        classifier::merge_categories(conn, &source, &target).await?;
+        sqlx::query("UPDATE rules SET set_category_id = ? WHERE set_category_id = ?")
+            .bind(&target)
+            .bind(&source)
+            .execute(&mut *conn)
+            .await?;
+
+        let children = sqlx::query_as::<_, (String, Option<String>)>(
+            r#"
+            SELECT child.id, existing.id
+            FROM categories child
+            LEFT JOIN categories existing
+                ON existing.parent_id = ? AND existing.name = child.name COLLATE NOCASE
+            WHERE child.parent_id = ?
+            "#,
+        )
+        .bind(&target)
+        .bind(&source)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        for (child, existing) in children {
+            match existing {
+                Some(existing) => pending.push((child, existing)),
+                None => {
+                    sqlx::query("UPDATE categories SET parent_id = ? WHERE id = ?")
+                        .bind(&target)
+                        .bind(&child)
+                        .execute(&mut *conn)
+                        .await?;
+                }
+            }
+        }
+
+        merged.push(source);
+    }
+
+    // Subcategories were pushed after their parents; delete them first
+    for id in merged.iter().rev() {
+        sqlx::query("DELETE FROM categories WHERE id = ?")
+            .bind(id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Sets the `category` path of each transaction from its `category_id`.
+pub async fn attach_paths(
+    conn: &mut SqliteConnection,
+    transactions: &mut [Transaction],
+) -> Result<()> {
+    if transactions.iter().all(|t| t.category_id.is_none()) {
+        return Ok(());
+    }
+    let categories: HashMap<String, (String, Option<String>)> =
+        sqlx::query_as::<_, (String, String, Option<String>)>(
+            "SELECT id, name, parent_id FROM categories",
+        )
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .map(|(id, name, parent_id)| (id, (name, parent_id)))
+        .collect();
+
+    let mut paths: HashMap<&str, String> = HashMap::new();
+    for transaction in transactions.iter_mut() {
+        let category = transaction
+            .category_id
+            .as_deref()
+            .and_then(|id| categories.get_key_value(id));
+        transaction.category = category.map(|(id, _)| {
+            paths
+                .entry(id.as_str())
+                .or_insert_with(|| path_of(&categories, id))
+                .clone()
+        });
+    }
+    Ok(())
+}
+
+/// Joins the names from the root category down to `id`.
+fn path_of(categories: &HashMap<String, (String, Option<String>)>, id: &str) -> String {
+    let mut names = Vec::new();
+    let mut next = Some(id);
+    // Bounded in case of a cycle, which category updates rule out
+    while let Some(id) = next.filter(|_| names.len() < categories.len()) {
+        let Some((name, parent_id)) = categories.get(id) else {
+            break;
+        };
+        names.push(name.as_str());
+        next = parent_id.as_deref();
+    }
+    names.reverse();
+    names.join(PATH_SEPARATOR)
+}
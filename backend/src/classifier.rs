@@ -0,0 +1,312 @@
+use anyhow::Result;
+use sqlx::SqliteConnection;
+use std::collections::{BTreeSet, HashMap};
+
+use crate::models::{CategorySource, Transaction};
+
+const MIN_TOKEN_LEN: usize = 2;
+
+/// Splits description, payee and memo into lowercase word tokens.
+///
+/// Each token counts once per transaction. Pure numbers are dropped since
+/// they are mostly store numbers and references that never repeat.
+pub fn tokenize(transaction: &Transaction) -> Vec<String> {
+    let mut tokens = BTreeSet::new();
+    let fields = [
+        Some(transaction.description.as_str()),
+        transaction.payee.as_deref(),
+        transaction.memo.as_deref(),
+    ];
+    for text in fields.into_iter().flatten() {
+        for word in text.split(|c: char| !c.is_alphanumeric()) {
+            if word.chars().count() >= MIN_TOKEN_LEN && !word.chars().all(|c| c.is_ascii_digit()) {
+                tokens.insert(word.to_lowercase());
+            }
+        }
+    }
+    tokens.into_iter().collect()
+}
+
+/// The category a transaction should contribute to the model, if any.
+fn training_category(transaction: &Transaction) -> Option<&str> {
+    match transaction.category_source {
+        Some(CategorySource::Manual | CategorySource::Rule) => transaction.category_id.as_deref(),
+        _ => None,
+    }
+}
+
+/// Adds (`delta = 1`) or removes (`delta = -1`) one transaction's tokens.
+async fn adjust(
+    conn: &mut SqliteConnection,
+    tokens: &[String],
+    category_id: &str,
+    delta: i64,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO classifier_categories (category_id, documents, tokens) VALUES (?, ?, ?)
+        ON CONFLICT (category_id) DO UPDATE SET
+            documents = documents + excluded.documents,
+            tokens = tokens + excluded.tokens
+        "#,
+    )
+    .bind(category_id)
+    .bind(delta)
+    .bind(delta * tokens.len() as i64)
+    .execute(&mut *conn)
+    .await?;
+
+    for token in tokens {
+        sqlx::query(
+            r#"
+            INSERT INTO classifier_tokens (token, category_id, count) VALUES (?, ?, ?)
+            ON CONFLICT (token, category_id) DO UPDATE SET count = count + excluded.count
+            "#,
+        )
+        .bind(token)
+        .bind(category_id)
+        .bind(delta)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    if delta < 0 {
+        sqlx::query("DELETE FROM classifier_tokens WHERE category_id = ? AND count <= 0")
+            .bind(category_id)
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query("DELETE FROM classifier_categories WHERE category_id = ? AND documents <= 0")
+            .bind(category_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Removes a transaction's contribution, e.g. before it is deleted or its
+/// description changes.
+pub async fn forget(conn: &mut SqliteConnection, transaction: &Transaction) -> Result<()> {
+    if let Some(category_id) = &transaction.trained_category_id {
+        adjust(conn, &tokenize(transaction), category_id, -1).await?;
+        sqlx::query("UPDATE transactions SET trained_category_id = NULL WHERE id = ?")
+            .bind(&transaction.id)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Removes the contributions of every transaction in an account that is about to be deleted.
+pub async fn forget_account(conn: &mut SqliteConnection, account_id: &str) -> Result<()> {
+    let transactions = sqlx::query_as::<_, Transaction>(
+        "SELECT * FROM transactions WHERE account_id = ? AND trained_category_id IS NOT NULL",
+    )
+    .bind(account_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for transaction in &transactions {
+        forget(conn, transaction).await?;
+    }
+    Ok(())
+}
+
+/// Brings the model in line with the current categories by retraining only
+/// the transactions whose category changed since they were last counted.
+///
+/// Returns the number of transactions retrained.
+pub async fn sync_model(conn: &mut SqliteConnection) -> Result<u32> {
+    let stale = sqlx::query_as::<_, Transaction>(
+        r#"
+        SELECT * FROM transactions
+        WHERE trained_category_id IS NOT
+            (CASE WHEN category_source IN ('manual', 'rule') THEN category_id END)
+        "#,
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for transaction in &stale {
+        retrain(conn, transaction).await?;
+    }
+
+    Ok(stale.len() as u32)
+}
+
+/// Brings the model in line with one transaction that was just created or
+/// edited, without looking for other stale transactions as [`sync_model`] does.
+pub async fn train(conn: &mut SqliteConnection, transaction: &mut Transaction) -> Result<()> {
+    if transaction.trained_category_id.as_deref() != training_category(transaction) {
+        transaction.trained_category_id = retrain(conn, transaction).await?;
+    }
+    Ok(())
+}
+
+/// Moves a transaction's counts from the category it was trained on to its
+/// current training category. Returns the new trained category.
+async fn retrain(conn: &mut SqliteConnection, transaction: &Transaction) -> Result<Option<String>> {
+    let tokens = tokenize(transaction);
+    if let Some(old) = &transaction.trained_category_id {
+        adjust(conn, &tokens, old, -1).await?;
+    }
+    let new = training_category(transaction);
+    if let Some(new) = new {
+        adjust(conn, &tokens, new, 1).await?;
+    }
+    sqlx::query("UPDATE transactions SET trained_category_id = ? WHERE id = ?")
+        .bind(new)
+        .bind(&transaction.id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(new.map(str::to_string))
+}
+
+/// Moves all counts from `source` to `target` when two categories are merged.
+pub async fn merge_categories(
+    conn: &mut SqliteConnection,
+    source: &str,
+    target: &str,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO classifier_categories (category_id, documents, tokens)
+        SELECT ?, documents, tokens FROM classifier_categories WHERE category_id = ?
+        ON CONFLICT (category_id) DO UPDATE SET
+            documents = documents + excluded.documents,
+            tokens = tokens + excluded.tokens
+        "#,
+    )
+    .bind(target)
+    .bind(source)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO classifier_tokens (token, category_id, count)
+        SELECT token, ?, count FROM classifier_tokens WHERE category_id = ?
+        ON CONFLICT (token, category_id) DO UPDATE SET count = count + excluded.count
+        "#,
+    )
+    .bind(target)
+    .bind(source)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query("UPDATE transactions SET trained_category_id = ? WHERE trained_category_id = ?")
+        .bind(target)
+        .bind(source)
+        .execute(&mut *conn)
+        .await?;
+
+    // The source category's own rows are removed by ON DELETE CASCADE
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct Suggestion {
+    pub category_id: String,
+    pub confidence: f64,
+}
+
+struct CategoryCounts {
+    id: String,
+    documents: i64,
+    tokens: i64,
+}
+
+/// Multinomial naive Bayes model with Laplace smoothing, loaded from the counts tables.
+pub struct Classifier {
+    categories: Vec<CategoryCounts>,
+    /// Token -> (index into `categories`, count)
+    token_counts: HashMap<String, Vec<(usize, i64)>>,
+    total_documents: i64,
+}
+
+impl Classifier {
+    pub async fn load(conn: &mut SqliteConnection) -> Result<Self> {
+        let categories: Vec<CategoryCounts> = sqlx::query_as::<_, (String, i64, i64)>(
+            "SELECT category_id, documents, tokens FROM classifier_categories WHERE documents > 0",
+        )
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .map(|(id, documents, tokens)| CategoryCounts {
+            id,
+            documents,
+            tokens,
+        })
+        .collect();
+
+        let index: HashMap<&str, usize> = categories
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (c.id.as_str(), i))
+            .collect();
+
+        let mut token_counts: HashMap<String, Vec<(usize, i64)>> = HashMap::new();
+        let rows = sqlx::query_as::<_, (String, String, i64)>(
+            "SELECT token, category_id, count FROM classifier_tokens WHERE count > 0",
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+        for (token, category_id, count) in rows {
+            if let Some(&i) = index.get(category_id.as_str()) {
+                token_counts.entry(token).or_default().push((i, count));
+            }
+        }
+
+        let total_documents = categories.iter().map(|c| c.documents).sum();
+        Ok(Self {
+            categories,
+            token_counts,
+            total_documents,
+        })
+    }
+
+    /// Predicts the most likely category. Returns `None` when fewer than two
+    /// categories have been learned or none of the transaction's tokens are known.
+    pub fn suggest(&self, transaction: &Transaction) -> Option<Suggestion> {
+        if self.categories.len() < 2 {
+            return None;
+        }
+
+        let known: Vec<&Vec<(usize, i64)>> = tokenize(transaction)
+            .iter()
+            .filter_map(|token| self.token_counts.get(token))
+            .collect();
+        if known.is_empty() {
+            return None;
+        }
+
+        let vocabulary = self.token_counts.len() as f64;
+        let mut scores: Vec<f64> = self
+            .categories
+            .iter()
+            .map(|c| {
+                let prior = (c.documents as f64 / self.total_documents as f64).ln();
+                // Every known token starts from the smoothed "unseen" probability
+                prior - known.len() as f64 * (c.tokens as f64 + vocabulary).ln()
+            })
+            .collect();
+        for counts in &known {
+            for &(i, count) in counts.iter() {
+                scores[i] += ((count + 1) as f64).ln();
+            }
+        }
+
+        // Softmax over log scores gives the posterior of each category
+        let max = scores.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let total: f64 = scores.iter().map(|s| (s - max).exp()).sum();
+        let (best, best_score) = scores
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))?;
+
+        Some(Suggestion {
+            category_id: self.categories[best].id.clone(),
+            confidence: (best_score - max).exp() / total,
+        })
+    }
+}
@@ -0,0 +1,201 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use sqlx::SqlitePool;
+use url::Url;
+use uuid::Uuid;
+
+use crate::credentials::{CredentialCipher, Secret};
+use crate::models::Connection;
+use crate::provider;
+use crate::simplefin::SimplefinClient;
+
+/// Stores a new connection with its access URL encrypted. Without a name,
+/// the bridge's host name is used.
+pub async fn insert(
+    pool: &SqlitePool,
+    cipher: &CredentialCipher,
+    name: Option<&str>,
+    access_url: &Secret,
+) -> Result<Connection> {
+    let server_url = SimplefinClient::new(access_url.expose().to_string())?
+        .base_url()
+        .to_string();
+    let name = match name.map(str::trim) {
+        Some(name) if !name.is_empty() => name.to_string(),
+        _ => Url::parse(&server_url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_else(|| "SimpleFin".to_string()),
+    };
+    let id = Uuid::new_v4().to_string();
+
+    // A connection that existed before connections were stored keeps its
+    // high-water mark
+    let connection = sqlx::query_as::<_, Connection>(
+        r#"
+        INSERT INTO connections (id, name, provider, encrypted_access_url, server_url, enabled, last_synced_at, created_at)
+        VALUES (?, ?, ?, ?, ?, TRUE, (SELECT last_synced_at FROM sync_state WHERE connection = ?), ?)
+        RETURNING *
+        "#,
+    )
+    .bind(&id)
+    .bind(name)
+    .bind(provider::SIMPLEFIN)
+    .bind(cipher.encrypt(access_url.expose(), &id)?)
+    .bind(&server_url)
+    .bind(&server_url)
+    .bind(Utc::now())
+    .fetch_one(pool)
+    .await?;
+    Ok(connection)
+}
+
+/// Stores the `SIMPLEFIN_ACCESS_URL` connection the first time the server
+/// starts with it. Once stored it is managed like any other connection, so a
+/// disabled one stays disabled; a deleted one comes back on the next start.
+pub async fn seed(pool: &SqlitePool, cipher: &CredentialCipher, access_url: &Secret) -> Result<()> {
+    let stored = sqlx::query_as::<_, Connection>("SELECT * FROM connections")
+        .fetch_all(pool)
+        .await?;
+    // Encryption is randomized, so stored URLs can only be compared decrypted
+    let mut exists = false;
+    for connection in &stored {
+        match access_url_of(cipher, connection) {
+            Ok(stored) => exists |= stored == *access_url,
+            Err(e) => {
+                // Storing it again would duplicate it if this is the same URL
+                tracing::warn!("Not storing SIMPLEFIN_ACCESS_URL: {:#}", e);
+                return Ok(());
+            }
+        }
+    }
+    if !exists {
+        let connection = insert(pool, cipher, None, access_url).await?;
+        tracing::info!(
+            "Stored SIMPLEFIN_ACCESS_URL as connection {} ({})",
+            connection.name,
+            connection.server_url
+        );
+    }
+    Ok(())
+}
+
+/// Connections to sync, oldest first.
+pub async fn enabled(pool: &SqlitePool) -> Result<Vec<Connection>> {
+    let connections = sqlx::query_as::<_, Connection>(
+        "SELECT * FROM connections WHERE enabled ORDER BY created_at",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(connections)
+}
+
+/// Enabled connections whose credentials the provider rejected, oldest first.
+pub async fn awaiting_reauth(pool: &SqlitePool) -> Result<Vec<Connection>> {
+    let connections = sqlx::query_as::<_, Connection>(
+        "SELECT * FROM connections WHERE enabled AND auth_failed_at IS NOT NULL ORDER BY created_at",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(connections)
+}
+
+/// Marks a connection as needing reauthentication, keeping when it was first
+/// rejected.
+pub async fn record_auth_failure(pool: &SqlitePool, id: &str, error: &str) -> Result<()> {
+    sqlx::query(
+        "UPDATE connections SET auth_failed_at = COALESCE(auth_failed_at, ?), auth_error = ? WHERE id = ?",
+    )
+    .bind(Utc::now())
+    .bind(error)
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Clears the reauthentication mark of connection `id`, or of every
+/// connection without one.
+pub async fn clear_auth_failure(pool: &SqlitePool, id: Option<&str>) -> Result<()> {
+    sqlx::query(
+        "UPDATE connections SET auth_failed_at = NULL, auth_error = NULL \
+         WHERE auth_failed_at IS NOT NULL AND (?1 IS NULL OR id = ?1)",
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Whether there is anything to sync.
+pub async fn any_enabled(pool: &SqlitePool) -> Result<bool> {
+    let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM connections WHERE enabled")
+        .fetch_one(pool)
+        .await?;
+    Ok(count > 0)
+}
+
+/// Decrypts a connection's access URL.
+pub fn access_url_of(cipher: &CredentialCipher, connection: &Connection) -> Result<Secret> {
+    cipher
+        .decrypt(&connection.encrypted_access_url, &connection.id)
+        .with_context(|| {
+            format!(
+                "Cannot read the credentials of connection {}",
+                connection.name
+            )
+        })
+}
+
+/// Encrypts access URLs stored in plaintext before credentials were
+/// encrypted. Returns how many there were.
+pub async fn encrypt_plaintext(pool: &SqlitePool, cipher: &CredentialCipher) -> Result<u64> {
+    let mut tx = pool.begin().await?;
+    let stored =
+        sqlx::query_as::<_, (String, String)>("SELECT id, encrypted_access_url FROM connections")
+            .fetch_all(&mut *tx)
+            .await?;
+
+    let mut encrypted = 0;
+    for (id, access_url) in stored {
+        if CredentialCipher::is_encrypted(&access_url) {
+            continue;
+        }
+        sqlx::query("UPDATE connections SET encrypted_access_url = ? WHERE id = ?")
+            .bind(cipher.encrypt(&access_url, &id)?)
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?;
+        encrypted += 1;
+    }
+    tx.commit().await?;
+    Ok(encrypted)
+}
+
+/// Re-encrypts every access URL with `new`. Nothing changes unless every
+/// one of them can be decrypted with `current`. Returns how many there were.
+pub async fn rotate_key(
+    pool: &SqlitePool,
+    current: &CredentialCipher,
+    new: &CredentialCipher,
+) -> Result<u64> {
+    let mut tx = pool.begin().await?;
+    let stored = sqlx::query_as::<_, Connection>("SELECT * FROM connections")
+        .fetch_all(&mut *tx)
+        .await?;
+
+    for connection in &stored {
+        let access_url = if CredentialCipher::is_encrypted(&connection.encrypted_access_url) {
+            access_url_of(current, connection)?
+        } else {
+            Secret::new(connection.encrypted_access_url.clone())
+        };
+        sqlx::query("UPDATE connections SET encrypted_access_url = ? WHERE id = ?")
+            .bind(new.encrypt(access_url.expose(), &connection.id)?)
+            .bind(&connection.id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(stored.len() as u64)
+}
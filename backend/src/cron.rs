@@ -0,0 +1,375 @@
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+use std::fmt;
+use std::str::FromStr;
+
+/// How far ahead `CronSchedule::next_after` looks before giving up, e.g. for
+/// `0 0 30 2 *` which never matches.
+const SEARCH_DAYS: u64 = 5 * 366;
+
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct CronError(String);
+
+/// A five-field cron expression: minute, hour, day of month, month and day
+/// of week. Fields accept `*`, numbers, ranges (`1-5`), lists (`1,15`) and
+/// steps (`*/15`, `8-18/2`). Day of week runs 0-7 with both 0 and 7 meaning
+/// Sunday. Names such as `MON` and macros such as `@daily` are not supported.
+///
+/// As in classic cron, when both day of month and day of week are
+/// restricted, a day matching either one matches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+/// Parses one field into a bit set of the values it matches.
+fn parse_field(name: &str, field: &str, min: u32, max: u32) -> Result<u64, CronError> {
+    let invalid =
+        |reason: &str| CronError(format!("invalid {} field {:?}: {}", name, field, reason));
+    let number = |text: &str| {
+        text.parse::<u32>()
+            .map_err(|_| invalid("expected a number"))
+            .and_then(|n| {
+                if (min..=max).contains(&n) {
+                    Ok(n)
+                } else {
+                    Err(invalid(&format!(
+                        "values must be between {} and {}",
+                        min, max
+                    )))
+                }
+            })
+    };
+
+    let mut set = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step = step
+                    .parse::<u32>()
+                    .map_err(|_| invalid("expected a step number"))?;
+                if step == 0 {
+                    return Err(invalid("step must be at least 1"));
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (number(start)?, number(end)?),
+                // `5/15` means from 5 to the end in steps of 15
+                None if step > 1 => (number(range)?, max),
+                None => {
+                    let n = number(range)?;
+                    (n, n)
+                }
+            },
+        };
+        if start > end {
+            return Err(invalid("range start is after its end"));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
+
+fn contains(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<Self, CronError> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(CronError(format!(
+                "expected 5 fields (minute hour day month weekday), found {}",
+                fields.len()
+            )));
+        };
+
+        let mut weekdays = parse_field("day of week", weekday, 0, 7)?;
+        // Sunday is both 0 and 7
+        if contains(weekdays, 7) {
+            weekdays |= 1;
+        }
+
+        Ok(Self {
+            expression: fields.join(" "),
+            minutes: parse_field("minute", minute, 0, 59)?,
+            hours: parse_field("hour", hour, 0, 23)?,
+            days: parse_field("day of month", day, 1, 31)?,
+            months: parse_field("month", month, 1, 12)?,
+            weekdays,
+            days_restricted: day != "*",
+            weekdays_restricted: weekday != "*",
+        })
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        let day = contains(self.days, date.day());
+        let weekday = contains(self.weekdays, date.weekday().num_days_from_sunday());
+        match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => day || weekday,
+            (true, false) => day,
+            (false, true) => weekday,
+            (false, false) => true,
+        }
+    }
+
+    /// The first matching minute strictly after `after`, in `after`'s time
+    /// zone. Local times skipped by a daylight-saving change never match;
+    /// repeated ones match the first time they occur.
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let timezone = after.timezone();
+        let local = after.naive_local();
+        let mut candidate = local.date().and_hms_opt(local.hour(), local.minute(), 0)?
+            + chrono::Duration::minutes(1);
+        let limit = local.date().checked_add_days(Days::new(SEARCH_DAYS))?;
+
+        while candidate.date() < limit {
+            let date = candidate.date();
+            if !contains(self.months, date.month()) {
+                let first_of_next = date
+                    .with_day(1)?
+                    .checked_add_months(chrono::Months::new(1))?;
+                candidate = first_of_next.and_hms_opt(0, 0, 0)?;
+                continue;
+            }
+            if !self.matches_day(date) {
+                candidate = date.checked_add_days(Days::new(1))?.and_hms_opt(0, 0, 0)?;
+                continue;
+            }
+            if !contains(self.hours, candidate.hour()) {
+                candidate = next_hour(candidate);
+                continue;
+            }
+            if !contains(self.minutes, candidate.minute()) {
+                candidate += chrono::Duration::minutes(1);
+                continue;
+            }
+            if let Some(time) = timezone.from_local_datetime(&candidate).earliest() {
+                return Some(time);
+            }
+            candidate += chrono::Duration::minutes(1);
+        }
+        None
+    }
+}
+
+fn next_hour(time: NaiveDateTime) -> NaiveDateTime {
+    let hour_start = time.date().and_hms_opt(time.hour(), 0, 0).unwrap_or(time);
+    hour_start + chrono::Duration::hours(1)
+}
+
+impl FromStr for CronSchedule {
+    type Err = CronError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use chrono::{FixedOffset, LocalResult, NaiveTime, Offset, Utc};
+
+    /// US Eastern time around the 2024 spring-forward change: clocks jump
+    /// from 02:00 to 03:00 on 2024-03-10, so 02:00-02:59 never happens.
+    #[derive(Debug, Clone, Copy)]
+    pub(crate) struct SpringForward;
+
+    impl SpringForward {
+        fn change_local() -> NaiveDateTime {
+            date(2024, 3, 10).and_hms_opt(2, 0, 0).unwrap()
+        }
+
+        fn standard() -> FixedOffset {
+            FixedOffset::west_opt(5 * 3600).unwrap()
+        }
+
+        fn daylight() -> FixedOffset {
+            FixedOffset::west_opt(4 * 3600).unwrap()
+        }
+    }
+
+    impl TimeZone for SpringForward {
+        type Offset = FixedOffset;
+
+        fn from_offset(_: &FixedOffset) -> Self {
+            SpringForward
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            let change = Self::change_local();
+            if *local < change {
+                LocalResult::Single(Self::standard())
+            } else if *local < change + chrono::Duration::hours(1) {
+                LocalResult::None
+            } else {
+                LocalResult::Single(Self::daylight())
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            if *utc < Self::change_local() - Self::standard().fix() {
+                Self::standard()
+            } else {
+                Self::daylight()
+            }
+        }
+    }
+
+    pub(crate) fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn utc(day: NaiveDate, hour: u32, minute: u32) -> DateTime<Utc> {
+        day.and_time(NaiveTime::from_hms_opt(hour, minute, 0).unwrap())
+            .and_utc()
+    }
+
+    fn next(expression: &str, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        CronSchedule::parse(expression).unwrap().next_after(&after)
+    }
+
+    #[test]
+    fn parses_fields_and_normalizes_whitespace() {
+        let schedule = CronSchedule::parse("  */15   8-18/2 1,15 * 1-5 ").unwrap();
+        assert_eq!(schedule.to_string(), "*/15 8-18/2 1,15 * 1-5");
+        assert_eq!(schedule.minutes, 1 | 1 << 15 | 1 << 30 | 1 << 45);
+        assert_eq!(
+            schedule.hours,
+            [8, 10, 12, 14, 16, 18].iter().map(|h| 1 << h).sum::<u64>()
+        );
+        assert!(schedule.days_restricted && schedule.weekdays_restricted);
+
+        // `5/20` runs from 5 to the end of the range
+        let schedule: CronSchedule = "5/20 * * * *".parse().unwrap();
+        assert_eq!(schedule.minutes, 1 << 5 | 1 << 25 | 1 << 45);
+        // Both 0 and 7 are Sunday
+        let schedule = CronSchedule::parse("0 0 * * 7").unwrap();
+        assert!(contains(schedule.weekdays, 0));
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        for (expression, message) in [
+            ("* * * *", "expected 5 fields"),
+            ("60 * * * *", "values must be between 0 and 59"),
+            ("* 24 * * *", "values must be between 0 and 23"),
+            ("* * 0 * *", "values must be between 1 and 31"),
+            ("*/0 * * * *", "step must be at least 1"),
+            ("*/x * * * *", "expected a step number"),
+            ("30-10 * * * *", "range start is after its end"),
+            ("MON * * * *", "expected a number"),
+        ] {
+            let err = CronSchedule::parse(expression).unwrap_err();
+            assert!(err.to_string().contains(message), "{expression:?}: {err}");
+        }
+    }
+
+    #[test]
+    fn next_after_is_strictly_later() {
+        let day = date(2024, 1, 10);
+        assert_eq!(
+            next("*/15 * * * *", utc(day, 10, 7)),
+            Some(utc(day, 10, 15))
+        );
+        assert_eq!(
+            next("*/15 * * * *", utc(day, 10, 15)),
+            Some(utc(day, 10, 30))
+        );
+        // Seconds past the minute do not count as the minute itself
+        let just_after = utc(day, 10, 15) + chrono::Duration::seconds(30);
+        assert_eq!(next("*/15 * * * *", just_after), Some(utc(day, 10, 30)));
+        assert_eq!(
+            next("*/15 * * * *", utc(day, 23, 50)),
+            Some(utc(date(2024, 1, 11), 0, 0))
+        );
+    }
+
+    #[test]
+    fn next_after_skips_to_matching_days_and_months() {
+        // 2024-01-12 is a Friday; weekdays resume on Monday
+        assert_eq!(
+            next("0 9 * * 1-5", utc(date(2024, 1, 12), 10, 0)),
+            Some(utc(date(2024, 1, 15), 9, 0))
+        );
+        assert_eq!(
+            next("0 0 1 6 *", utc(date(2024, 1, 12), 10, 0)),
+            Some(utc(date(2024, 6, 1), 0, 0))
+        );
+        // Day of month and day of week restricted: either one matches, so
+        // the 13th (a Saturday) and Friday the 19th both run
+        assert_eq!(
+            next("0 0 13 * 5", utc(date(2024, 1, 12), 10, 0)),
+            Some(utc(date(2024, 1, 13), 0, 0))
+        );
+        assert_eq!(
+            next("0 0 13 * 5", utc(date(2024, 1, 13), 10, 0)),
+            Some(utc(date(2024, 1, 19), 0, 0))
+        );
+        // February 29th only exists in leap years
+        assert_eq!(
+            next("0 0 29 2 *", utc(date(2024, 3, 1), 0, 0)),
+            Some(utc(date(2028, 2, 29), 0, 0))
+        );
+    }
+
+    #[test]
+    fn next_after_gives_up_on_impossible_dates() {
+        assert_eq!(next("0 0 30 2 *", utc(date(2024, 1, 1), 0, 0)), None);
+    }
+
+    #[test]
+    fn next_after_skips_times_lost_to_daylight_saving() {
+        let schedule = CronSchedule::parse("30 2 * * *").unwrap();
+        let after = SpringForward
+            .from_local_datetime(&date(2024, 3, 9).and_hms_opt(12, 0, 0).unwrap())
+            .unwrap();
+        let next = schedule.next_after(&after).unwrap();
+        assert_eq!(
+            next.naive_local(),
+            date(2024, 3, 11).and_hms_opt(2, 30, 0).unwrap()
+        );
+        assert_eq!(next.offset().fix(), SpringForward::daylight());
+
+        // Hourly runs go straight from 01:00 to 03:00 on the day of the change
+        let schedule = CronSchedule::parse("0 * * * *").unwrap();
+        let after = SpringForward
+            .from_local_datetime(&date(2024, 3, 10).and_hms_opt(1, 0, 0).unwrap())
+            .unwrap();
+        let next = schedule.next_after(&after).unwrap();
+        assert_eq!(next - after, chrono::Duration::hours(1));
+        assert_eq!(
+            next.naive_local(),
+            date(2024, 3, 10).and_hms_opt(3, 0, 0).unwrap()
+        );
+    }
+}
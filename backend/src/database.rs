@@ -1,15 +1,57 @@
-use sqlx::{SqlitePool, sqlite::SqliteConnectOptions};
+use sqlx::{SqlitePool, sqlite::{SqliteConnectOptions, SqlitePoolOptions}};
 use std::str::FromStr;
 use anyhow::Result;
 
 pub async fn create_pool(database_url: &str) -> Result<SqlitePool> {
     let options = SqliteConnectOptions::from_str(database_url)?
         .create_if_missing(true);
-    
-    let pool = SqlitePool::connect_with(options).await?;
-    
+
+    // An in-memory database is dropped with its last connection, and
+    // connections sharing one lock each other out of whole tables; keep a
+    // single connection open for the life of the pool
+    let pool_options = if database_url.contains(":memory:") || database_url.contains("mode=memory") {
+        SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+    } else {
+        SqlitePoolOptions::new()
+    };
+    let pool = pool_options.connect_with(options).await?;
+
+    keep_unsplit_legacy_categories(&pool).await?;
     // Run migrations
     sqlx::migrate!("./migrations").run(&pool).await?;
-    
+
     Ok(pool)
-}
\ No newline at end of file
+}
+
+/// Migration 009 splits the legacy `transactions.category` strings into
+/// categories and drops the column, but strings with no name between the
+/// separators, such as ":", yield no category. Until 009 has run, copy them
+/// into a table that migration 022 turns into top-level categories.
+async fn keep_unsplit_legacy_categories(pool: &SqlitePool) -> Result<()> {
+    let has_legacy_column: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM pragma_table_info('transactions') WHERE name = 'category')",
+    )
+    .fetch_one(pool)
+    .await?;
+    if !has_legacy_column {
+        return Ok(());
+    }
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("DROP TABLE IF EXISTS legacy_unsplit_categories")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        "CREATE TABLE legacy_unsplit_categories AS
+         SELECT id AS transaction_id, category FROM transactions
+         WHERE category IS NOT NULL AND TRIM(category) <> ''
+           AND TRIM(REPLACE(category, ':', '')) = ''",
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
@@ -0,0 +1,157 @@
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::credentials::Secret;
+use crate::models::ApiResponse;
+
+/// Result type returned by every API handler.
+pub type ApiResult<T> = Result<Json<ApiResponse<T>>, AppError>;
+
+/// A problem with one field of the request.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Error payload carried in `ApiResponse.error`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiErrorBody {
+    /// Machine-readable error code, e.g. `not_found` or `validation_failed`
+    pub code: String,
+    /// Human-readable description
+    pub message: String,
+    /// Field-level problems, present for validation failures
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<FieldError>,
+    /// Access URL of a claimed SimpleFin setup token that could not be
+    /// stored. The token cannot be claimed again, so this is the only copy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_url: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("{0} not found")]
+    NotFound(&'static str),
+
+    #[error("{0}")]
+    BadRequest(String),
+
+    #[error("request validation failed")]
+    Validation(Vec<FieldError>),
+
+    #[error("{0}")]
+    Conflict(String),
+
+    #[error("{0}")]
+    ServiceUnavailable(String),
+
+    /// A setup token was claimed but its access URL could not be stored; the
+    /// response carries the URL so it is not lost.
+    #[error("{message}")]
+    AccessUrlNotStored { message: String, access_url: Secret },
+
+    #[error("database error: {0}")]
+    Database(sqlx::Error),
+
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+impl AppError {
+    /// Shorthand for a validation failure on a single field.
+    pub fn invalid_field(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::Validation(vec![FieldError::new(field, message)])
+    }
+
+    /// Maps a database error, attributing a foreign-key violation to `field`.
+    ///
+    /// SQLite does not report which constraint failed, so handlers name the
+    /// field they know the insert or update depends on.
+    pub fn foreign_key(field: &'static str) -> impl FnOnce(sqlx::Error) -> AppError {
+        move |err| match &err {
+            sqlx::Error::Database(db) if db.is_foreign_key_violation() => {
+                Self::invalid_field(field, "references a record that does not exist")
+            }
+            _ => err.into(),
+        }
+    }
+
+    fn status_and_code(&self) -> (StatusCode, &'static str) {
+        match self {
+            Self::NotFound(_) => (StatusCode::NOT_FOUND, "not_found"),
+            Self::BadRequest(_) => (StatusCode::BAD_REQUEST, "bad_request"),
+            Self::Validation(_) => (StatusCode::UNPROCESSABLE_ENTITY, "validation_failed"),
+            Self::Conflict(_) => (StatusCode::CONFLICT, "conflict"),
+            Self::ServiceUnavailable(_) => (StatusCode::SERVICE_UNAVAILABLE, "service_unavailable"),
+            Self::AccessUrlNotStored { .. } => (StatusCode::INTERNAL_SERVER_ERROR, "access_url_not_stored"),
+            Self::Database(_) => (StatusCode::INTERNAL_SERVER_ERROR, "database_error"),
+            Self::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, "internal_error"),
+        }
+    }
+}
+
+impl From<sqlx::Error> for AppError {
+    /// Constraint violations are the client's fault and map to 4xx responses;
+    /// everything else is an internal database failure. Handlers that know which
+    /// field a foreign key belongs to use [`AppError::foreign_key`] instead.
+    fn from(err: sqlx::Error) -> Self {
+        if let sqlx::Error::Database(db) = &err {
+            if db.is_unique_violation() {
+                return Self::Conflict("a record with the same unique values already exists".to_string());
+            }
+            if db.is_foreign_key_violation() {
+                return Self::BadRequest("the request references a record that does not exist".to_string());
+            }
+        }
+        Self::Database(err)
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let (status, code) = self.status_and_code();
+
+        let message = match &self {
+            Self::Database(e) => {
+                tracing::error!("Database error: {:?}", e);
+                "An internal database error occurred".to_string()
+            }
+            Self::Internal(e) => {
+                tracing::error!("Internal error: {:#}", e);
+                "An internal server error occurred".to_string()
+            }
+            other => other.to_string(),
+        };
+
+        let (details, access_url) = match self {
+            Self::Validation(details) => (details, None),
+            Self::AccessUrlNotStored { access_url, .. } => (Vec::new(), Some(access_url)),
+            _ => (Vec::new(), None),
+        };
+
+        let body = ApiResponse::<()>::error(ApiErrorBody {
+            code: code.to_string(),
+            message,
+            details,
+            access_url: access_url.map(|url| url.expose().to_string()),
+        });
+
+        (status, Json(body)).into_response()
+    }
+}
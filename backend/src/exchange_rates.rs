@@ -0,0 +1,246 @@
+use anyhow::{Result, anyhow};
+use chrono::NaiveDate;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::error::FieldError;
+use crate::models::{ConvertedAmount, ExchangeRate, ExchangeRateInput};
+use crate::money::{Money, normalize_currency};
+
+/// A rate picked for a conversion, possibly the inverse of a stored pair.
+#[derive(Debug, Clone, Copy)]
+pub struct AppliedRate {
+    pub rate: f64,
+    pub rate_date: NaiveDate,
+}
+
+/// Finds the rate for `on`, falling back to the nearest earlier date.
+///
+/// Both directions of the pair are considered, so storing EUR→USD is enough
+/// to convert USD amounts into EUR as well.
+pub async fn find_rate(
+    pool: &SqlitePool,
+    from: &str,
+    to: &str,
+    on: NaiveDate,
+) -> Result<Option<AppliedRate>> {
+    if from == to {
+        return Ok(Some(AppliedRate {
+            rate: 1.0,
+            rate_date: on,
+        }));
+    }
+
+    let row = sqlx::query_as::<_, (f64, NaiveDate)>(
+        r#"
+        SELECT rate, rate_date FROM (
+            SELECT rate, rate_date, 0 AS inverted FROM exchange_rates
+            WHERE from_currency = ? AND to_currency = ? AND rate_date <= ?
+            UNION ALL
+            SELECT 1.0 / rate, rate_date, 1 AS inverted FROM exchange_rates
+            WHERE from_currency = ? AND to_currency = ? AND rate_date <= ?
+        )
+        ORDER BY rate_date DESC, inverted
+        LIMIT 1
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .bind(on)
+    .bind(to)
+    .bind(from)
+    .bind(on)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|(rate, rate_date)| AppliedRate { rate, rate_date }))
+}
+
+/// Converts amounts into a single base currency, caching rate lookups.
+pub struct CurrencyConverter {
+    pool: SqlitePool,
+    base_currency: String,
+    cache: HashMap<(String, NaiveDate), Option<AppliedRate>>,
+}
+
+impl CurrencyConverter {
+    pub fn new(pool: SqlitePool, base_currency: &str) -> Self {
+        Self {
+            pool,
+            base_currency: normalize_currency(base_currency),
+            cache: HashMap::new(),
+        }
+    }
+
+    pub fn base_currency(&self) -> &str {
+        &self.base_currency
+    }
+
+    pub async fn convert(
+        &mut self,
+        amount: Money,
+        currency: &str,
+        on: NaiveDate,
+    ) -> Result<Option<ConvertedAmount>> {
+        let key = (currency.to_string(), on);
+        let applied = match self.cache.get(&key) {
+            Some(applied) => *applied,
+            None => {
+                let applied = find_rate(&self.pool, currency, &self.base_currency, on).await?;
+                self.cache.insert(key, applied);
+                applied
+            }
+        };
+
+        let Some(applied) = applied else {
+            return Ok(None);
+        };
+        Ok(Some(ConvertedAmount {
+            currency: self.base_currency.clone(),
+            amount: amount.convert(applied.rate, currency, &self.base_currency)?,
+            rate: applied.rate,
+            rate_date: applied.rate_date,
+        }))
+    }
+}
+
+pub fn validate_rate(input: &ExchangeRateInput) -> Result<ExchangeRateInput> {
+    let from_currency = normalize_currency(&input.from_currency);
+    let to_currency = normalize_currency(&input.to_currency);
+
+    if from_currency.is_empty() || to_currency.is_empty() {
+        return Err(anyhow!("currency codes must not be empty"));
+    }
+    if from_currency == to_currency {
+        return Err(anyhow!("from_currency and to_currency must differ"));
+    }
+    if !input.rate.is_finite() || input.rate <= 0.0 {
+        return Err(anyhow!("rate must be a positive number"));
+    }
+
+    Ok(ExchangeRateInput {
+        from_currency,
+        to_currency,
+        rate: input.rate,
+        rate_date: input.rate_date,
+    })
+}
+
+/// Validates a batch of rates, reporting every invalid entry by its index.
+pub fn validate_rates(
+    inputs: &[ExchangeRateInput],
+) -> std::result::Result<Vec<ExchangeRateInput>, Vec<FieldError>> {
+    let mut rates = Vec::with_capacity(inputs.len());
+    let mut errors = Vec::new();
+
+    for (index, input) in inputs.iter().enumerate() {
+        match validate_rate(input) {
+            Ok(rate) => rates.push(rate),
+            Err(e) => errors.push(FieldError::new(format!("[{}]", index), e.to_string())),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(rates)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Parses `date,from_currency,to_currency,rate` rows. A header row is optional.
+///
+/// Returns every malformed line rather than stopping at the first one.
+pub fn parse_csv(body: &str) -> std::result::Result<Vec<ExchangeRateInput>, Vec<FieldError>> {
+    let mut rates = Vec::new();
+    let mut errors = Vec::new();
+
+    for (index, line) in body.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || (index == 0 && line.to_ascii_lowercase().starts_with("date")) {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [date, from, to, rate] = fields.as_slice() else {
+            errors.push(FieldError::new(
+                format!("line {}", line_number),
+                format!("expected 4 columns, found {}", fields.len()),
+            ));
+            continue;
+        };
+
+        let rate_date = match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+            Ok(date) => date,
+            Err(_) => {
+                errors.push(FieldError::new(
+                    format!("line {}", line_number),
+                    format!("invalid date {:?}", date),
+                ));
+                continue;
+            }
+        };
+        let rate = match rate.parse::<f64>() {
+            Ok(rate) => rate,
+            Err(_) => {
+                errors.push(FieldError::new(
+                    format!("line {}", line_number),
+                    format!("invalid rate {:?}", rate),
+                ));
+                continue;
+            }
+        };
+
+        let input = ExchangeRateInput {
+            from_currency: from.to_string(),
+            to_currency: to.to_string(),
+            rate,
+            rate_date,
+        };
+        match validate_rate(&input) {
+            Ok(input) => rates.push(input),
+            Err(e) => errors.push(FieldError::new(format!("line {}", line_number), e.to_string())),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(rates)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Inserts rates, replacing any existing rate for the same pair and date.
+pub async fn upsert_rates(
+    pool: &SqlitePool,
+    rates: &[ExchangeRateInput],
+    source: &str,
+) -> Result<Vec<ExchangeRate>> {
+    let mut tx = pool.begin().await?;
+    let mut saved = Vec::with_capacity(rates.len());
+
+    for rate in rates {
+        let row = sqlx::query_as::<_, ExchangeRate>(
+            r#"
+            INSERT INTO exchange_rates (id, from_currency, to_currency, rate, rate_date, source)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT (from_currency, to_currency, rate_date)
+            DO UPDATE SET rate = excluded.rate, source = excluded.source
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&rate.from_currency)
+        .bind(&rate.to_currency)
+        .bind(rate.rate)
+        .bind(rate.rate_date)
+        .bind(source)
+        .fetch_one(&mut *tx)
+        .await?;
+        saved.push(row);
+    }
+
+    tx.commit().await?;
+    Ok(saved)
+}
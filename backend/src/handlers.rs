@@ -5,15 +5,46 @@ use axum::{
 };
 use uuid::Uuid;
 use chrono::Utc;
+use anyhow::Context;
 
+use crate::error::{ApiResult, AppError, FieldError};
+use crate::extract::{ApiJson, ApiQuery};
 use crate::models::*;
+use crate::money::{self, normalize_currency};
 use crate::sync::SyncStats;
+use crate::sync_runs::SyncTrigger;
 use crate::app_state::AppState;
+use crate::categories::attach_paths;
+use crate::classifier;
+use crate::merchants::MerchantMatcher;
+use crate::reports;
+use crate::transaction_query::{self, TransactionCursor};
+use crate::validation::{Validate, ValidateIn};
+
+mod backfill;
+mod categories;
+mod connections;
+mod exchange_rates;
+mod merchants;
+mod rules;
+mod schedule;
+mod suggestions;
+mod sync_runs;
+pub use backfill::*;
+pub use categories::*;
+pub use connections::*;
+pub use exchange_rates::*;
+pub use merchants::*;
+pub use rules::*;
+pub use schedule::*;
+pub use suggestions::*;
+pub use sync_runs::*;
 
 /// Get all accounts
 #[utoipa::path(
     get,
     path = "/api/accounts",
+    params(BaseCurrencyQuery),
     responses(
         (status = 200, description = "List of all accounts", body = Vec<Account>),
         (status = 500, description = "Internal server error")
@@ -21,11 +52,18 @@ use crate::app_state::AppState;
 )]
 pub async fn get_accounts(
     State(app_state): State<AppState>,
-) -> Result<Json<ApiResponse<Vec<Account>>>, StatusCode> {
-    let accounts = sqlx::query_as::<_, Account>("SELECT * FROM accounts ORDER BY created_at DESC")
+    ApiQuery(query): ApiQuery<BaseCurrencyQuery>,
+) -> ApiResult<Vec<Account>> {
+    let mut accounts = sqlx::query_as::<_, Account>("SELECT * FROM accounts ORDER BY created_at DESC")
         .fetch_all(&app_state.pool)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .await?;
+
+    let mut converter = app_state.converter(query.base_currency.as_deref());
+    for account in &mut accounts {
+        account.converted_balance = converter
+            .convert(account.balance, &account.currency, account.last_updated.date_naive())
+            .await?;
+    }
 
     Ok(Json(ApiResponse::success(accounts)))
 }
@@ -37,34 +75,38 @@ pub async fn get_accounts(
     request_body = CreateAccountRequest,
     responses(
         (status = 201, description = "Account created successfully", body = Account),
-        (status = 400, description = "Invalid request data"),
+        (status = 400, description = "Malformed request body or unknown account type"),
+        (status = 422, description = "Invalid request data; every invalid field is listed"),
         (status = 500, description = "Internal server error")
     )
 )]
 pub async fn create_account(
     State(app_state): State<AppState>,
-    Json(payload): Json<CreateAccountRequest>,
-) -> Result<Json<ApiResponse<Account>>, StatusCode> {
+    ApiJson(payload): ApiJson<CreateAccountRequest>,
+) -> ApiResult<Account> {
+    payload.validate()?;
+
     let id = Uuid::new_v4().to_string();
     let now = Utc::now();
+    let currency = normalize_currency(&payload.currency);
 
     let account = sqlx::query_as::<_, Account>(
         r#"
-        INSERT INTO accounts (id, name, institution, account_type, balance, last_updated, created_at)
-        VALUES (?, ?, ?, ?, ?, ?, ?)
+        INSERT INTO accounts (id, name, institution, account_type, balance, currency, last_updated, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
         RETURNING *
         "#,
     )
     .bind(&id)
-    .bind(&payload.name)
-    .bind(&payload.institution)
-    .bind(&payload.account_type)
-    .bind(payload.balance)
+    .bind(payload.name.trim())
+    .bind(payload.institution.trim())
+    .bind(payload.account_type.value())
+    .bind(payload.balance.value_in(&currency))
+    .bind(&currency)
     .bind(now)
     .bind(now)
     .fetch_one(&app_state.pool)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    .await?;
 
     Ok(Json(ApiResponse::success(account)))
 }
@@ -85,17 +127,212 @@ pub async fn create_account(
 pub async fn get_account(
     State(app_state): State<AppState>,
     Path(id): Path<String>,
-) -> Result<Json<ApiResponse<Account>>, StatusCode> {
+) -> ApiResult<Account> {
     let account = sqlx::query_as::<_, Account>("SELECT * FROM accounts WHERE id = ?")
         .bind(&id)
         .fetch_optional(&app_state.pool)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .await?;
 
     match account {
         Some(account) => Ok(Json(ApiResponse::success(account))),
-        None => Err(StatusCode::NOT_FOUND),
+        None => Err(AppError::NotFound("Account")),
+    }
+}
+
+/// Update an account
+#[utoipa::path(
+    patch,
+    path = "/api/accounts/{id}",
+    params(
+        ("id" = String, Path, description = "Account ID")
+    ),
+    request_body = UpdateAccountRequest,
+    responses(
+        (status = 200, description = "Account updated", body = Account),
+        (status = 400, description = "Malformed request body"),
+        (status = 404, description = "Account not found"),
+        (status = 422, description = "Invalid request data"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_account(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+    ApiJson(payload): ApiJson<UpdateAccountRequest>,
+) -> ApiResult<Account> {
+    let mut tx = app_state.pool.begin().await?;
+
+    let mut account = sqlx::query_as::<_, Account>("SELECT * FROM accounts WHERE id = ?")
+        .bind(&id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(AppError::NotFound("Account"))?;
+
+    payload.validate_in(&account.currency)?;
+
+    let currency = payload.currency.as_deref().map(normalize_currency);
+    let account_type = payload.account_type.value();
+    let balance = payload
+        .balance
+        .value_in(currency.as_deref().unwrap_or(&account.currency));
+
+    let is_linked = account.external_id.is_some();
+    if is_linked {
+        // These fields are owned by the provider and would be overwritten on the next sync
+        let provider_fields = [
+            ("institution", payload.institution.is_some()),
+            ("balance", balance.is_some()),
+            ("currency", payload.currency.is_some()),
+        ];
+        let errors: Vec<FieldError> = provider_fields
+            .iter()
+            .filter(|(_, present)| *present)
+            .map(|(field, _)| FieldError::new(*field, "cannot be edited on a linked account"))
+            .collect();
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors));
+        }
+    }
+
+    if let Some(name) = &payload.name {
+        let name = name.trim();
+        if !is_linked {
+            account.name = name.to_string();
+        } else if name == account.name {
+            // Renaming back to the provider's name clears the override
+            account.display_name = None;
+        } else {
+            account.display_name = Some(name.to_string());
+        }
+    }
+    if let Some(institution) = &payload.institution {
+        account.institution = institution.trim().to_string();
+    }
+    if let Some(account_type) = account_type {
+        account.account_type = account_type;
+    }
+    if let Some(currency) = currency
+        && currency != account.currency
+    {
+        // Transactions always carry their account's currency. Stored amounts
+        // keep their value in major units, in the new currency's minor units.
+        let overflow = || AppError::invalid_field("currency", "would overflow the account's amounts");
+        if let Some(limit) = money::rescale_limit(&account.currency, &currency) {
+            // SQLite would store an overflowing product as a REAL
+            let overflows: bool = sqlx::query_scalar(
+                "SELECT EXISTS (SELECT 1 FROM transactions WHERE account_id = ?1 AND (amount > ?2 OR amount < -?2)) \
+                 OR EXISTS (SELECT 1 FROM balances_history WHERE account_id = ?1 AND (balance > ?2 OR balance < -?2))",
+            )
+            .bind(&account.id)
+            .bind(limit)
+            .fetch_one(&mut *tx)
+            .await?;
+            if overflows {
+                return Err(overflow());
+            }
+        }
+        sqlx::query(&format!(
+            "UPDATE transactions SET currency = ?, amount = {} WHERE account_id = ?",
+            money::sql_rescale("amount", &account.currency, &currency)
+        ))
+        .bind(&currency)
+        .bind(&account.id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(&format!(
+            "UPDATE balances_history SET balance = {} WHERE account_id = ?",
+            money::sql_rescale("balance", &account.currency, &currency)
+        ))
+        .bind(&account.id)
+        .execute(&mut *tx)
+        .await?;
+        account.balance = account
+            .balance
+            .rescale(&account.currency, &currency)
+            .ok_or_else(overflow)?;
+        account.currency = currency;
+    }
+
+    let now = Utc::now();
+    if let Some(balance) = balance
+        && balance != account.balance
+    {
+        account.balance = balance;
+        account.last_updated = now;
+
+        sqlx::query(
+            "INSERT INTO balances_history (id, account_id, balance, timestamp) VALUES (?, ?, ?, ?)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&account.id)
+        .bind(balance)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
     }
+
+    let account = sqlx::query_as::<_, Account>(
+        r#"
+        UPDATE accounts SET
+            name = ?, display_name = ?, institution = ?, account_type = ?,
+            balance = ?, currency = ?, last_updated = ?
+        WHERE id = ?
+        RETURNING *
+        "#,
+    )
+    .bind(&account.name)
+    .bind(&account.display_name)
+    .bind(&account.institution)
+    .bind(account.account_type)
+    .bind(account.balance)
+    .bind(&account.currency)
+    .bind(account.last_updated)
+    .bind(&account.id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success(account)))
+}
+
+/// Delete an account along with its transactions and balance history
+///
+/// A linked account that the provider still reports will be recreated by
+/// the next sync.
+#[utoipa::path(
+    delete,
+    path = "/api/accounts/{id}",
+    params(
+        ("id" = String, Path, description = "Account ID")
+    ),
+    responses(
+        (status = 204, description = "Account deleted"),
+        (status = 404, description = "Account not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_account(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let mut tx = app_state.pool.begin().await?;
+
+    classifier::forget_account(&mut tx, &id).await?;
+
+    // Transactions and balance history are removed by ON DELETE CASCADE
+    let result = sqlx::query("DELETE FROM accounts WHERE id = ?")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Account"));
+    }
+
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
 }
 
 /// Get transactions for an account
@@ -114,18 +351,52 @@ pub async fn get_account(
 pub async fn get_account_transactions(
     State(app_state): State<AppState>,
     Path(id): Path<String>,
-) -> Result<Json<ApiResponse<Vec<Transaction>>>, StatusCode> {
-    let transactions = sqlx::query_as::<_, Transaction>(
+) -> ApiResult<Vec<Transaction>> {
+    let mut conn = app_state.pool.acquire().await?;
+    let mut transactions = sqlx::query_as::<_, Transaction>(
         "SELECT * FROM transactions WHERE account_id = ? ORDER BY transaction_date DESC, created_at DESC"
     )
     .bind(&id)
-    .fetch_all(&app_state.pool)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    .fetch_all(&mut *conn)
+    .await?;
+    attach_paths(&mut conn, &mut transactions).await?;
 
     Ok(Json(ApiResponse::success(transactions)))
 }
 
+/// Get the current holdings of an investment account
+#[utoipa::path(
+    get,
+    path = "/api/accounts/{id}/holdings",
+    params(
+        ("id" = String, Path, description = "Account ID")
+    ),
+    responses(
+        (status = 200, description = "Holdings as of the last sync, largest first", body = Vec<Holding>),
+        (status = 404, description = "Account not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_account_holdings(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<Vec<Holding>> {
+    sqlx::query_scalar::<_, String>("SELECT id FROM accounts WHERE id = ?")
+        .bind(&id)
+        .fetch_optional(&app_state.pool)
+        .await?
+        .ok_or(AppError::NotFound("Account"))?;
+
+    let holdings = sqlx::query_as::<_, Holding>(
+        "SELECT * FROM holdings WHERE account_id = ? ORDER BY market_value DESC"
+    )
+    .bind(&id)
+    .fetch_all(&app_state.pool)
+    .await?;
+
+    Ok(Json(ApiResponse::success(holdings)))
+}
+
 /// Create a new transaction
 #[utoipa::path(
     post,
@@ -133,58 +404,369 @@ pub async fn get_account_transactions(
     request_body = CreateTransactionRequest,
     responses(
         (status = 201, description = "Transaction created successfully", body = Transaction),
-        (status = 400, description = "Invalid request data"),
+        (status = 400, description = "Malformed request body"),
+        (status = 422, description = "Invalid request data, e.g. an unknown account_id; every invalid field is listed"),
         (status = 500, description = "Internal server error")
     )
 )]
 pub async fn create_transaction(
     State(app_state): State<AppState>,
-    Json(payload): Json<CreateTransactionRequest>,
-) -> Result<Json<ApiResponse<Transaction>>, StatusCode> {
+    ApiJson(payload): ApiJson<CreateTransactionRequest>,
+) -> ApiResult<Transaction> {
+    // An unknown account is reported by the insert below
+    let currency: Option<String> = sqlx::query_scalar("SELECT currency FROM accounts WHERE id = ?")
+        .bind(&payload.account_id)
+        .fetch_optional(&app_state.pool)
+        .await?;
+    let currency = currency.unwrap_or_default();
+    payload.validate_in(&currency)?;
+
     let id = Uuid::new_v4().to_string();
     let now = Utc::now();
+    let category_source = payload.category_id.as_ref().map(|_| CategorySource::Manual);
+
+    let mut tx = app_state.pool.begin().await?;
 
-    let transaction = sqlx::query_as::<_, Transaction>(
+    let mut transaction = sqlx::query_as::<_, Transaction>(
         r#"
-        INSERT INTO transactions (id, account_id, amount, description, transaction_date, category, created_at)
-        VALUES (?, ?, ?, ?, ?, ?, ?)
+        INSERT INTO transactions (id, account_id, amount, currency, description, transaction_date, category_id, category_locked, category_source, created_at)
+        SELECT ?, id, ?, currency, ?, ?, ?, ?, ?, ? FROM accounts WHERE id = ?
         RETURNING *
         "#,
     )
     .bind(&id)
-    .bind(&payload.account_id)
-    .bind(payload.amount)
-    .bind(&payload.description)
-    .bind(&payload.transaction_date)
-    .bind(&payload.category)
+    .bind(payload.amount.value_in(&currency))
+    .bind(payload.description.trim())
+    .bind(payload.transaction_date.value())
+    .bind(&payload.category_id)
+    .bind(payload.category_id.is_some())
+    .bind(category_source)
     .bind(now)
-    .fetch_one(&app_state.pool)
+    .bind(&payload.account_id)
+    .fetch_optional(&mut *tx)
     .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    .map_err(AppError::foreign_key("category_id"))?
+    // The insert selects the currency from the account, so no row means no such account
+    .ok_or_else(|| AppError::invalid_field("account_id", "account does not exist"))?;
+
+    let matcher = MerchantMatcher::load(&mut tx).await?;
+    transaction.merchant_id = matcher.assign(&mut tx, &transaction).await?;
+
+    classifier::train(&mut tx, &mut transaction).await?;
+    attach_paths(&mut tx, std::slice::from_mut(&mut transaction)).await?;
+    tx.commit().await?;
 
     Ok(Json(ApiResponse::success(transaction)))
 }
 
+/// Query transactions across all accounts
+///
+/// Results are keyset-paginated: pass the returned `next_cursor` back as
+/// `cursor` with the same filters and sort to fetch the following page.
+#[utoipa::path(
+    get,
+    path = "/api/transactions",
+    params(TransactionQuery),
+    responses(
+        (status = 200, description = "One page of matching transactions", body = Vec<Transaction>),
+        (status = 400, description = "Malformed query string"),
+        (status = 422, description = "Invalid cursor or amount filter"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn query_transactions(
+    State(app_state): State<AppState>,
+    ApiQuery(query): ApiQuery<TransactionQuery>,
+) -> ApiResult<Vec<Transaction>> {
+    query.validate()?;
+    let cursor = query
+        .cursor
+        .as_deref()
+        .map(|raw| TransactionCursor::decode(raw, query.sort.unwrap_or_default()))
+        .transpose()
+        .map_err(|_| AppError::invalid_field("cursor", "invalid or does not match the requested sort"))?;
+
+    let mut page = transaction_query::query_transactions(&app_state.pool, &query, cursor.as_ref()).await?;
+    let mut conn = app_state.pool.acquire().await?;
+    attach_paths(&mut conn, &mut page.transactions).await?;
+
+    Ok(Json(ApiResponse::page(page.transactions, page.next_cursor)))
+}
+
+/// Get transaction by ID
+#[utoipa::path(
+    get,
+    path = "/api/transactions/{id}",
+    params(
+        ("id" = String, Path, description = "Transaction ID")
+    ),
+    responses(
+        (status = 200, description = "Transaction found", body = Transaction),
+        (status = 404, description = "Transaction not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_transaction(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<Transaction> {
+    let mut conn = app_state.pool.acquire().await?;
+    let mut transaction = sqlx::query_as::<_, Transaction>("SELECT * FROM transactions WHERE id = ?")
+        .bind(&id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or(AppError::NotFound("Transaction"))?;
+    attach_paths(&mut conn, std::slice::from_mut(&mut transaction)).await?;
+
+    Ok(Json(ApiResponse::success(transaction)))
+}
+
+/// Update a transaction
+#[utoipa::path(
+    patch,
+    path = "/api/transactions/{id}",
+    params(
+        ("id" = String, Path, description = "Transaction ID")
+    ),
+    request_body = UpdateTransactionRequest,
+    responses(
+        (status = 200, description = "Transaction updated", body = Transaction),
+        (status = 400, description = "Malformed request body"),
+        (status = 404, description = "Transaction not found"),
+        (status = 422, description = "Invalid request data"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_transaction(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+    ApiJson(payload): ApiJson<UpdateTransactionRequest>,
+) -> ApiResult<Transaction> {
+    let mut tx = app_state.pool.begin().await?;
+
+    let mut transaction = sqlx::query_as::<_, Transaction>("SELECT * FROM transactions WHERE id = ?")
+        .bind(&id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(AppError::NotFound("Transaction"))?;
+
+    payload.validate_in(&transaction.currency)?;
+
+    let amount = payload.amount.value_in(&transaction.currency);
+    let transaction_date = payload.transaction_date.value();
+
+    let is_synced = transaction.external_id.is_some();
+    if is_synced {
+        // Amount and date are owned by the provider and would be overwritten on the next sync
+        let provider_fields = [
+            ("amount", amount.is_some()),
+            ("transaction_date", transaction_date.is_some()),
+        ];
+        let errors: Vec<FieldError> = provider_fields
+            .iter()
+            .filter(|(_, present)| *present)
+            .map(|(field, _)| FieldError::new(*field, "cannot be edited on a synced transaction"))
+            .collect();
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors));
+        }
+    }
+
+    let mut description_changed = false;
+    if let Some(description) = &payload.description {
+        let description = description.trim();
+        if !is_synced {
+            description_changed = description != transaction.description;
+            if description_changed {
+                // The classifier learned the old description's tokens
+                classifier::forget(&mut tx, &transaction).await?;
+            }
+            transaction.description = description.to_string();
+        } else if description == transaction.description {
+            // Restoring the provider's description clears the override
+            transaction.display_description = None;
+        } else {
+            transaction.display_description = Some(description.to_string());
+        }
+    }
+    if let Some(category_id) = &payload.category_id {
+        transaction.category_id = category_id.clone();
+        transaction.category_source = category_id.as_ref().map(|_| CategorySource::Manual);
+        transaction.category_locked = true;
+    }
+    if let Some(note) = &payload.note {
+        transaction.note = note.clone().filter(|n| !n.trim().is_empty());
+    }
+    if let Some(amount) = amount {
+        transaction.amount = amount;
+    }
+    if let Some(transaction_date) = transaction_date {
+        transaction.transaction_date = transaction_date;
+    }
+
+    let mut transaction = sqlx::query_as::<_, Transaction>(
+        r#"
+        UPDATE transactions SET
+            description = ?, display_description = ?, category_id = ?, category_locked = ?,
+            category_source = ?, note = ?, amount = ?, transaction_date = ?
+        WHERE id = ?
+        RETURNING *
+        "#,
+    )
+    .bind(&transaction.description)
+    .bind(&transaction.display_description)
+    .bind(&transaction.category_id)
+    .bind(transaction.category_locked)
+    .bind(transaction.category_source)
+    .bind(&transaction.note)
+    .bind(transaction.amount)
+    .bind(transaction.transaction_date)
+    .bind(&transaction.id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(AppError::foreign_key("category_id"))?;
+
+    if description_changed {
+        let matcher = MerchantMatcher::load(&mut tx).await?;
+        transaction.merchant_id = matcher.assign(&mut tx, &transaction).await?;
+    }
+
+    classifier::train(&mut tx, &mut transaction).await?;
+    attach_paths(&mut tx, std::slice::from_mut(&mut transaction)).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success(transaction)))
+}
+
+/// Delete a transaction
+///
+/// Deleting a synced transaction records a tombstone for its external ID so
+/// that later syncs do not import it again.
+#[utoipa::path(
+    delete,
+    path = "/api/transactions/{id}",
+    params(
+        ("id" = String, Path, description = "Transaction ID")
+    ),
+    responses(
+        (status = 204, description = "Transaction deleted"),
+        (status = 404, description = "Transaction not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_transaction(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let mut tx = app_state.pool.begin().await?;
+
+    let deleted = sqlx::query_as::<_, Transaction>("DELETE FROM transactions WHERE id = ? RETURNING *")
+        .bind(&id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(AppError::NotFound("Transaction"))?;
+
+    classifier::forget(&mut tx, &deleted).await?;
+
+    if let Transaction { account_id, external_id: Some(external_id), .. } = deleted {
+        sqlx::query(
+            "INSERT OR IGNORE INTO transaction_tombstones (account_id, external_id, deleted_at) VALUES (?, ?, ?)",
+        )
+        .bind(&account_id)
+        .bind(&external_id)
+        .bind(Utc::now())
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Get net worth per currency and in a base currency
+#[utoipa::path(
+    get,
+    path = "/api/reports/net-worth",
+    params(BaseCurrencyQuery),
+    responses(
+        (status = 200, description = "Account balances per currency and converted", body = NetWorthReport),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_net_worth(
+    State(app_state): State<AppState>,
+    ApiQuery(query): ApiQuery<BaseCurrencyQuery>,
+) -> ApiResult<NetWorthReport> {
+    let mut converter = app_state.converter(query.base_currency.as_deref());
+    let report = reports::net_worth_report(&app_state.pool, &mut converter).await?;
+
+    Ok(Json(ApiResponse::success(report)))
+}
+
+/// Get monthly income and expenses per currency and in a base currency
+#[utoipa::path(
+    get,
+    path = "/api/reports/monthly",
+    params(ReportQuery),
+    responses(
+        (status = 200, description = "Monthly cash flow per currency and converted", body = MonthlyReport),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_monthly_cash_flow(
+    State(app_state): State<AppState>,
+    ApiQuery(query): ApiQuery<ReportQuery>,
+) -> ApiResult<MonthlyReport> {
+    let mut converter = app_state.converter(query.base_currency.as_deref());
+    let report = reports::monthly_report(&app_state.pool, &mut converter, query.from, query.to).await?;
+
+    Ok(Json(ApiResponse::success(report)))
+}
+
+/// Get spending per merchant and currency
+///
+/// Only outflows are counted, and excluded transactions are left out.
+#[utoipa::path(
+    get,
+    path = "/api/reports/merchants",
+    params(MerchantSpendingQuery),
+    responses(
+        (status = 200, description = "Spending per merchant and currency, largest first", body = Vec<MerchantSpending>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_merchant_spending(
+    State(app_state): State<AppState>,
+    ApiQuery(query): ApiQuery<MerchantSpendingQuery>,
+) -> ApiResult<Vec<MerchantSpending>> {
+    let spending = reports::merchant_spending(&app_state.pool, &query).await?;
+
+    Ok(Json(ApiResponse::success(spending)))
+}
+
 /// Trigger manual sync with SimpleFin
+///
+/// The run is recorded in the sync history like scheduled runs. If a sync is
+/// already running, no second one starts: the request waits for the running
+/// sync and returns its result. A successful sync resumes paused scheduling.
 #[utoipa::path(
     post,
     path = "/api/sync",
     responses(
         (status = 200, description = "Sync completed successfully", body = SyncStats),
-        (status = 500, description = "Sync failed")
+        (status = 500, description = "Sync failed"),
+        (status = 503, description = "No SimpleFin connection is enabled")
     )
 )]
 pub async fn trigger_sync(
     State(app_state): State<AppState>,
-) -> Result<Json<ApiResponse<SyncStats>>, StatusCode> {
-    let sync_service = app_state.sync_service.as_ref()
-        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
-    
-    match sync_service.sync_all().await {
-        Ok(stats) => Ok(Json(ApiResponse::success(stats))),
-        Err(e) => {
-            tracing::error!("Manual sync failed: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
+) -> ApiResult<SyncStats> {
+    app_state.require_provider().await?;
+
+    let result = app_state.sync_service.sync_all(SyncTrigger::Manual).await;
+    app_state.scheduler.record(&result, SyncTrigger::Manual).await;
+    let stats = result.context("Manual sync failed")?;
+
+    Ok(Json(ApiResponse::success(stats)))
 }
\ No newline at end of file
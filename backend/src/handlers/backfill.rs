@@ -0,0 +1,171 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use chrono::{Days, NaiveDate, NaiveTime, Utc};
+
+use crate::app_state::AppState;
+use crate::backfill::BackfillJob;
+use crate::connections;
+use crate::error::{ApiResult, AppError, FieldError};
+use crate::extract::ApiJson;
+use crate::models::*;
+use crate::sync::BackfillTarget;
+use crate::validation::Validate;
+
+/// Start a historical backfill from SimpleFin
+///
+/// The range is fetched in date windows in the background; poll the returned
+/// job for progress. Transactions that already exist are skipped as in a
+/// regular sync.
+#[utoipa::path(
+    post,
+    path = "/api/sync/backfill",
+    request_body = BackfillRequest,
+    responses(
+        (status = 202, description = "Backfill started", body = BackfillJob),
+        (status = 400, description = "Malformed request body"),
+        (status = 422, description = "Invalid date range or account"),
+        (status = 500, description = "Internal server error"),
+        (status = 503, description = "No SimpleFin connection is enabled")
+    )
+)]
+pub async fn start_backfill(
+    State(app_state): State<AppState>,
+    ApiJson(payload): ApiJson<BackfillRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<BackfillJob>>), AppError> {
+    app_state.require_provider().await?;
+    payload.validate()?;
+
+    let mut errors = Vec::new();
+    let mut targets: Vec<BackfillTarget> = Vec::new();
+    for (index, account_id) in payload.account_ids.iter().enumerate() {
+        let field = format!("account_ids[{}]", index);
+        let link = sqlx::query_as::<_, (Option<String>, Option<String>)>(
+            "SELECT external_id, connection_id FROM accounts WHERE id = ?",
+        )
+        .bind(account_id)
+        .fetch_optional(&app_state.pool)
+        .await?;
+        let (external_id, connection_id) = match link {
+            Some((Some(external_id), Some(connection_id))) => (external_id, connection_id),
+            Some((None, _)) => {
+                errors.push(FieldError::new(field, "account is not linked to a provider"));
+                continue;
+            }
+            Some((Some(_), None)) => {
+                errors.push(FieldError::new(field, "account's connection was removed"));
+                continue;
+            }
+            None => {
+                errors.push(FieldError::new(field, "account does not exist"));
+                continue;
+            }
+        };
+        match targets.iter_mut().find(|t| t.connection.id == connection_id) {
+            Some(target) => target.external_ids.push(external_id),
+            None => {
+                let connection =
+                    sqlx::query_as::<_, Connection>("SELECT * FROM connections WHERE id = ?")
+                        .bind(&connection_id)
+                        .fetch_one(&app_state.pool)
+                        .await?;
+                targets.push(BackfillTarget {
+                    connection,
+                    external_ids: vec![external_id],
+                });
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Err(AppError::Validation(errors));
+    }
+    if payload.account_ids.is_empty() {
+        targets = connections::enabled(&app_state.pool)
+            .await?
+            .into_iter()
+            .map(|connection| BackfillTarget {
+                connection,
+                external_ids: Vec::new(),
+            })
+            .collect();
+    }
+
+    // Whole days: from the start of `from` to the end of `to`
+    let start_of = |date: NaiveDate| date.and_time(NaiveTime::MIN).and_utc();
+    let to = payload.to.value().unwrap_or_else(|| Utc::now().date_naive());
+    let job = app_state.sync_service.start_backfill(
+        start_of(payload.from.value()),
+        start_of(to + Days::new(1)),
+        payload.account_ids,
+        targets,
+    );
+
+    Ok((StatusCode::ACCEPTED, Json(ApiResponse::success(job))))
+}
+
+/// List backfill jobs since the server started, newest first
+#[utoipa::path(
+    get,
+    path = "/api/sync/backfill",
+    responses(
+        (status = 200, description = "Backfill jobs", body = Vec<BackfillJob>)
+    )
+)]
+pub async fn get_backfills(State(app_state): State<AppState>) -> ApiResult<Vec<BackfillJob>> {
+    let jobs = app_state.sync_service.backfills().list();
+
+    Ok(Json(ApiResponse::success(jobs)))
+}
+
+/// Get a backfill job's progress
+#[utoipa::path(
+    get,
+    path = "/api/sync/backfill/{id}",
+    params(
+        ("id" = String, Path, description = "Backfill job ID")
+    ),
+    responses(
+        (status = 200, description = "Backfill job found", body = BackfillJob),
+        (status = 404, description = "Backfill job not found")
+    )
+)]
+pub async fn get_backfill(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<BackfillJob> {
+    let job = app_state.sync_service
+        .backfills()
+        .get(&id)
+        .ok_or(AppError::NotFound("Backfill job"))?;
+
+    Ok(Json(ApiResponse::success(job)))
+}
+
+/// Cancel a backfill job
+///
+/// The job stops after the window it is importing. Windows already imported
+/// are kept.
+#[utoipa::path(
+    delete,
+    path = "/api/sync/backfill/{id}",
+    params(
+        ("id" = String, Path, description = "Backfill job ID")
+    ),
+    responses(
+        (status = 200, description = "Cancellation requested; returns the job", body = BackfillJob),
+        (status = 404, description = "Backfill job not found")
+    )
+)]
+pub async fn cancel_backfill(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<BackfillJob> {
+    let job = app_state.sync_service
+        .backfills()
+        .cancel(&id)
+        .ok_or(AppError::NotFound("Backfill job"))?;
+
+    Ok(Json(ApiResponse::success(job)))
+}
@@ -0,0 +1,300 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::app_state::AppState;
+use crate::categories;
+use crate::error::{ApiResult, AppError};
+use crate::extract::ApiJson;
+use crate::models::*;
+use crate::validation::Validate;
+
+/// Sibling category names are unique regardless of case.
+fn name_conflict(err: sqlx::Error) -> AppError {
+    match &err {
+        sqlx::Error::Database(db) if db.is_unique_violation() => AppError::Conflict(
+            "a category with this name already exists under the same parent".to_string(),
+        ),
+        _ => AppError::foreign_key("parent_id")(err),
+    }
+}
+
+/// List all categories
+///
+/// Categories are returned flat; use `parent_id` to build the tree.
+#[utoipa::path(
+    get,
+    path = "/api/categories",
+    responses(
+        (status = 200, description = "List of all categories", body = Vec<Category>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_categories(State(app_state): State<AppState>) -> ApiResult<Vec<Category>> {
+    let categories =
+        sqlx::query_as::<_, Category>("SELECT * FROM categories ORDER BY name COLLATE NOCASE")
+            .fetch_all(&app_state.pool)
+            .await?;
+
+    Ok(Json(ApiResponse::success(categories)))
+}
+
+/// Create a category
+#[utoipa::path(
+    post,
+    path = "/api/categories",
+    request_body = CreateCategoryRequest,
+    responses(
+        (status = 201, description = "Category created", body = Category),
+        (status = 400, description = "Malformed request body"),
+        (status = 409, description = "A sibling category with the same name exists"),
+        (status = 422, description = "Invalid request data"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_category(
+    State(app_state): State<AppState>,
+    ApiJson(payload): ApiJson<CreateCategoryRequest>,
+) -> ApiResult<Category> {
+    payload.validate()?;
+
+    let category = sqlx::query_as::<_, Category>(
+        r#"
+        INSERT INTO categories (id, name, parent_id, kind, color, icon, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        RETURNING *
+        "#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(payload.name.trim())
+    .bind(&payload.parent_id)
+    .bind(payload.kind.unwrap_or_default())
+    .bind(payload.color.as_deref().map(str::trim))
+    .bind(
+        payload
+            .icon
+            .as_deref()
+            .map(str::trim)
+            .filter(|i| !i.is_empty()),
+    )
+    .bind(Utc::now())
+    .fetch_one(&app_state.pool)
+    .await
+    .map_err(name_conflict)?;
+
+    Ok(Json(ApiResponse::success(category)))
+}
+
+/// Get category by ID
+#[utoipa::path(
+    get,
+    path = "/api/categories/{id}",
+    params(
+        ("id" = String, Path, description = "Category ID")
+    ),
+    responses(
+        (status = 200, description = "Category found", body = Category),
+        (status = 404, description = "Category not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_category(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<Category> {
+    let category = sqlx::query_as::<_, Category>("SELECT * FROM categories WHERE id = ?")
+        .bind(&id)
+        .fetch_optional(&app_state.pool)
+        .await?
+        .ok_or(AppError::NotFound("Category"))?;
+
+    Ok(Json(ApiResponse::success(category)))
+}
+
+/// Update a category
+#[utoipa::path(
+    patch,
+    path = "/api/categories/{id}",
+    params(
+        ("id" = String, Path, description = "Category ID")
+    ),
+    request_body = UpdateCategoryRequest,
+    responses(
+        (status = 200, description = "Category updated", body = Category),
+        (status = 400, description = "Malformed request body"),
+        (status = 404, description = "Category not found"),
+        (status = 409, description = "A sibling category with the same name exists"),
+        (status = 422, description = "Invalid request data, e.g. a parent that would create a cycle"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_category(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+    ApiJson(payload): ApiJson<UpdateCategoryRequest>,
+) -> ApiResult<Category> {
+    payload.validate()?;
+
+    let mut tx = app_state.pool.begin().await?;
+
+    let mut category = sqlx::query_as::<_, Category>("SELECT * FROM categories WHERE id = ?")
+        .bind(&id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(AppError::NotFound("Category"))?;
+
+    if let Some(name) = &payload.name {
+        category.name = name.trim().to_string();
+    }
+    if let Some(parent_id) = &payload.parent_id {
+        if let Some(parent_id) = parent_id
+            && categories::is_in_subtree(&mut tx, &id, parent_id).await?
+        {
+            return Err(AppError::invalid_field(
+                "parent_id",
+                "cannot be the category itself or one of its subcategories",
+            ));
+        }
+        category.parent_id = parent_id.clone();
+    }
+    if let Some(kind) = payload.kind {
+        category.kind = kind;
+    }
+    if let Some(color) = &payload.color {
+        category.color = color.as_deref().map(str::trim).map(str::to_string);
+    }
+    if let Some(icon) = &payload.icon {
+        category.icon = icon
+            .as_deref()
+            .map(str::trim)
+            .filter(|i| !i.is_empty())
+            .map(str::to_string);
+    }
+
+    let category = sqlx::query_as::<_, Category>(
+        r#"
+        UPDATE categories SET name = ?, parent_id = ?, kind = ?, color = ?, icon = ?
+        WHERE id = ?
+        RETURNING *
+        "#,
+    )
+    .bind(&category.name)
+    .bind(&category.parent_id)
+    .bind(category.kind)
+    .bind(&category.color)
+    .bind(&category.icon)
+    .bind(&id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(name_conflict)?;
+
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success(category)))
+}
+
+/// Delete a category
+///
+/// Its subcategories move up to its parent and its transactions become
+/// uncategorized.
+#[utoipa::path(
+    delete,
+    path = "/api/categories/{id}",
+    params(
+        ("id" = String, Path, description = "Category ID")
+    ),
+    responses(
+        (status = 204, description = "Category deleted"),
+        (status = 404, description = "Category not found"),
+        (status = 409, description = "A subcategory's name clashes with one under the new parent"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_category(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let mut tx = app_state.pool.begin().await?;
+
+    let parent_id =
+        sqlx::query_scalar::<_, Option<String>>("SELECT parent_id FROM categories WHERE id = ?")
+            .bind(&id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or(AppError::NotFound("Category"))?;
+
+    sqlx::query("UPDATE categories SET parent_id = ? WHERE parent_id = ?")
+        .bind(&parent_id)
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(name_conflict)?;
+
+    // Transactions are uncategorized by ON DELETE SET NULL
+    sqlx::query("DELETE FROM categories WHERE id = ?")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Merge a category into another
+///
+/// Transactions and subcategories of the merged category move to `into_id`,
+/// subcategories with matching names are merged as well, and the merged
+/// category is deleted. The whole operation is atomic.
+#[utoipa::path(
+    post,
+    path = "/api/categories/{id}/merge",
+    params(
+        ("id" = String, Path, description = "ID of the category to merge away")
+    ),
+    request_body = MergeCategoryRequest,
+    responses(
+        (status = 200, description = "Categories merged; returns the remaining category", body = Category),
+        (status = 404, description = "Category not found"),
+        (status = 422, description = "Target is the category itself or one of its subcategories"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn merge_category(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+    ApiJson(payload): ApiJson<MergeCategoryRequest>,
+) -> ApiResult<Category> {
+    let mut tx = app_state.pool.begin().await?;
+
+    let exists = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM categories WHERE id = ?")
+        .bind(&id)
+        .fetch_one(&mut *tx)
+        .await?;
+    if exists == 0 {
+        return Err(AppError::NotFound("Category"));
+    }
+
+    let target = sqlx::query_as::<_, Category>("SELECT * FROM categories WHERE id = ?")
+        .bind(&payload.into_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::invalid_field("into_id", "category does not exist"))?;
+
+    if categories::is_in_subtree(&mut tx, &id, &target.id).await? {
+        return Err(AppError::invalid_field(
+            "into_id",
+            "cannot be the category itself or one of its subcategories",
+        ));
+    }
+
+    categories::merge(&mut tx, &id, &target.id).await?;
+
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success(target)))
+}
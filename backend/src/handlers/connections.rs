@@ -0,0 +1,252 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+
+use crate::app_state::AppState;
+use crate::connections;
+use crate::credentials::Secret;
+use crate::error::{ApiResult, AppError};
+use crate::extract::ApiJson;
+use crate::models::*;
+use crate::simplefin::{ClaimError, SimplefinClient};
+use crate::validation::Validate;
+
+impl From<ClaimError> for AppError {
+    fn from(err: ClaimError) -> Self {
+        match err {
+            ClaimError::InvalidToken(_) | ClaimError::Rejected(_) => {
+                AppError::invalid_field("setup_token", err.to_string())
+            }
+            ClaimError::Request(ref e) => {
+                tracing::error!("Failed to claim a SimpleFin setup token: {}", e);
+                AppError::ServiceUnavailable(err.to_string())
+            }
+            ClaimError::InvalidAccessUrl(access_url) => AppError::AccessUrlNotStored {
+                message: "SimpleFin returned an invalid access URL".to_string(),
+                access_url,
+            },
+        }
+    }
+}
+
+/// List connections
+#[utoipa::path(
+    get,
+    path = "/api/connections",
+    responses(
+        (status = 200, description = "List of all connections", body = Vec<Connection>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_connections(State(app_state): State<AppState>) -> ApiResult<Vec<Connection>> {
+    let connections =
+        sqlx::query_as::<_, Connection>("SELECT * FROM connections ORDER BY created_at")
+            .fetch_all(&app_state.pool)
+            .await?;
+
+    Ok(Json(ApiResponse::success(connections)))
+}
+
+/// Add a connection from a SimpleFin access URL
+///
+/// It is included from the next sync on. On a server running without a
+/// connection, this starts the sync scheduler.
+#[utoipa::path(
+    post,
+    path = "/api/connections",
+    request_body = CreateConnectionRequest,
+    responses(
+        (status = 201, description = "Connection added", body = Connection),
+        (status = 400, description = "Malformed request body"),
+        (status = 422, description = "Invalid request data"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_connection(
+    State(app_state): State<AppState>,
+    ApiJson(payload): ApiJson<CreateConnectionRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<Connection>>), AppError> {
+    payload.validate()?;
+
+    let access_url = Secret::new(payload.access_url.expose().trim().to_string());
+    let connection = connections::insert(
+        &app_state.pool,
+        &app_state.cipher,
+        payload.name.as_deref(),
+        &access_url,
+    )
+    .await?;
+    tracing::info!(
+        "Added SimpleFin connection {} ({})",
+        connection.name,
+        connection.server_url
+    );
+    app_state.scheduler.start_late();
+
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(connection))))
+}
+
+/// Get connection by ID
+#[utoipa::path(
+    get,
+    path = "/api/connections/{id}",
+    params(
+        ("id" = String, Path, description = "Connection ID")
+    ),
+    responses(
+        (status = 200, description = "Connection found", body = Connection),
+        (status = 404, description = "Connection not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_connection(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<Connection> {
+    let connection = sqlx::query_as::<_, Connection>("SELECT * FROM connections WHERE id = ?")
+        .bind(&id)
+        .fetch_optional(&app_state.pool)
+        .await?
+        .ok_or(AppError::NotFound("Connection"))?;
+
+    Ok(Json(ApiResponse::success(connection)))
+}
+
+/// Rename, disable or re-enable a connection
+///
+/// Disabled connections are skipped by sync; their accounts and transactions are kept.
+/// Re-enabling a connection clears its reauthentication mark, so scheduled
+/// syncs try its credentials again.
+#[utoipa::path(
+    patch,
+    path = "/api/connections/{id}",
+    params(
+        ("id" = String, Path, description = "Connection ID")
+    ),
+    request_body = UpdateConnectionRequest,
+    responses(
+        (status = 200, description = "Connection updated", body = Connection),
+        (status = 400, description = "Malformed request body"),
+        (status = 404, description = "Connection not found"),
+        (status = 422, description = "Invalid request data"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_connection(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+    ApiJson(payload): ApiJson<UpdateConnectionRequest>,
+) -> ApiResult<Connection> {
+    payload.validate()?;
+
+    let connection = sqlx::query_as::<_, Connection>(
+        r#"
+        UPDATE connections SET
+            name = COALESCE(?1, name),
+            enabled = COALESCE(?2, enabled),
+            auth_failed_at = CASE WHEN ?2 THEN NULL ELSE auth_failed_at END,
+            auth_error = CASE WHEN ?2 THEN NULL ELSE auth_error END
+        WHERE id = ?3
+        RETURNING *
+        "#,
+    )
+    .bind(payload.name.as_deref().map(str::trim))
+    .bind(payload.enabled)
+    .bind(&id)
+    .fetch_optional(&app_state.pool)
+    .await?
+    .ok_or(AppError::NotFound("Connection"))?;
+    if connection.enabled {
+        app_state.scheduler.start_late();
+    }
+
+    Ok(Json(ApiResponse::success(connection)))
+}
+
+/// Remove a connection
+///
+/// Its accounts and transactions are kept but no longer synced. A connection
+/// from `SIMPLEFIN_ACCESS_URL` is added again when the server restarts; disable
+/// it instead to keep it out of syncs.
+#[utoipa::path(
+    delete,
+    path = "/api/connections/{id}",
+    params(
+        ("id" = String, Path, description = "Connection ID")
+    ),
+    responses(
+        (status = 204, description = "Connection removed"),
+        (status = 404, description = "Connection not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_connection(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let result = sqlx::query("DELETE FROM connections WHERE id = ?")
+        .bind(&id)
+        .execute(&app_state.pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Connection"));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Claim a SimpleFin setup token
+///
+/// The token is exchanged for an access URL, which is stored as a new
+/// connection and included from the next sync on, starting the sync
+/// scheduler if it is not running yet. A setup token can be claimed only once,
+/// so if the access URL cannot be stored, the error returns it in `access_url`
+/// to add with `POST /api/connections`.
+#[utoipa::path(
+    post,
+    path = "/api/connections/simplefin/claim",
+    request_body = ClaimConnectionRequest,
+    responses(
+        (status = 201, description = "Token claimed; returns the new connection", body = Connection),
+        (status = 400, description = "Malformed request body"),
+        (status = 422, description = "Invalid setup token, or SimpleFin rejected it"),
+        (status = 500, description = "Internal server error, or the access URL could not be stored"),
+        (status = 503, description = "SimpleFin could not be reached")
+    )
+)]
+pub async fn claim_simplefin_connection(
+    State(app_state): State<AppState>,
+    ApiJson(payload): ApiJson<ClaimConnectionRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<Connection>>), AppError> {
+    payload.validate()?;
+
+    let access_url = SimplefinClient::claim(payload.setup_token.expose()).await?;
+    let connection = match connections::insert(
+        &app_state.pool,
+        &app_state.cipher,
+        payload.name.as_deref(),
+        &access_url,
+    )
+    .await
+    {
+        Ok(connection) => connection,
+        Err(e) => {
+            tracing::error!("Failed to store the claimed SimpleFin connection: {:#}", e);
+            return Err(AppError::AccessUrlNotStored {
+                message: "the setup token was claimed, but the connection could not be stored"
+                    .to_string(),
+                access_url,
+            });
+        }
+    };
+
+    tracing::info!(
+        "Claimed SimpleFin connection {} ({})",
+        connection.name,
+        connection.server_url
+    );
+    app_state.scheduler.start_late();
+
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(connection))))
+}
@@ -0,0 +1,178 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+
+use crate::app_state::AppState;
+use crate::error::{ApiResult, AppError};
+use crate::extract::{ApiJson, ApiQuery};
+use crate::exchange_rates;
+use crate::models::*;
+use crate::money::normalize_currency;
+
+/// List exchange rates
+#[utoipa::path(
+    get,
+    path = "/api/exchange-rates",
+    params(ExchangeRateQuery),
+    responses(
+        (status = 200, description = "Exchange rates, newest first", body = Vec<ExchangeRate>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_exchange_rates(
+    State(app_state): State<AppState>,
+    ApiQuery(query): ApiQuery<ExchangeRateQuery>,
+) -> ApiResult<Vec<ExchangeRate>> {
+    let from_currency = query.from_currency.as_deref().map(normalize_currency);
+    let to_currency = query.to_currency.as_deref().map(normalize_currency);
+
+    let rates = sqlx::query_as::<_, ExchangeRate>(
+        r#"
+        SELECT * FROM exchange_rates
+        WHERE (? IS NULL OR from_currency = ?)
+          AND (? IS NULL OR to_currency = ?)
+        ORDER BY rate_date DESC, from_currency, to_currency
+        "#,
+    )
+    .bind(&from_currency)
+    .bind(&from_currency)
+    .bind(&to_currency)
+    .bind(&to_currency)
+    .fetch_all(&app_state.pool)
+    .await?;
+
+    Ok(Json(ApiResponse::success(rates)))
+}
+
+/// Add or replace exchange rates
+#[utoipa::path(
+    post,
+    path = "/api/exchange-rates",
+    request_body = Vec<ExchangeRateInput>,
+    responses(
+        (status = 200, description = "Rates saved; an existing rate for the same pair and date is replaced", body = Vec<ExchangeRate>),
+        (status = 400, description = "Malformed request body"),
+        (status = 422, description = "One or more rates are invalid"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn upsert_exchange_rates(
+    State(app_state): State<AppState>,
+    ApiJson(payload): ApiJson<Vec<ExchangeRateInput>>,
+) -> ApiResult<Vec<ExchangeRate>> {
+    let rates = exchange_rates::validate_rates(&payload).map_err(AppError::Validation)?;
+
+    let saved = exchange_rates::upsert_rates(&app_state.pool, &rates, "manual").await?;
+
+    Ok(Json(ApiResponse::success(saved)))
+}
+
+/// Import exchange rates from CSV
+///
+/// Expects `date,from_currency,to_currency,rate` rows with an optional header.
+/// Nothing is imported if any line is invalid.
+#[utoipa::path(
+    post,
+    path = "/api/exchange-rates/import",
+    request_body(content = String, content_type = "text/csv"),
+    responses(
+        (status = 200, description = "Rates imported", body = Vec<ExchangeRate>),
+        (status = 422, description = "One or more lines are invalid"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn import_exchange_rates(
+    State(app_state): State<AppState>,
+    body: String,
+) -> ApiResult<Vec<ExchangeRate>> {
+    let rates = exchange_rates::parse_csv(&body).map_err(AppError::Validation)?;
+
+    let saved = exchange_rates::upsert_rates(&app_state.pool, &rates, "import").await?;
+
+    Ok(Json(ApiResponse::success(saved)))
+}
+
+/// Update an exchange rate
+#[utoipa::path(
+    put,
+    path = "/api/exchange-rates/{id}",
+    params(
+        ("id" = String, Path, description = "Exchange rate ID")
+    ),
+    request_body = UpdateExchangeRateRequest,
+    responses(
+        (status = 200, description = "Exchange rate updated", body = ExchangeRate),
+        (status = 400, description = "Malformed request body"),
+        (status = 404, description = "Exchange rate not found"),
+        (status = 409, description = "A rate already exists for this pair and date"),
+        (status = 422, description = "Invalid rate"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_exchange_rate(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+    ApiJson(payload): ApiJson<UpdateExchangeRateRequest>,
+) -> ApiResult<ExchangeRate> {
+    if payload
+        .rate
+        .is_some_and(|rate| !rate.is_finite() || rate <= 0.0)
+    {
+        return Err(AppError::invalid_field(
+            "rate",
+            "must be a positive number",
+        ));
+    }
+
+    // Moving the rate onto a date that already has one for this pair is a
+    // unique violation, which surfaces as 409 Conflict
+    let rate = sqlx::query_as::<_, ExchangeRate>(
+        r#"
+        UPDATE exchange_rates SET
+            rate = COALESCE(?, rate),
+            rate_date = COALESCE(?, rate_date),
+            source = 'manual'
+        WHERE id = ?
+        RETURNING *
+        "#,
+    )
+    .bind(payload.rate)
+    .bind(payload.rate_date)
+    .bind(&id)
+    .fetch_optional(&app_state.pool)
+    .await?
+    .ok_or(AppError::NotFound("Exchange rate"))?;
+
+    Ok(Json(ApiResponse::success(rate)))
+}
+
+/// Delete an exchange rate
+#[utoipa::path(
+    delete,
+    path = "/api/exchange-rates/{id}",
+    params(
+        ("id" = String, Path, description = "Exchange rate ID")
+    ),
+    responses(
+        (status = 204, description = "Exchange rate deleted"),
+        (status = 404, description = "Exchange rate not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_exchange_rate(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let result = sqlx::query("DELETE FROM exchange_rates WHERE id = ?")
+        .bind(&id)
+        .execute(&app_state.pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Exchange rate"));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
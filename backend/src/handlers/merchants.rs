@@ -0,0 +1,284 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use chrono::Utc;
+use sqlx::SqliteConnection;
+use uuid::Uuid;
+
+use crate::app_state::AppState;
+use crate::error::{ApiResult, AppError};
+use crate::extract::ApiJson;
+use crate::merchants;
+use crate::models::*;
+use crate::validation::Validate;
+
+/// Merchant names are unique regardless of case.
+fn name_conflict(err: sqlx::Error) -> AppError {
+    match &err {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            AppError::Conflict("a merchant with this name already exists".to_string())
+        }
+        _ => err.into(),
+    }
+}
+
+/// Replaces a merchant's aliases, returning true if they changed.
+async fn save_aliases(
+    conn: &mut SqliteConnection,
+    merchant: &mut Merchant,
+    input: &MerchantInput,
+) -> Result<bool, AppError> {
+    let mut aliases: Vec<String> = input.aliases.iter().map(|a| a.trim().to_string()).collect();
+    aliases.sort();
+    aliases.dedup();
+    if aliases == merchant.aliases {
+        return Ok(false);
+    }
+
+    sqlx::query("DELETE FROM merchant_aliases WHERE merchant_id = ?")
+        .bind(&merchant.id)
+        .execute(&mut *conn)
+        .await?;
+    for alias in &aliases {
+        sqlx::query("INSERT INTO merchant_aliases (merchant_id, pattern) VALUES (?, ?)")
+            .bind(&merchant.id)
+            .bind(alias)
+            .execute(&mut *conn)
+            .await?;
+    }
+    merchant.aliases = aliases;
+    Ok(true)
+}
+
+/// List all merchants with their aliases
+#[utoipa::path(
+    get,
+    path = "/api/merchants",
+    responses(
+        (status = 200, description = "List of all merchants", body = Vec<Merchant>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_merchants(State(app_state): State<AppState>) -> ApiResult<Vec<Merchant>> {
+    let mut conn = app_state.pool.acquire().await?;
+    let mut merchants =
+        sqlx::query_as::<_, Merchant>("SELECT * FROM merchants ORDER BY name COLLATE NOCASE")
+            .fetch_all(&mut *conn)
+            .await?;
+    merchants::load_aliases(&mut conn, &mut merchants).await?;
+
+    Ok(Json(ApiResponse::success(merchants)))
+}
+
+/// Create a merchant
+///
+/// Transactions matching one of its aliases are reassigned to it.
+#[utoipa::path(
+    post,
+    path = "/api/merchants",
+    request_body = MerchantInput,
+    responses(
+        (status = 201, description = "Merchant created", body = Merchant),
+        (status = 400, description = "Malformed request body"),
+        (status = 409, description = "A merchant with the same name exists"),
+        (status = 422, description = "Invalid request data"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_merchant(
+    State(app_state): State<AppState>,
+    ApiJson(payload): ApiJson<MerchantInput>,
+) -> ApiResult<Merchant> {
+    payload.validate()?;
+
+    let mut tx = app_state.pool.begin().await?;
+
+    let mut merchant = sqlx::query_as::<_, Merchant>(
+        "INSERT INTO merchants (id, name, created_at) VALUES (?, ?, ?) RETURNING *",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(payload.name.trim())
+    .bind(Utc::now())
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(name_conflict)?;
+
+    if save_aliases(&mut tx, &mut merchant, &payload).await? {
+        merchants::assign_all(&mut tx, false).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success(merchant)))
+}
+
+/// Get merchant by ID
+#[utoipa::path(
+    get,
+    path = "/api/merchants/{id}",
+    params(
+        ("id" = String, Path, description = "Merchant ID")
+    ),
+    responses(
+        (status = 200, description = "Merchant found", body = Merchant),
+        (status = 404, description = "Merchant not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_merchant(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<Merchant> {
+    let mut conn = app_state.pool.acquire().await?;
+    let merchant = sqlx::query_as::<_, Merchant>("SELECT * FROM merchants WHERE id = ?")
+        .bind(&id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or(AppError::NotFound("Merchant"))?;
+
+    let mut merchants = [merchant];
+    merchants::load_aliases(&mut conn, &mut merchants).await?;
+    let [merchant] = merchants;
+
+    Ok(Json(ApiResponse::success(merchant)))
+}
+
+/// Replace a merchant's name and aliases
+///
+/// When the aliases change, every transaction's merchant is resolved again.
+#[utoipa::path(
+    put,
+    path = "/api/merchants/{id}",
+    params(
+        ("id" = String, Path, description = "Merchant ID")
+    ),
+    request_body = MerchantInput,
+    responses(
+        (status = 200, description = "Merchant updated", body = Merchant),
+        (status = 400, description = "Malformed request body"),
+        (status = 404, description = "Merchant not found"),
+        (status = 409, description = "A merchant with the same name exists"),
+        (status = 422, description = "Invalid request data"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_merchant(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+    ApiJson(payload): ApiJson<MerchantInput>,
+) -> ApiResult<Merchant> {
+    payload.validate()?;
+
+    let mut tx = app_state.pool.begin().await?;
+
+    let merchant =
+        sqlx::query_as::<_, Merchant>("UPDATE merchants SET name = ? WHERE id = ? RETURNING *")
+            .bind(payload.name.trim())
+            .bind(&id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(name_conflict)?
+            .ok_or(AppError::NotFound("Merchant"))?;
+
+    let mut merchants = [merchant];
+    merchants::load_aliases(&mut tx, &mut merchants).await?;
+    let [mut merchant] = merchants;
+
+    if save_aliases(&mut tx, &mut merchant, &payload).await? {
+        merchants::assign_all(&mut tx, false).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success(merchant)))
+}
+
+/// Delete a merchant
+///
+/// Its transactions are left without a merchant until they are resolved again.
+#[utoipa::path(
+    delete,
+    path = "/api/merchants/{id}",
+    params(
+        ("id" = String, Path, description = "Merchant ID")
+    ),
+    responses(
+        (status = 204, description = "Merchant deleted"),
+        (status = 404, description = "Merchant not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_merchant(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let result = sqlx::query("DELETE FROM merchants WHERE id = ?")
+        .bind(&id)
+        .execute(&app_state.pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Merchant"));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Merge a merchant into another
+///
+/// Transactions and aliases move to `into_id`, and the merged merchant's name
+/// becomes an alias of `into_id` so future transactions follow. The merged
+/// merchant is deleted.
+#[utoipa::path(
+    post,
+    path = "/api/merchants/{id}/merge",
+    params(
+        ("id" = String, Path, description = "ID of the merchant to merge away")
+    ),
+    request_body = MergeMerchantRequest,
+    responses(
+        (status = 200, description = "Merchants merged; returns the remaining merchant", body = Merchant),
+        (status = 404, description = "Merchant not found"),
+        (status = 422, description = "Target is the merchant itself or does not exist"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn merge_merchant(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+    ApiJson(payload): ApiJson<MergeMerchantRequest>,
+) -> ApiResult<Merchant> {
+    let mut tx = app_state.pool.begin().await?;
+
+    let exists = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM merchants WHERE id = ?")
+        .bind(&id)
+        .fetch_one(&mut *tx)
+        .await?;
+    if exists == 0 {
+        return Err(AppError::NotFound("Merchant"));
+    }
+    if payload.into_id == id {
+        return Err(AppError::invalid_field(
+            "into_id",
+            "cannot be the merchant itself",
+        ));
+    }
+
+    let target = sqlx::query_as::<_, Merchant>("SELECT * FROM merchants WHERE id = ?")
+        .bind(&payload.into_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::invalid_field("into_id", "merchant does not exist"))?;
+
+    merchants::merge(&mut tx, &id, &target.id).await?;
+
+    let mut merchants = [target];
+    merchants::load_aliases(&mut tx, &mut merchants).await?;
+    let [target] = merchants;
+
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success(target)))
+}
@@ -0,0 +1,293 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use chrono::Utc;
+use sqlx::{SqliteConnection, types::Json as SqlJson};
+use uuid::Uuid;
+
+use crate::app_state::AppState;
+use crate::classifier;
+use crate::error::{ApiResult, AppError, FieldError};
+use crate::extract::ApiJson;
+use crate::models::*;
+use crate::rules;
+use crate::validation::Validate;
+
+/// Checks that the account and category a rule refers to exist.
+async fn check_references(conn: &mut SqliteConnection, input: &RuleInput) -> Result<(), AppError> {
+    let mut errors = Vec::new();
+    for (field, table, id) in [
+        ("account_id", "accounts", &input.account_id),
+        ("set_category_id", "categories", &input.set_category_id),
+    ] {
+        let Some(id) = id else { continue };
+        let exists =
+            sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM {} WHERE id = ?", table))
+                .bind(id)
+                .fetch_one(&mut *conn)
+                .await?;
+        if exists == 0 {
+            errors.push(FieldError::new(
+                field,
+                "references a record that does not exist",
+            ));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::Validation(errors))
+    }
+}
+
+/// Trims free text and drops duplicate tags.
+fn normalized_tags(input: &RuleInput) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::with_capacity(input.add_tags.len());
+    for tag in &input.add_tags {
+        let tag = tag.trim().to_string();
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+/// List rules in the order they run
+#[utoipa::path(
+    get,
+    path = "/api/rules",
+    responses(
+        (status = 200, description = "List of all rules", body = Vec<Rule>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_rules(State(app_state): State<AppState>) -> ApiResult<Vec<Rule>> {
+    let rules = sqlx::query_as::<_, Rule>("SELECT * FROM rules ORDER BY priority, created_at, id")
+        .fetch_all(&app_state.pool)
+        .await?;
+
+    Ok(Json(ApiResponse::success(rules)))
+}
+
+/// Create a rule
+#[utoipa::path(
+    post,
+    path = "/api/rules",
+    request_body = RuleInput,
+    responses(
+        (status = 201, description = "Rule created", body = Rule),
+        (status = 400, description = "Malformed request body"),
+        (status = 422, description = "Invalid request data"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_rule(
+    State(app_state): State<AppState>,
+    ApiJson(payload): ApiJson<RuleInput>,
+) -> ApiResult<Rule> {
+    payload.validate()?;
+
+    let mut tx = app_state.pool.begin().await?;
+    check_references(&mut tx, &payload).await?;
+
+    let rule = sqlx::query_as::<_, Rule>(
+        r#"
+        INSERT INTO rules (
+            id, name, priority, enabled, description_pattern, payee_pattern, memo_pattern,
+            min_amount, max_amount, account_id, sign, set_category_id, set_payee, add_tags,
+            set_flagged, set_excluded, stop_processing, created_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        RETURNING *
+        "#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(payload.name.trim())
+    .bind(payload.priority)
+    .bind(payload.enabled)
+    .bind(&payload.description_pattern)
+    .bind(&payload.payee_pattern)
+    .bind(&payload.memo_pattern)
+    .bind(payload.min_amount.threshold())
+    .bind(payload.max_amount.threshold())
+    .bind(&payload.account_id)
+    .bind(payload.sign.value())
+    .bind(&payload.set_category_id)
+    .bind(payload.set_payee.as_deref().map(str::trim))
+    .bind(SqlJson(normalized_tags(&payload)))
+    .bind(payload.set_flagged)
+    .bind(payload.set_excluded)
+    .bind(payload.stop_processing)
+    .bind(Utc::now())
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success(rule)))
+}
+
+/// Get rule by ID
+#[utoipa::path(
+    get,
+    path = "/api/rules/{id}",
+    params(
+        ("id" = String, Path, description = "Rule ID")
+    ),
+    responses(
+        (status = 200, description = "Rule found", body = Rule),
+        (status = 404, description = "Rule not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_rule(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<Rule> {
+    let rule = sqlx::query_as::<_, Rule>("SELECT * FROM rules WHERE id = ?")
+        .bind(&id)
+        .fetch_optional(&app_state.pool)
+        .await?
+        .ok_or(AppError::NotFound("Rule"))?;
+
+    Ok(Json(ApiResponse::success(rule)))
+}
+
+/// Replace a rule
+#[utoipa::path(
+    put,
+    path = "/api/rules/{id}",
+    params(
+        ("id" = String, Path, description = "Rule ID")
+    ),
+    request_body = RuleInput,
+    responses(
+        (status = 200, description = "Rule updated", body = Rule),
+        (status = 400, description = "Malformed request body"),
+        (status = 404, description = "Rule not found"),
+        (status = 422, description = "Invalid request data"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_rule(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+    ApiJson(payload): ApiJson<RuleInput>,
+) -> ApiResult<Rule> {
+    payload.validate()?;
+
+    let mut tx = app_state.pool.begin().await?;
+    check_references(&mut tx, &payload).await?;
+
+    let rule = sqlx::query_as::<_, Rule>(
+        r#"
+        UPDATE rules SET
+            name = ?, priority = ?, enabled = ?, description_pattern = ?, payee_pattern = ?,
+            memo_pattern = ?, min_amount = ?, max_amount = ?, account_id = ?, sign = ?,
+            set_category_id = ?, set_payee = ?, add_tags = ?, set_flagged = ?, set_excluded = ?,
+            stop_processing = ?
+        WHERE id = ?
+        RETURNING *
+        "#,
+    )
+    .bind(payload.name.trim())
+    .bind(payload.priority)
+    .bind(payload.enabled)
+    .bind(&payload.description_pattern)
+    .bind(&payload.payee_pattern)
+    .bind(&payload.memo_pattern)
+    .bind(payload.min_amount.threshold())
+    .bind(payload.max_amount.threshold())
+    .bind(&payload.account_id)
+    .bind(payload.sign.value())
+    .bind(&payload.set_category_id)
+    .bind(payload.set_payee.as_deref().map(str::trim))
+    .bind(SqlJson(normalized_tags(&payload)))
+    .bind(payload.set_flagged)
+    .bind(payload.set_excluded)
+    .bind(payload.stop_processing)
+    .bind(&id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(AppError::NotFound("Rule"))?;
+
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success(rule)))
+}
+
+/// Delete a rule
+///
+/// Changes the rule already made to transactions are kept.
+#[utoipa::path(
+    delete,
+    path = "/api/rules/{id}",
+    params(
+        ("id" = String, Path, description = "Rule ID")
+    ),
+    responses(
+        (status = 204, description = "Rule deleted"),
+        (status = 404, description = "Rule not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_rule(
+    State(app_state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let result = sqlx::query("DELETE FROM rules WHERE id = ?")
+        .bind(&id)
+        .execute(&app_state.pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Rule"));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Re-apply rules to existing transactions
+///
+/// With `dry_run` the changes are only reported. Locked categories are never
+/// changed.
+#[utoipa::path(
+    post,
+    path = "/api/rules/apply",
+    request_body = ApplyRulesRequest,
+    responses(
+        (status = 200, description = "Changes made, or that would be made for a dry run", body = ApplyRulesResult),
+        (status = 400, description = "Malformed request body"),
+        (status = 422, description = "Unknown rule_id"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn apply_rules(
+    State(app_state): State<AppState>,
+    ApiJson(payload): ApiJson<ApplyRulesRequest>,
+) -> ApiResult<ApplyRulesResult> {
+    let mut tx = app_state.pool.begin().await?;
+
+    if let Some(rule_id) = &payload.rule_id {
+        let exists = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM rules WHERE id = ?")
+            .bind(rule_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        if exists == 0 {
+            return Err(AppError::invalid_field("rule_id", "rule does not exist"));
+        }
+    }
+
+    let result = rules::apply_rules(&mut tx, &payload).await?;
+
+    if payload.dry_run {
+        tx.rollback().await?;
+    } else {
+        classifier::sync_model(&mut tx).await?;
+        tx.commit().await?;
+    }
+
+    Ok(Json(ApiResponse::success(result)))
+}
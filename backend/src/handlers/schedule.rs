@@ -0,0 +1,84 @@
+use axum::{Json, extract::State};
+
+use crate::app_state::AppState;
+use crate::error::ApiResult;
+use crate::extract::ApiJson;
+use crate::models::*;
+use crate::scheduler::{SyncSchedule, SyncScheduleState};
+use crate::validation::Validate;
+
+/// Get the sync schedule and its state
+#[utoipa::path(
+    get,
+    path = "/api/sync/schedule",
+    responses(
+        (status = 200, description = "Sync schedule", body = SyncScheduleState)
+    )
+)]
+pub async fn get_sync_schedule(State(app_state): State<AppState>) -> ApiResult<SyncScheduleState> {
+    let state = app_state.scheduler.state();
+
+    Ok(Json(ApiResponse::success(state)))
+}
+
+/// Replace the sync schedule
+///
+/// The schedule is saved and overrides the `SYNC_*` environment variables
+/// until it is reset.
+#[utoipa::path(
+    put,
+    path = "/api/sync/schedule",
+    request_body = SyncSchedule,
+    responses(
+        (status = 200, description = "Schedule updated", body = SyncScheduleState),
+        (status = 400, description = "Malformed request body"),
+        (status = 422, description = "Invalid interval, cron expression or quiet hours"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_sync_schedule(
+    State(app_state): State<AppState>,
+    ApiJson(payload): ApiJson<SyncSchedule>,
+) -> ApiResult<SyncScheduleState> {
+    payload.validate()?;
+
+    let state = app_state.scheduler.set_schedule(Some(payload)).await?;
+
+    Ok(Json(ApiResponse::success(state)))
+}
+
+/// Reset the sync schedule to the environment's
+#[utoipa::path(
+    delete,
+    path = "/api/sync/schedule",
+    responses(
+        (status = 200, description = "Schedule reset", body = SyncScheduleState),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn reset_sync_schedule(
+    State(app_state): State<AppState>,
+) -> ApiResult<SyncScheduleState> {
+    let state = app_state.scheduler.set_schedule(None).await?;
+
+    Ok(Json(ApiResponse::success(state)))
+}
+
+/// Resume scheduled syncs paused after SimpleFin rejected the credentials
+///
+/// Connections marked as needing reauthentication are tried again too.
+#[utoipa::path(
+    post,
+    path = "/api/sync/schedule/resume",
+    responses(
+        (status = 200, description = "Scheduling resumed", body = SyncScheduleState),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn resume_sync_schedule(
+    State(app_state): State<AppState>,
+) -> ApiResult<SyncScheduleState> {
+    let state = app_state.scheduler.resume().await?;
+
+    Ok(Json(ApiResponse::success(state)))
+}
@@ -0,0 +1,76 @@
+use axum::{Json, extract::State};
+use sqlx::{QueryBuilder, Sqlite};
+
+use crate::app_state::AppState;
+use crate::classifier::{self, Classifier};
+use crate::error::{ApiResult, AppError};
+use crate::extract::ApiQuery;
+use crate::models::*;
+
+const DEFAULT_SUGGESTION_LIMIT: u32 = 100;
+
+/// Suggest categories for uncategorized transactions
+///
+/// Predictions are learned from manually and rule-categorized transactions.
+/// Transactions with a locked category are skipped.
+#[utoipa::path(
+    get,
+    path = "/api/transactions/suggestions",
+    params(SuggestionQuery),
+    responses(
+        (status = 200, description = "Suggestions, most confident first", body = Vec<CategorySuggestion>),
+        (status = 400, description = "Malformed query parameters"),
+        (status = 422, description = "Invalid query parameters"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_category_suggestions(
+    State(app_state): State<AppState>,
+    ApiQuery(query): ApiQuery<SuggestionQuery>,
+) -> ApiResult<Vec<CategorySuggestion>> {
+    let min_confidence = query.min_confidence.unwrap_or(0.0);
+    if !(0.0..=1.0).contains(&min_confidence) {
+        return Err(AppError::invalid_field(
+            "min_confidence",
+            "must be between 0 and 1",
+        ));
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_SUGGESTION_LIMIT) as usize;
+
+    let mut tx = app_state.pool.begin().await?;
+    classifier::sync_model(&mut tx).await?;
+    let classifier = Classifier::load(&mut tx).await?;
+
+    let mut builder: QueryBuilder<Sqlite> = QueryBuilder::new(
+        "SELECT * FROM transactions WHERE category_id IS NULL AND NOT category_locked",
+    );
+    if let Some(account_id) = &query.account_id {
+        builder
+            .push(" AND account_id = ")
+            .push_bind(account_id.clone());
+    }
+    builder.push(" ORDER BY transaction_date DESC, created_at DESC, id");
+    let transactions = builder
+        .build_query_as::<Transaction>()
+        .fetch_all(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    let mut suggestions: Vec<CategorySuggestion> = transactions
+        .into_iter()
+        .filter_map(|transaction| {
+            let suggestion = classifier.suggest(&transaction)?;
+            (suggestion.confidence >= min_confidence).then_some(CategorySuggestion {
+                transaction_id: transaction.id,
+                description: transaction.description,
+                category_id: suggestion.category_id,
+                confidence: suggestion.confidence,
+            })
+        })
+        .collect();
+    suggestions.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    suggestions.truncate(limit);
+
+    Ok(Json(ApiResponse::success(suggestions)))
+}
@@ -0,0 +1,59 @@
+use axum::{Json, extract::State};
+
+use crate::app_state::AppState;
+use crate::error::{ApiResult, AppError};
+use crate::extract::ApiQuery;
+use crate::models::*;
+use crate::sync_runs::{self, SyncRun, SyncStatus};
+
+const DEFAULT_SYNC_RUN_LIMIT: u32 = 50;
+const MAX_SYNC_RUN_LIMIT: u32 = 500;
+
+/// List past and running syncs, newest first
+#[utoipa::path(
+    get,
+    path = "/api/sync/runs",
+    params(SyncRunQuery),
+    responses(
+        (status = 200, description = "Sync runs, newest first", body = Vec<SyncRun>),
+        (status = 400, description = "Malformed query parameters"),
+        (status = 422, description = "Invalid query parameters"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_sync_runs(
+    State(app_state): State<AppState>,
+    ApiQuery(query): ApiQuery<SyncRunQuery>,
+) -> ApiResult<Vec<SyncRun>> {
+    let limit = query.limit.unwrap_or(DEFAULT_SYNC_RUN_LIMIT);
+    if !(1..=MAX_SYNC_RUN_LIMIT).contains(&limit) {
+        return Err(AppError::invalid_field(
+            "limit",
+            format!("must be between 1 and {}", MAX_SYNC_RUN_LIMIT),
+        ));
+    }
+
+    let runs = sync_runs::list(&app_state.pool, query.status, limit).await?;
+
+    Ok(Json(ApiResponse::success(runs)))
+}
+
+/// Get the last successful and failed syncs, the running sync and the next scheduled one
+///
+/// `paused` is set when scheduled syncs stopped because SimpleFin rejected
+/// the credentials.
+#[utoipa::path(
+    get,
+    path = "/api/sync/status",
+    responses(
+        (status = 200, description = "Sync status", body = SyncStatus),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_sync_status(State(app_state): State<AppState>) -> ApiResult<SyncStatus> {
+    let mut status = sync_runs::status(&app_state.pool).await?;
+    status.next_scheduled_at = app_state.scheduler.next_run_at();
+    status.paused = app_state.scheduler.paused();
+
+    Ok(Json(ApiResponse::success(status)))
+}
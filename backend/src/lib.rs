@@ -1,15 +1,38 @@
+pub mod backfill;
+pub mod categories;
+pub mod classifier;
+pub mod connections;
+pub mod credentials;
+pub mod cron;
 pub mod database;
+pub mod error;
+pub mod exchange_rates;
+pub mod extract;
 pub mod handlers;
+pub mod merchants;
 pub mod models;
+pub mod money;
+pub mod provider;
+pub mod reports;
+pub mod routes;
+pub mod rules;
 pub mod simplefin;
 pub mod sync;
+pub mod sync_runs;
+pub mod transaction_query;
+pub mod validation;
 pub mod scheduler;
 pub mod app_state;
 
 use utoipa::OpenApi;
 
+use crate::backfill::{BackfillJob, BackfillStatus};
+use crate::error::{ApiErrorBody, FieldError};
 use crate::models::*;
-use crate::sync::SyncStats;
+use crate::money::Money;
+use crate::scheduler::{QuietHours, SyncPause, SyncSchedule, SyncScheduleState};
+use crate::sync::{ConnectionFailure, PendingMerge, SyncIssue, SyncStats};
+use crate::sync_runs::{SyncRun, SyncRunStatus, SyncStatus, SyncTrigger};
 
 #[derive(OpenApi)]
 #[openapi(
@@ -17,16 +40,86 @@ use crate::sync::SyncStats;
         handlers::get_accounts,
         handlers::create_account,
         handlers::get_account,
+        handlers::update_account,
+        handlers::delete_account,
         handlers::get_account_transactions,
+        handlers::get_account_holdings,
+        handlers::query_transactions,
         handlers::create_transaction,
+        handlers::get_transaction,
+        handlers::update_transaction,
+        handlers::delete_transaction,
+        handlers::get_category_suggestions,
+        handlers::get_categories,
+        handlers::create_category,
+        handlers::get_category,
+        handlers::update_category,
+        handlers::delete_category,
+        handlers::merge_category,
+        handlers::get_rules,
+        handlers::create_rule,
+        handlers::get_rule,
+        handlers::update_rule,
+        handlers::delete_rule,
+        handlers::apply_rules,
+        handlers::get_merchants,
+        handlers::create_merchant,
+        handlers::get_merchant,
+        handlers::update_merchant,
+        handlers::delete_merchant,
+        handlers::merge_merchant,
+        handlers::get_exchange_rates,
+        handlers::upsert_exchange_rates,
+        handlers::import_exchange_rates,
+        handlers::update_exchange_rate,
+        handlers::delete_exchange_rate,
+        handlers::get_net_worth,
+        handlers::get_monthly_cash_flow,
+        handlers::get_merchant_spending,
+        handlers::get_connections,
+        handlers::create_connection,
+        handlers::get_connection,
+        handlers::update_connection,
+        handlers::delete_connection,
+        handlers::claim_simplefin_connection,
         handlers::trigger_sync,
+        handlers::get_sync_runs,
+        handlers::get_sync_status,
+        handlers::get_sync_schedule,
+        handlers::update_sync_schedule,
+        handlers::reset_sync_schedule,
+        handlers::resume_sync_schedule,
+        handlers::start_backfill,
+        handlers::get_backfills,
+        handlers::get_backfill,
+        handlers::cancel_backfill,
     ),
     components(
-        schemas(Account, CreateAccountRequest, Transaction, CreateTransactionRequest, BalanceHistory, SyncStats)
+        schemas(
+            Money,
+            Account, AccountType, CreateAccountRequest, UpdateAccountRequest, Holding,
+            Category, CategoryKind, CreateCategoryRequest, UpdateCategoryRequest, MergeCategoryRequest,
+            AmountSign, Rule, RuleInput, ApplyRulesRequest, RuleFields, RuleChange, ApplyRulesResult,
+            Merchant, MerchantInput, MergeMerchantRequest, MerchantSpending,
+            Transaction, TransactionSort, CategorySource, CategorySuggestion, CreateTransactionRequest, UpdateTransactionRequest, BalanceHistory,
+            ExchangeRate, ExchangeRateInput, UpdateExchangeRateRequest, ConvertedAmount,
+            CurrencyTotal, NetWorthReport, MonthlyCashFlow, MonthlyReport,
+            Connection, CreateConnectionRequest, UpdateConnectionRequest, ClaimConnectionRequest,
+            SyncStats, PendingMerge, SyncIssue, ConnectionFailure, SyncRun, SyncRunStatus, SyncTrigger, SyncStatus,
+            SyncSchedule, QuietHours, SyncPause, SyncScheduleState,
+            BackfillRequest, BackfillJob, BackfillStatus,
+            ApiErrorBody, FieldError
+        )
     ),
     tags(
         (name = "accounts", description = "Account management endpoints"),
         (name = "transactions", description = "Transaction management endpoints"),
+        (name = "categories", description = "Category management endpoints"),
+        (name = "rules", description = "Categorization rule endpoints"),
+        (name = "merchants", description = "Merchant management endpoints"),
+        (name = "exchange-rates", description = "Exchange rate management endpoints"),
+        (name = "reports", description = "Aggregated reporting endpoints"),
+        (name = "connections", description = "Bank data connection endpoints"),
         (name = "sync", description = "Data synchronization endpoints")
     ),
     info(
@@ -1,18 +1,65 @@
 use anyhow::Result;
-use axum::{
-    Router,
-    routing::{get, post},
-};
 use std::{env, sync::Arc};
-use tower_http::cors::CorsLayer;
-use tracing_subscriber;
-use utoipa::OpenApi;
-use utoipa_swagger_ui::SwaggerUi;
 
 use budget_tracker_backend::{
-    ApiDoc, app_state::AppState, database, handlers::*, scheduler::*, sync::SyncService,
+    app_state::AppState, connections,
+    credentials::{CredentialCipher, Secret},
+    database, error::AppError, merchants, routes, scheduler::*,
+    sync::{SyncService, SyncWindow},
+    sync_runs,
+    validation::Validate,
 };
 
+/// Reads a whole number of days from the environment, or returns `default`.
+fn env_days(name: &str, default: chrono::Duration) -> Result<chrono::Duration, String> {
+    match env::var(name) {
+        Ok(value) => match value.parse::<u32>() {
+            Ok(days) => Ok(chrono::Duration::days(days.into())),
+            Err(_) => Err(format!("{} must be a whole number of days", name)),
+        },
+        Err(_) => Ok(default),
+    }
+}
+
+/// Reads the sync schedule from `SYNC_INTERVAL_MINUTES`, `SYNC_CRON` and `SYNC_QUIET_HOURS`.
+fn env_schedule() -> Result<SyncSchedule, String> {
+    let interval_minutes = match env::var("SYNC_INTERVAL_MINUTES") {
+        Ok(value) => value
+            .parse::<u32>()
+            .map_err(|_| "SYNC_INTERVAL_MINUTES must be a whole number of minutes".to_string())?,
+        Err(_) => 5,
+    };
+    let quiet_hours = match env::var("SYNC_QUIET_HOURS") {
+        Ok(value) => Some(
+            value
+                .parse::<QuietHours>()
+                .map_err(|e| format!("SYNC_QUIET_HOURS: {}", e))?,
+        ),
+        Err(_) => None,
+    };
+    let schedule = SyncSchedule {
+        interval_minutes,
+        cron: env::var("SYNC_CRON").ok(),
+        quiet_hours,
+    };
+
+    match schedule.validate() {
+        Err(AppError::Validation(errors)) => Err(errors
+            .iter()
+            .map(|e| {
+                let variable = match e.field.as_str() {
+                    "interval_minutes" => "SYNC_INTERVAL_MINUTES",
+                    "cron" => "SYNC_CRON",
+                    _ => "SYNC_QUIET_HOURS",
+                };
+                format!("{}: {}", variable, e.message)
+            })
+            .collect::<Vec<_>>()
+            .join("; ")),
+        _ => Ok(schedule),
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Load environment variables first
@@ -23,59 +70,96 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let database_url =
         env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite:budget_tracker.db".to_string());
     let port = env::var("SERVER_PORT").unwrap_or_else(|_| "3001".to_string());
+    let base_currency = env::var("BASE_CURRENCY").unwrap_or_else(|_| "USD".to_string());
+
+    // SimpleFin access URL, stored as a connection next to those added through the API
+    let env_access_url = env::var("SIMPLEFIN_ACCESS_URL").ok().map(Secret::new);
 
-    // SimpleFin integration - optional, warn if not present
-    let simplefin_access_url = match env::var("SIMPLEFIN_ACCESS_URL") {
-        Ok(url) => {
-            tracing::info!("SimpleFin integration enabled");
-            url
-        }
-        Err(_) => {
-            panic!("SIMPLEFIN_ACCESS_URL not set. Please set it in the environment variables.");
-        }
+    // Key that encrypts stored credentials
+    let cipher = Arc::new(CredentialCipher::from_env()?);
+
+    // Suggested categories at or above this confidence are applied during sync
+    let auto_categorize_threshold = match env::var("AUTO_CATEGORIZE_THRESHOLD") {
+        Ok(value) => match value.parse::<f64>() {
+            Ok(threshold) if (0.0..=1.0).contains(&threshold) => Some(threshold),
+            _ => return Err("AUTO_CATEGORIZE_THRESHOLD must be a number between 0 and 1".into()),
+        },
+        Err(_) => None,
+    };
+
+    // How much history each sync requests from SimpleFin
+    let default_window = SyncWindow::default();
+    let sync_window = SyncWindow {
+        overlap: env_days("SYNC_OVERLAP_DAYS", default_window.overlap)?,
+        history: env_days("SYNC_HISTORY_DAYS", default_window.history)?,
+        chunk: env_days("SYNC_CHUNK_DAYS", default_window.chunk)?,
     };
+    if sync_window.chunk.is_zero() {
+        return Err("SYNC_CHUNK_DAYS must be at least 1".into());
+    }
+
+    // When scheduled syncs run, unless changed through the API
+    let sync_schedule = env_schedule()?;
 
     // Create database connection pool
     let pool = database::create_pool(&database_url).await?;
     tracing::info!("Database connected successfully");
 
+    // Assign merchants to transactions recorded before merchants existed
+    let mut tx = pool.begin().await?;
+    let assigned = merchants::assign_all(&mut tx, true).await?;
+    tx.commit().await?;
+    if assigned > 0 {
+        tracing::info!("Assigned merchants to {} transactions", assigned);
+    }
+
+    // Access URLs stored before credentials were encrypted
+    let encrypted = connections::encrypt_plaintext(&pool, &cipher).await?;
+    if encrypted > 0 {
+        tracing::info!("Encrypted the credentials of {} connections", encrypted);
+    }
+    if let Some(access_url) = &env_access_url {
+        connections::seed(&pool, &cipher, access_url).await?;
+    }
+
+    // Syncs still marked running were cut short when the server last stopped
+    let interrupted = sync_runs::fail_interrupted(&pool).await?;
+    if interrupted > 0 {
+        tracing::warn!("Marked {} interrupted sync runs as failed", interrupted);
+    }
+
     // Initialize SimpleFin sync service
-    let sync_service = match SyncService::new(pool.clone(), simplefin_access_url) {
-        Ok(service) => {
-            let service_arc = Arc::new(service);
-
-            // Perform initial sync
-            perform_initial_sync(&service_arc).await?;
-
-            // Start background scheduler (5 minutes)
-            let scheduler = SyncScheduler::new(service_arc.clone(), 5);
-            scheduler.start_background_sync().await?;
-
-            Some(service_arc)
-        }
-        Err(e) => {
-            tracing::error!("Failed to initialize SimpleFin service: {}", e);
-            tracing::warn!("Server will continue without SimpleFin integration");
-            None
-        }
+    let service = SyncService::new(pool.clone(), cipher.clone()).with_window(sync_window);
+    let service = match auto_categorize_threshold {
+        Some(threshold) => service.with_auto_categorize(threshold),
+        None => service,
     };
+    let sync_service = Arc::new(service);
+    let scheduler = SyncScheduler::load(sync_service.clone(), pool.clone(), sync_schedule).await?;
+
+    let connections = connections::enabled(&pool).await?;
+    if connections.is_empty() {
+        // Manual accounts and transactions work without a provider; adding a
+        // connection through the API starts the scheduler
+        tracing::warn!("No SimpleFin connection is configured; running without sync");
+    } else {
+        tracing::info!(
+            "SimpleFin integration enabled with {} connections",
+            connections.len()
+        );
+
+        // Perform initial sync
+        perform_initial_sync(&scheduler).await?;
+
+        // Start background scheduler
+        scheduler.start();
+    }
 
     // Create application state
-    let app_state = AppState::new(pool, sync_service);
+    let app_state = AppState::new(pool, sync_service, scheduler, cipher, base_currency);
 
     // Create router
-    let app = Router::new()
-        .route("/api/accounts", get(get_accounts).post(create_account))
-        .route("/api/accounts/:id", get(get_account))
-        .route(
-            "/api/accounts/:id/transactions",
-            get(get_account_transactions),
-        )
-        .route("/api/transactions", post(create_transaction))
-        .route("/api/sync", post(trigger_sync))
-        .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
-        .layer(CorsLayer::permissive())
-        .with_state(app_state);
+    let app = routes::router(app_state);
 
     // Start server
     let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", port)).await?;
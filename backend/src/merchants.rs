@@ -0,0 +1,261 @@
+use anyhow::Result;
+use chrono::Utc;
+use regex::Regex;
+use sqlx::SqliteConnection;
+use uuid::Uuid;
+
+use crate::models::{Merchant, Transaction};
+use crate::rules::compile_pattern;
+
+/// Card processors that put their own code before the merchant, as in
+/// `SQ *BLUE BOTTLE`. Any other text before a `*` is taken to be the merchant.
+const PROCESSOR_PREFIXES: &[&str] = &[
+    "SQ", "TST", "SP", "PP", "PAYPAL", "GOOGLE", "IC", "BT", "DD",
+];
+
+/// Words banks put in front of the merchant name.
+const NOISE_WORDS: &[&str] = &[
+    "POS",
+    "DEBIT",
+    "CARD",
+    "PURCHASE",
+    "CHECKCARD",
+    "RECURRING",
+    "ACH",
+    "VISA",
+];
+
+const US_STATES: &[&str] = &[
+    "AL", "AK", "AZ", "AR", "CA", "CO", "CT", "DE", "DC", "FL", "GA", "HI", "ID", "IL", "IN", "IA",
+    "KS", "KY", "LA", "ME", "MD", "MA", "MI", "MN", "MS", "MO", "MT", "NE", "NV", "NH", "NJ", "NM",
+    "NY", "NC", "ND", "OH", "OK", "OR", "PA", "RI", "SC", "SD", "TN", "TX", "UT", "VT", "VA", "WA",
+    "WV", "WI", "WY",
+];
+
+/// Cleans a raw payee or description into a merchant name, e.g.
+/// `SQ *BLUE BOTTLE 0423 OAKLAND CA` becomes `Blue Bottle`.
+///
+/// Processor prefixes, store numbers and everything after them, and a
+/// trailing city and state are dropped. Returns `None` when nothing is left.
+pub fn canonical_name(text: &str) -> Option<String> {
+    let text = text.trim().to_uppercase();
+    let text = match text.split_once('*') {
+        Some((prefix, rest)) if PROCESSOR_PREFIXES.contains(&prefix.trim()) => rest.to_string(),
+        Some((prefix, _)) => prefix.to_string(),
+        None => text,
+    };
+
+    let mut words: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        if word.starts_with('#') || word.chars().any(|c| c.is_ascii_digit()) {
+            break;
+        }
+        let word: String = word
+            .chars()
+            .filter(|c| c.is_alphanumeric() || *c == '&' || *c == '\'')
+            .collect();
+        if word.is_empty() || (words.is_empty() && NOISE_WORDS.contains(&word.as_str())) {
+            continue;
+        }
+        words.push(word);
+    }
+
+    // "WHOLE FOODS OAKLAND CA": drop the state and the (one-word) city before it
+    if words.len() >= 4
+        && words
+            .last()
+            .is_some_and(|w| US_STATES.contains(&w.as_str()))
+    {
+        words.truncate(words.len() - 2);
+    }
+
+    if words.is_empty() {
+        return None;
+    }
+    Some(
+        words
+            .iter()
+            .map(|word| title_case(word))
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
+fn title_case(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+/// Alias pattern that catches future transactions of a merchant merged into another.
+pub fn name_alias(name: &str) -> String {
+    format!(r"\b{}\b", regex::escape(name))
+}
+
+/// Loads the aliases of each merchant, sorted by pattern.
+pub async fn load_aliases(conn: &mut SqliteConnection, merchants: &mut [Merchant]) -> Result<()> {
+    for merchant in merchants.iter_mut() {
+        merchant.aliases = sqlx::query_scalar::<_, String>(
+            "SELECT pattern FROM merchant_aliases WHERE merchant_id = ? ORDER BY pattern",
+        )
+        .bind(&merchant.id)
+        .fetch_all(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Resolves transactions to merchants: alias patterns first, then the
+/// cleaned-up name, creating a merchant for names not seen before.
+pub struct MerchantMatcher {
+    /// (pattern, merchant ID), longest pattern first so the most specific alias wins
+    aliases: Vec<(Regex, String)>,
+}
+
+impl MerchantMatcher {
+    pub async fn load(conn: &mut SqliteConnection) -> Result<Self> {
+        let rows = sqlx::query_as::<_, (String, String)>(
+            "SELECT pattern, merchant_id FROM merchant_aliases ORDER BY LENGTH(pattern) DESC, pattern",
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let aliases = rows
+            .into_iter()
+            .filter_map(|(pattern, merchant_id)| {
+                compile_pattern(&pattern)
+                    .inspect_err(|e| tracing::warn!("Skipping merchant alias {:?}: {}", pattern, e))
+                    .ok()
+                    .map(|regex| (regex, merchant_id))
+            })
+            .collect();
+
+        Ok(Self { aliases })
+    }
+
+    pub async fn resolve(
+        &self,
+        conn: &mut SqliteConnection,
+        description: &str,
+        payee: Option<&str>,
+    ) -> Result<Option<String>> {
+        let payee = payee.map(str::trim).filter(|payee| !payee.is_empty());
+
+        for (pattern, merchant_id) in &self.aliases {
+            if payee.is_some_and(|payee| pattern.is_match(payee)) || pattern.is_match(description) {
+                return Ok(Some(merchant_id.clone()));
+            }
+        }
+
+        let Some(name) = payee
+            .and_then(canonical_name)
+            .or_else(|| canonical_name(description))
+        else {
+            return Ok(None);
+        };
+
+        let existing = sqlx::query_scalar::<_, String>(
+            "SELECT id FROM merchants WHERE name = ? COLLATE NOCASE",
+        )
+        .bind(&name)
+        .fetch_optional(&mut *conn)
+        .await?;
+        if let Some(id) = existing {
+            return Ok(Some(id));
+        }
+
+        let id = Uuid::new_v4().to_string();
+        sqlx::query("INSERT INTO merchants (id, name, created_at) VALUES (?, ?, ?)")
+            .bind(&id)
+            .bind(&name)
+            .bind(Utc::now())
+            .execute(&mut *conn)
+            .await?;
+        Ok(Some(id))
+    }
+
+    /// Resolves and saves the merchant of one transaction, returning it.
+    pub async fn assign(
+        &self,
+        conn: &mut SqliteConnection,
+        transaction: &Transaction,
+    ) -> Result<Option<String>> {
+        let merchant_id = self
+            .resolve(conn, &transaction.description, transaction.payee.as_deref())
+            .await?;
+        if merchant_id != transaction.merchant_id {
+            sqlx::query("UPDATE transactions SET merchant_id = ? WHERE id = ?")
+                .bind(&merchant_id)
+                .bind(&transaction.id)
+                .execute(&mut *conn)
+                .await?;
+        }
+        Ok(merchant_id)
+    }
+}
+
+/// Assigns a merchant to every transaction, or only to those without one.
+///
+/// Returns the number of transactions whose merchant changed.
+pub async fn assign_all(conn: &mut SqliteConnection, only_missing: bool) -> Result<u32> {
+    let matcher = MerchantMatcher::load(conn).await?;
+    let transactions = sqlx::query_as::<_, Transaction>(
+        "SELECT * FROM transactions WHERE NOT ? OR merchant_id IS NULL",
+    )
+    .bind(only_missing)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut changed = 0;
+    for transaction in &transactions {
+        if matcher.assign(conn, transaction).await? != transaction.merchant_id {
+            changed += 1;
+        }
+    }
+    Ok(changed)
+}
+
+/// Merges `source` into `target`: transactions and aliases move over, the
+/// source name becomes an alias so future transactions follow, and `source`
+/// is deleted. The caller provides the transaction that makes this atomic.
+pub async fn merge(conn: &mut SqliteConnection, source: &str, target: &str) -> Result<()> {
+    sqlx::query("UPDATE transactions SET merchant_id = ? WHERE merchant_id = ?")
+        .bind(target)
+        .bind(source)
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query(
+        r#"
+        INSERT OR IGNORE INTO merchant_aliases (merchant_id, pattern)
+        SELECT ?, pattern FROM merchant_aliases WHERE merchant_id = ?
+        "#,
+    )
+    .bind(target)
+    .bind(source)
+    .execute(&mut *conn)
+    .await?;
+
+    let name = sqlx::query_scalar::<_, String>("SELECT name FROM merchants WHERE id = ?")
+        .bind(source)
+        .fetch_one(&mut *conn)
+        .await?;
+    sqlx::query("INSERT OR IGNORE INTO merchant_aliases (merchant_id, pattern) VALUES (?, ?)")
+        .bind(target)
+        .bind(name_alias(&name))
+        .execute(&mut *conn)
+        .await?;
+
+    // The source's own aliases are removed by ON DELETE CASCADE
+    sqlx::query("DELETE FROM merchants WHERE id = ?")
+        .bind(source)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
@@ -1,76 +1,861 @@
-use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use utoipa::{IntoParams, ToSchema};
 use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::types::Json;
 
-#[derive(Debug, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+use crate::credentials::Secret;
+use crate::error::ApiErrorBody;
+use crate::money::Money;
+use crate::sync_runs::SyncRunStatus;
+use crate::validation::Lenient;
+
+/// Kind of account. Stored as lowercase text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum AccountType {
+    Checking,
+    Savings,
+    Credit,
+    Investment,
+    Loan,
+    Cash,
+    Other,
+}
+
+#[derive(Debug, Clone, ToSchema, sqlx::FromRow)]
 pub struct Account {
     pub id: String,
+    /// Name as entered for manual accounts, or as last reported by the provider
     pub name: String,
+    /// User override shown instead of `name`; never touched by sync
+    pub display_name: Option<String>,
     pub institution: String,
-    pub account_type: String,
-    pub balance: f64,
+    pub account_type: AccountType,
+    pub balance: Money,
+    /// ISO 4217 code (or the provider's custom currency identifier)
+    pub currency: String,
     #[schema(value_type = String, format = DateTime)]
     pub last_updated: DateTime<Utc>,
     #[schema(value_type = String, format = DateTime)]
     pub created_at: DateTime<Utc>,
-    // SimpleFin integration fields
-    pub simplefin_id: Option<String>,
-    pub available_balance: Option<f64>,
+    // Bank-data provider fields
+    /// Provider the account is synced from, e.g. `simplefin`; absent for manual accounts
+    pub provider: Option<String>,
+    /// The provider's ID for the account
+    pub external_id: Option<String>,
+    pub available_balance: Option<Money>,
     pub is_credit_card: Option<bool>,
+    /// Start of the last sync that fetched this account's transactions
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub last_synced_at: Option<DateTime<Utc>>,
+    /// What the provider reported about this account in the last sync, e.g.
+    /// that the bank needs reauthentication
+    pub sync_error: Option<String>,
+    /// Connection the account was last synced through
+    pub connection_id: Option<String>,
+    /// Balance in the requested base currency; absent when no rate is known
+    #[sqlx(skip)]
+    pub converted_balance: Option<ConvertedAmount>,
+}
+
+// Amounts are written with the fractional digits of the account's currency
+impl Serialize for Account {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Account", 16)?;
+        state.serialize_field("id", &self.id)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("display_name", &self.display_name)?;
+        state.serialize_field("institution", &self.institution)?;
+        state.serialize_field("account_type", &self.account_type)?;
+        state.serialize_field("balance", &self.balance.display(&self.currency))?;
+        state.serialize_field("currency", &self.currency)?;
+        state.serialize_field("last_updated", &self.last_updated)?;
+        state.serialize_field("created_at", &self.created_at)?;
+        state.serialize_field("provider", &self.provider)?;
+        state.serialize_field("external_id", &self.external_id)?;
+        state.serialize_field(
+            "available_balance",
+            &self.available_balance.map(|balance| balance.display(&self.currency)),
+        )?;
+        state.serialize_field("is_credit_card", &self.is_credit_card)?;
+        state.serialize_field("last_synced_at", &self.last_synced_at)?;
+        state.serialize_field("sync_error", &self.sync_error)?;
+        state.serialize_field("connection_id", &self.connection_id)?;
+        match &self.converted_balance {
+            Some(converted) => state.serialize_field("converted_balance", converted)?,
+            None => state.skip_field("converted_balance")?,
+        }
+        state.end()
+    }
+}
+
+/// A position in an investment account, as last reported by the provider.
+#[derive(Debug, ToSchema, sqlx::FromRow)]
+pub struct Holding {
+    pub id: String,
+    pub account_id: String,
+    /// The provider's ID for the holding
+    pub external_id: String,
+    pub symbol: Option<String>,
+    pub description: String,
+    pub shares: f64,
+    pub market_value: Money,
+    pub cost_basis: Option<Money>,
+    pub currency: String,
+    #[schema(value_type = String, format = DateTime)]
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Serialize for Holding {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Holding", 10)?;
+        state.serialize_field("id", &self.id)?;
+        state.serialize_field("account_id", &self.account_id)?;
+        state.serialize_field("external_id", &self.external_id)?;
+        state.serialize_field("symbol", &self.symbol)?;
+        state.serialize_field("description", &self.description)?;
+        state.serialize_field("shares", &self.shares)?;
+        state.serialize_field("market_value", &self.market_value.display(&self.currency))?;
+        state.serialize_field(
+            "cost_basis",
+            &self.cost_basis.map(|cost| cost.display(&self.currency)),
+        )?;
+        state.serialize_field("currency", &self.currency)?;
+        state.serialize_field("updated_at", &self.updated_at)?;
+        state.end()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CreateAccountRequest {
+    #[serde(default)]
     pub name: String,
+    #[serde(default)]
     pub institution: String,
-    pub account_type: String,
-    pub balance: f64,
+    #[serde(default)]
+    #[schema(value_type = AccountType)]
+    pub account_type: Lenient<AccountType>,
+    #[serde(default)]
+    #[schema(value_type = Money)]
+    pub balance: Lenient<Money>,
+    #[serde(default)]
+    pub currency: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+/// Partial update for an account. Omitted fields are left unchanged.
+///
+/// On provider-linked accounts `name` sets the display name, and the
+/// provider-owned `institution`, `balance` and `currency` cannot be edited.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateAccountRequest {
+    pub name: Option<String>,
+    pub institution: Option<String>,
+    #[serde(default)]
+    #[schema(value_type = Option<AccountType>)]
+    pub account_type: Lenient<Option<AccountType>>,
+    #[serde(default)]
+    #[schema(value_type = Option<Money>)]
+    pub balance: Lenient<Option<Money>>,
+    pub currency: Option<String>,
+}
+
+#[derive(Debug, ToSchema, sqlx::FromRow)]
 pub struct Transaction {
     pub id: String,
     pub account_id: String,
-    pub amount: f64,
+    pub amount: Money,
+    /// Always the currency of the owning account
+    pub currency: String,
+    /// Description as entered, or as reported by the provider
     pub description: String,
+    /// User override shown instead of `description`; never touched by sync
+    pub display_description: Option<String>,
     #[schema(value_type = String, format = Date)]
     pub transaction_date: NaiveDate,
+    pub category_id: Option<String>,
+    /// Full path of the category, such as `Food:Groceries`; read-only, set
+    /// from `category_id`
+    #[sqlx(default)]
+    #[schema(read_only)]
     pub category: Option<String>,
+    /// True once the user has set the category; automatic categorization skips it
+    pub category_locked: bool,
+    pub category_source: Option<CategorySource>,
+    /// Category this transaction currently contributes to the classifier;
+    /// internal, never serialized
+    pub trained_category_id: Option<String>,
+    pub note: Option<String>,
     #[schema(value_type = String, format = DateTime)]
     pub created_at: DateTime<Utc>,
-    // SimpleFin integration fields
-    pub simplefin_id: Option<String>,
+    // Bank-data provider fields
+    /// The provider's ID for the transaction, unique within the account
+    pub external_id: Option<String>,
     #[schema(value_type = String, format = DateTime)]
     pub posted_date: Option<DateTime<Utc>>,
     pub payee: Option<String>,
     pub memo: Option<String>,
     pub pending: Option<bool>,
+    /// Payee set by a rule, shown instead of `payee`
+    pub payee_override: Option<String>,
+    #[schema(value_type = Vec<String>)]
+    pub tags: Json<Vec<String>>,
+    pub flagged: bool,
+    /// Excluded transactions are left out of reports
+    pub excluded: bool,
+    /// Canonical merchant derived from the payee or description
+    pub merchant_id: Option<String>,
+}
+
+impl Serialize for Transaction {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Transaction", 23)?;
+        state.serialize_field("id", &self.id)?;
+        state.serialize_field("account_id", &self.account_id)?;
+        state.serialize_field("amount", &self.amount.display(&self.currency))?;
+        state.serialize_field("currency", &self.currency)?;
+        state.serialize_field("description", &self.description)?;
+        state.serialize_field("display_description", &self.display_description)?;
+        state.serialize_field("transaction_date", &self.transaction_date)?;
+        state.serialize_field("category_id", &self.category_id)?;
+        state.serialize_field("category", &self.category)?;
+        state.serialize_field("category_locked", &self.category_locked)?;
+        state.serialize_field("category_source", &self.category_source)?;
+        state.serialize_field("note", &self.note)?;
+        state.serialize_field("created_at", &self.created_at)?;
+        state.serialize_field("external_id", &self.external_id)?;
+        state.serialize_field("posted_date", &self.posted_date)?;
+        state.serialize_field("payee", &self.payee)?;
+        state.serialize_field("memo", &self.memo)?;
+        state.serialize_field("pending", &self.pending)?;
+        state.serialize_field("payee_override", &self.payee_override)?;
+        state.serialize_field("tags", &self.tags)?;
+        state.serialize_field("flagged", &self.flagged)?;
+        state.serialize_field("excluded", &self.excluded)?;
+        state.serialize_field("merchant_id", &self.merchant_id)?;
+        state.end()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CreateTransactionRequest {
+    #[serde(default)]
     pub account_id: String,
-    pub amount: f64,
+    #[serde(default)]
+    #[schema(value_type = Money)]
+    pub amount: Lenient<Money>,
+    #[serde(default)]
     pub description: String,
+    #[serde(default)]
     #[schema(value_type = String, format = Date)]
-    pub transaction_date: NaiveDate,
-    pub category: Option<String>,
+    pub transaction_date: Lenient<NaiveDate>,
+    pub category_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionSort {
+    #[default]
+    DateDesc,
+    DateAsc,
+    AmountDesc,
+    AmountAsc,
+}
+
+/// Filters for the global transaction list. All filters are combined with AND.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TransactionQuery {
+    /// Comma-separated account IDs
+    pub account_ids: Option<String>,
+    /// Inclusive start of the transaction date range
+    #[param(value_type = Option<String>, format = Date)]
+    pub from: Option<NaiveDate>,
+    /// Inclusive end of the transaction date range
+    #[param(value_type = Option<String>, format = Date)]
+    pub to: Option<NaiveDate>,
+    /// Inclusive lower bound on the signed amount in major units of any
+    /// currency, e.g. `-100.00`
+    #[serde(default, with = "crate::money::threshold")]
+    #[param(value_type = Option<String>)]
+    pub min_amount: Option<Money>,
+    /// Inclusive upper bound on the signed amount
+    #[serde(default, with = "crate::money::threshold")]
+    #[param(value_type = Option<String>)]
+    pub max_amount: Option<Money>,
+    /// Category ID; transactions in its subcategories match as well
+    pub category_id: Option<String>,
+    pub merchant_id: Option<String>,
+    pub pending: Option<bool>,
+    /// Case-insensitive substring of the payee
+    pub payee: Option<String>,
+    /// Case-insensitive substring of the description, payee, memo or note
+    pub q: Option<String>,
+    #[param(inline)]
+    pub sort: Option<TransactionSort>,
+    /// Page size, 1 to 500 (default 100)
+    pub limit: Option<u32>,
+    /// `next_cursor` from the previous page
+    pub cursor: Option<String>,
+}
+
+/// Partial update for a transaction. Omitted fields are left unchanged and
+/// `null` clears `category_id` or `note`.
+///
+/// On synced transactions `description` sets the display description, and the
+/// provider-owned `amount` and `transaction_date` cannot be edited.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateTransactionRequest {
+    pub description: Option<String>,
+    #[serde(default, deserialize_with = "explicit_null")]
+    #[schema(value_type = Option<String>)]
+    pub category_id: Option<Option<String>>,
+    #[serde(default, deserialize_with = "explicit_null")]
+    #[schema(value_type = Option<String>)]
+    pub note: Option<Option<String>>,
+    #[serde(default)]
+    #[schema(value_type = Option<Money>)]
+    pub amount: Lenient<Option<Money>>,
+    #[serde(default)]
+    #[schema(value_type = Option<String>, format = Date)]
+    pub transaction_date: Lenient<Option<NaiveDate>>,
+}
+
+/// Deserializes a present field, including `null`, as `Some`, so that an
+/// omitted field (`None`) can be told apart from one being cleared (`Some(None)`).
+fn explicit_null<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum CategoryKind {
+    Income,
+    #[default]
+    Expense,
+    Transfer,
+}
+
+/// How a transaction's category was assigned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum CategorySource {
+    Manual,
+    Rule,
+    /// Applied automatically from a classifier suggestion
+    Suggestion,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+pub struct Category {
+    pub id: String,
+    pub name: String,
+    /// Parent category; `null` for top-level categories
+    pub parent_id: Option<String>,
+    pub kind: CategoryKind,
+    /// Hex colour such as `#4caf50`
+    pub color: Option<String>,
+    /// Icon name understood by the frontend
+    pub icon: Option<String>,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateCategoryRequest {
+    pub name: String,
+    pub parent_id: Option<String>,
+    /// Defaults to `expense`
+    pub kind: Option<CategoryKind>,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+}
+
+/// Partial update for a category. Omitted fields are left unchanged and
+/// `null` clears `parent_id`, `color` or `icon`.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateCategoryRequest {
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "explicit_null")]
+    #[schema(value_type = Option<String>)]
+    pub parent_id: Option<Option<String>>,
+    pub kind: Option<CategoryKind>,
+    #[serde(default, deserialize_with = "explicit_null")]
+    #[schema(value_type = Option<String>)]
+    pub color: Option<Option<String>>,
+    #[serde(default, deserialize_with = "explicit_null")]
+    #[schema(value_type = Option<String>)]
+    pub icon: Option<Option<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct MergeCategoryRequest {
+    /// Category that receives the merged category's transactions and subcategories
+    pub into_id: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum AmountSign {
+    /// Money leaving the account
+    Negative,
+    /// Money entering the account
+    Positive,
+}
+
+/// A categorization rule. Every condition that is set must match; actions
+/// from rules earlier in priority order win over later ones.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+pub struct Rule {
+    pub id: String,
+    pub name: String,
+    /// Lower values run first
+    pub priority: i64,
+    pub enabled: bool,
+    /// Case-insensitive regular expression matched against the provider description
+    pub description_pattern: Option<String>,
+    /// Case-insensitive regular expression matched against the provider payee
+    pub payee_pattern: Option<String>,
+    /// Case-insensitive regular expression matched against the memo
+    pub memo_pattern: Option<String>,
+    /// Inclusive lower bound on the absolute amount, in major units of any currency
+    #[serde(default, with = "crate::money::threshold")]
+    pub min_amount: Option<Money>,
+    /// Inclusive upper bound on the absolute amount, in major units of any currency
+    #[serde(default, with = "crate::money::threshold")]
+    pub max_amount: Option<Money>,
+    pub account_id: Option<String>,
+    pub sign: Option<AmountSign>,
+    pub set_category_id: Option<String>,
+    pub set_payee: Option<String>,
+    #[schema(value_type = Vec<String>)]
+    pub add_tags: Json<Vec<String>>,
+    pub set_flagged: Option<bool>,
+    pub set_excluded: Option<bool>,
+    /// Skip the remaining rules once this one matches
+    pub stop_processing: bool,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Body for creating or replacing a rule.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RuleInput {
+    pub name: String,
+    #[serde(default)]
+    pub priority: i64,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    pub description_pattern: Option<String>,
+    pub payee_pattern: Option<String>,
+    pub memo_pattern: Option<String>,
+    #[serde(default)]
+    #[schema(value_type = Option<Money>)]
+    pub min_amount: Lenient<Option<Money>>,
+    #[serde(default)]
+    #[schema(value_type = Option<Money>)]
+    pub max_amount: Lenient<Option<Money>>,
+    pub account_id: Option<String>,
+    #[serde(default)]
+    #[schema(value_type = Option<AmountSign>)]
+    pub sign: Lenient<Option<AmountSign>>,
+    pub set_category_id: Option<String>,
+    pub set_payee: Option<String>,
+    #[serde(default)]
+    pub add_tags: Vec<String>,
+    pub set_flagged: Option<bool>,
+    pub set_excluded: Option<bool>,
+    #[serde(default)]
+    pub stop_processing: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Which transactions to re-run the rules against. All filters are optional.
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct ApplyRulesRequest {
+    /// Report the changes without saving them
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Only run this rule instead of every enabled rule
+    pub rule_id: Option<String>,
+    pub account_id: Option<String>,
+    #[schema(value_type = Option<String>, format = Date)]
+    pub from: Option<NaiveDate>,
+    #[schema(value_type = Option<String>, format = Date)]
+    pub to: Option<NaiveDate>,
+}
+
+/// The rule-controlled fields of a transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct RuleFields {
+    pub category_id: Option<String>,
+    pub payee_override: Option<String>,
+    pub tags: Vec<String>,
+    pub flagged: bool,
+    pub excluded: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RuleChange {
+    pub transaction_id: String,
+    pub description: String,
+    /// Rules that matched, in the order they ran
+    pub rule_ids: Vec<String>,
+    pub before: RuleFields,
+    pub after: RuleFields,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ApplyRulesResult {
+    pub dry_run: bool,
+    /// Transactions examined
+    pub scanned: u32,
+    /// Transactions at least one rule matched
+    pub matched: u32,
+    /// Transactions whose fields change (or would change, for a dry run)
+    pub changes: Vec<RuleChange>,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SuggestionQuery {
+    pub account_id: Option<String>,
+    /// Only return suggestions at least this confident, 0 to 1
+    pub min_confidence: Option<f64>,
+    /// Maximum number of suggestions (default 100)
+    pub limit: Option<u32>,
+}
+
+/// Category predicted for an uncategorized transaction.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CategorySuggestion {
+    pub transaction_id: String,
+    pub description: String,
+    pub category_id: String,
+    /// Posterior probability of the suggested category, 0 to 1
+    pub confidence: f64,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+pub struct Merchant {
+    pub id: String,
+    pub name: String,
+    /// Case-insensitive regex patterns matched against payee and description
+    #[sqlx(skip)]
+    pub aliases: Vec<String>,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Body for creating or replacing a merchant.
+///
+/// Transactions are reassigned whenever the aliases change.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct MerchantInput {
+    pub name: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct MergeMerchantRequest {
+    /// Merchant that receives the merged merchant's transactions and aliases
+    pub into_id: String,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct MerchantSpendingQuery {
+    /// Inclusive start date
+    #[param(value_type = Option<String>, format = Date)]
+    pub from: Option<NaiveDate>,
+    /// Inclusive end date
+    #[param(value_type = Option<String>, format = Date)]
+    pub to: Option<NaiveDate>,
+    pub account_id: Option<String>,
+}
+
+/// Outflows to one merchant in one currency.
+#[derive(Debug, ToSchema, sqlx::FromRow)]
+pub struct MerchantSpending {
+    pub merchant_id: String,
+    pub name: String,
+    pub currency: String,
+    /// Total spent, as a positive amount
+    pub spent: Money,
+    pub transaction_count: i64,
+}
+
+impl Serialize for MerchantSpending {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("MerchantSpending", 5)?;
+        state.serialize_field("merchant_id", &self.merchant_id)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("currency", &self.currency)?;
+        state.serialize_field("spent", &self.spent.display(&self.currency))?;
+        state.serialize_field("transaction_count", &self.transaction_count)?;
+        state.end()
+    }
+}
+
+/// A bank-data source the server syncs from, such as a SimpleFin bridge.
+#[derive(Debug, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+pub struct Connection {
+    pub id: String,
+    pub name: String,
+    /// Bank-data provider serving the connection, e.g. `simplefin`
+    pub provider: String,
+    /// Access URL encrypted by `CredentialCipher` for this connection's ID.
+    /// It contains the credentials, so it is never returned.
+    #[serde(skip)]
+    pub encrypted_access_url: String,
+    /// Access URL without the credentials
+    pub server_url: String,
+    /// Disabled connections are skipped by sync
+    pub enabled: bool,
+    /// Start of the last sync that fetched this connection
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub last_synced_at: Option<DateTime<Utc>>,
+    /// What the provider reported in the last sync that names no account
+    pub sync_error: Option<String>,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+    /// Set when the provider rejected the credentials; scheduled syncs skip
+    /// the connection until it needs no reauthentication
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub auth_failed_at: Option<DateTime<Utc>>,
+    /// Why the credentials were rejected
+    pub auth_error: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateConnectionRequest {
+    /// SimpleFin access URL, including the credentials
+    #[schema(value_type = String)]
+    pub access_url: Secret,
+    /// Defaults to the bridge's host name
+    pub name: Option<String>,
+}
+
+/// Partial update for a connection. Omitted fields are left unchanged.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateConnectionRequest {
+    pub name: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ClaimConnectionRequest {
+    /// Setup token from the SimpleFin bridge, a base64-encoded claim URL
+    #[schema(value_type = String)]
+    pub setup_token: Secret,
+    /// Defaults to the bridge's host name
+    pub name: Option<String>,
+}
+
+/// Date range to import from the connections' providers.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BackfillRequest {
+    /// Inclusive start date
+    #[serde(default)]
+    #[schema(value_type = String, format = Date)]
+    pub from: Lenient<NaiveDate>,
+    /// Inclusive end date; defaults to today
+    #[serde(default)]
+    #[schema(value_type = Option<String>, format = Date)]
+    pub to: Lenient<Option<NaiveDate>>,
+    /// Local IDs of provider-linked accounts; every account of every enabled
+    /// connection when omitted
+    #[serde(default)]
+    pub account_ids: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SyncRunQuery {
+    pub status: Option<SyncRunStatus>,
+    /// Maximum number of runs, 1 to 500 (default 50)
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, ToSchema, sqlx::FromRow)]
 pub struct BalanceHistory {
     pub id: String,
     pub account_id: String,
-    pub balance: f64,
+    pub balance: Money,
     #[schema(value_type = String, format = DateTime)]
     pub timestamp: DateTime<Utc>,
 }
 
+#[derive(Debug, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+pub struct ExchangeRate {
+    pub id: String,
+    pub from_currency: String,
+    pub to_currency: String,
+    /// Units of `to_currency` per one unit of `from_currency`
+    pub rate: f64,
+    #[schema(value_type = String, format = Date)]
+    pub rate_date: NaiveDate,
+    /// `manual` or `import`
+    pub source: String,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ExchangeRateInput {
+    pub from_currency: String,
+    pub to_currency: String,
+    pub rate: f64,
+    #[schema(value_type = String, format = Date)]
+    pub rate_date: NaiveDate,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateExchangeRateRequest {
+    pub rate: Option<f64>,
+    #[schema(value_type = Option<String>, format = Date)]
+    pub rate_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ExchangeRateQuery {
+    pub from_currency: Option<String>,
+    pub to_currency: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct BaseCurrencyQuery {
+    /// Currency to convert into; defaults to the server's `BASE_CURRENCY`
+    pub base_currency: Option<String>,
+}
+
+/// An amount converted into another currency, with the rate that was applied.
+#[derive(Debug, Clone, ToSchema)]
+pub struct ConvertedAmount {
+    pub currency: String,
+    pub amount: Money,
+    pub rate: f64,
+    /// Date of the rate used; the nearest rate on or before the amount's date
+    #[schema(value_type = String, format = Date)]
+    pub rate_date: NaiveDate,
+}
+
+impl Serialize for ConvertedAmount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("ConvertedAmount", 4)?;
+        state.serialize_field("currency", &self.currency)?;
+        state.serialize_field("amount", &self.amount.display(&self.currency))?;
+        state.serialize_field("rate", &self.rate)?;
+        state.serialize_field("rate_date", &self.rate_date)?;
+        state.end()
+    }
+}
+
+/// Account balances summed per currency; amounts in different currencies are never added together.
+#[derive(Debug, ToSchema, sqlx::FromRow)]
+pub struct CurrencyTotal {
+    pub currency: String,
+    pub assets: Money,
+    pub liabilities: Money,
+    pub net: Money,
+}
+
+impl Serialize for CurrencyTotal {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("CurrencyTotal", 4)?;
+        state.serialize_field("currency", &self.currency)?;
+        state.serialize_field("assets", &self.assets.display(&self.currency))?;
+        state.serialize_field("liabilities", &self.liabilities.display(&self.currency))?;
+        state.serialize_field("net", &self.net.display(&self.currency))?;
+        state.end()
+    }
+}
+
+#[derive(Debug, ToSchema)]
+pub struct NetWorthReport {
+    pub by_currency: Vec<CurrencyTotal>,
+    pub base_currency: String,
+    /// Net worth converted into `base_currency`, excluding `missing_rates`
+    pub base_total: Money,
+    /// Currencies that could not be converted because no rate exists yet
+    pub missing_rates: Vec<String>,
+}
+
+impl Serialize for NetWorthReport {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("NetWorthReport", 4)?;
+        state.serialize_field("by_currency", &self.by_currency)?;
+        state.serialize_field("base_currency", &self.base_currency)?;
+        state.serialize_field("base_total", &self.base_total.display(&self.base_currency))?;
+        state.serialize_field("missing_rates", &self.missing_rates)?;
+        state.end()
+    }
+}
+
+/// Income and expenses for one calendar month in one currency.
+#[derive(Debug, ToSchema, sqlx::FromRow)]
+pub struct MonthlyCashFlow {
+    /// Month in `YYYY-MM` form
+    pub month: String,
+    pub currency: String,
+    pub income: Money,
+    pub expenses: Money,
+    pub net: Money,
+}
+
+impl Serialize for MonthlyCashFlow {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("MonthlyCashFlow", 5)?;
+        state.serialize_field("month", &self.month)?;
+        state.serialize_field("currency", &self.currency)?;
+        state.serialize_field("income", &self.income.display(&self.currency))?;
+        state.serialize_field("expenses", &self.expenses.display(&self.currency))?;
+        state.serialize_field("net", &self.net.display(&self.currency))?;
+        state.end()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MonthlyReport {
+    pub by_currency: Vec<MonthlyCashFlow>,
+    pub base_currency: String,
+    /// Every month converted into `base_currency` at each transaction date's rate
+    pub converted: Vec<MonthlyCashFlow>,
+    /// Currencies with transactions that could not be converted
+    pub missing_rates: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ReportQuery {
+    /// Inclusive start date
+    #[param(value_type = Option<String>, format = Date)]
+    pub from: Option<NaiveDate>,
+    /// Inclusive end date
+    #[param(value_type = Option<String>, format = Date)]
+    pub to: Option<NaiveDate>,
+    /// Currency to convert into; defaults to the server's `BASE_CURRENCY`
+    pub base_currency: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ApiResponse<T> {
     pub success: bool,
     pub data: Option<T>,
-    pub error: Option<String>,
+    pub error: Option<ApiErrorBody>,
+    /// Opaque cursor for the next page of a paginated list; absent on the last page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
 }
 
 impl<T> ApiResponse<T> {
@@ -79,14 +864,25 @@ impl<T> ApiResponse<T> {
             success: true,
             data: Some(data),
             error: None,
+            next_cursor: None,
+        }
+    }
+
+    pub fn page(data: T, next_cursor: Option<String>) -> Self {
+        Self {
+            success: true,
+            data: Some(data),
+            error: None,
+            next_cursor,
         }
     }
 
-    pub fn error(message: String) -> Self {
+    pub fn error(error: ApiErrorBody) -> Self {
         Self {
             success: false,
             data: None,
-            error: Some(message),
+            error: Some(error),
+            next_cursor: None,
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,691 @@
+use serde::{Deserializer, Serialize, Serializer, de};
+use sqlx::{
+    Decode, Encode, Sqlite, Type,
+    encode::IsNull,
+    error::BoxDynError,
+    sqlite::{SqliteArgumentValue, SqliteTypeInfo, SqliteValueRef},
+};
+use std::cmp::Ordering;
+use std::fmt;
+use utoipa::{
+    ToSchema,
+    openapi::{
+        RefOr,
+        schema::{ObjectBuilder, Schema, SchemaFormat, SchemaType},
+    },
+};
+
+/// Currencies whose minor unit is not a hundredth, by ISO 4217 exponent.
+/// Every other code, including provider-specific identifiers, uses two digits.
+const MINOR_DIGITS: &[(u32, &[&str])] = &[
+    (
+        0,
+        &[
+            "BIF", "CLP", "DJF", "GNF", "ISK", "JPY", "KMF", "KRW", "PYG", "RWF", "UGX", "UYI",
+            "VND", "VUV", "XAF", "XOF", "XPF",
+        ],
+    ),
+    (3, &["BHD", "IQD", "JOD", "KWD", "LYD", "OMR", "TND"]),
+    (4, &["CLF", "UYW"]),
+];
+const DEFAULT_MINOR_DIGITS: u32 = 2;
+
+/// The most fractional digits any currency has; amounts in different
+/// currencies are compared in units of this many digits.
+pub const MAX_MINOR_DIGITS: u32 = 4;
+
+/// Fractional digits of thresholds, amounts that are not tied to a currency
+/// such as rule bounds and list filters. They are compared with amounts in
+/// every currency as a number of major units.
+pub const THRESHOLD_DIGITS: u32 = 2;
+
+/// Decimal places an exchange rate is rounded to before it is applied.
+pub const RATE_DIGITS: usize = 12;
+
+/// An exact monetary amount stored as a signed count of its currency's minor
+/// units: cents for USD, yen for JPY, fils for BHD.
+///
+/// The value carries no currency of its own; it always travels next to one,
+/// which decides how it is parsed and displayed (see [`minor_digits`]).
+/// Amounts are persisted as SQLite `INTEGER` and serialized over the API as
+/// decimal strings such as `"-12.34"` so no precision is lost on either side.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money(i64);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoneyParseError {
+    input: String,
+    /// Set when the input is a number with more decimal places than allowed
+    max_digits: Option<u32>,
+}
+
+impl fmt::Display for MoneyParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.max_digits {
+            Some(0) => write!(f, "{:?} must be a whole number", self.input),
+            Some(digits) => write!(
+                f,
+                "{:?} has more than {} decimal places",
+                self.input, digits
+            ),
+            None => write!(f, "invalid monetary amount: {:?}", self.input),
+        }
+    }
+}
+
+impl std::error::Error for MoneyParseError {}
+
+/// Arithmetic produced an amount outside the range of [`Money`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoneyOverflow;
+
+impl fmt::Display for MoneyOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("monetary amount out of range")
+    }
+}
+
+impl std::error::Error for MoneyOverflow {}
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    pub const fn from_minor(minor: i64) -> Self {
+        Self(minor)
+    }
+
+    /// A whole number of major units, or `None` when that overflows.
+    pub fn from_major(major: i64, currency: &str) -> Option<Self> {
+        major.checked_mul(pow10(minor_digits(currency))).map(Self)
+    }
+
+    pub const fn minor_units(self) -> i64 {
+        self.0
+    }
+
+    pub const fn abs(self) -> Self {
+        Self(self.0.saturating_abs())
+    }
+
+    pub fn checked_add(self, rhs: Money) -> Result<Money, MoneyOverflow> {
+        self.0.checked_add(rhs.0).map(Money).ok_or(MoneyOverflow)
+    }
+
+    pub fn checked_sub(self, rhs: Money) -> Result<Money, MoneyOverflow> {
+        self.0.checked_sub(rhs.0).map(Money).ok_or(MoneyOverflow)
+    }
+
+    pub const fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub const fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    /// Parses a plain decimal string (`"1234"`, `"-12.5"`, `"+0.07"`) as an
+    /// amount in `currency`.
+    ///
+    /// Digits beyond the currency's minor unit are accepted only when they are
+    /// zeros; anything else would silently lose precision.
+    pub fn parse(s: &str, currency: &str) -> Result<Self, MoneyParseError> {
+        parse_decimal(s, minor_digits(currency))
+    }
+
+    /// Parses a threshold (see [`THRESHOLD_DIGITS`]).
+    pub fn parse_threshold(s: &str) -> Result<Self, MoneyParseError> {
+        parse_decimal(s, THRESHOLD_DIGITS)
+    }
+
+    /// Formats the amount with the fractional digits of `currency`.
+    pub fn display(self, currency: &str) -> MoneyDisplay {
+        MoneyDisplay {
+            minor: self.0,
+            digits: minor_digits(currency),
+        }
+    }
+
+    /// Formats a threshold (see [`THRESHOLD_DIGITS`]).
+    pub fn display_threshold(self) -> MoneyDisplay {
+        MoneyDisplay {
+            minor: self.0,
+            digits: THRESHOLD_DIGITS,
+        }
+    }
+
+    /// Compares this amount in `currency` with a threshold, in major units.
+    pub fn cmp_threshold(self, currency: &str, threshold: Money) -> Ordering {
+        let amount =
+            i128::from(self.0) * i128::from(pow10(MAX_MINOR_DIGITS - minor_digits(currency)));
+        let threshold =
+            i128::from(threshold.0) * i128::from(pow10(MAX_MINOR_DIGITS - THRESHOLD_DIGITS));
+        amount.cmp(&threshold)
+    }
+
+    /// Rewrites an amount in `from` as the same value in the minor units of
+    /// `to`, rounding half away from zero; `None` when that overflows.
+    pub fn rescale(self, from: &str, to: &str) -> Option<Money> {
+        let (from, to) = (minor_digits(from), minor_digits(to));
+        if to >= from {
+            self.0.checked_mul(pow10(to - from)).map(Money)
+        } else {
+            let divisor = pow10(from - to).into();
+            Some(Money(round_div(self.0.into(), divisor) as i64))
+        }
+    }
+
+    /// Multiplies by an exchange rate from `from` into `to`, rounding to the
+    /// nearest minor unit of `to`. The rate is first rounded to
+    /// [`RATE_DIGITS`] decimal places so the product is exact integer math.
+    pub fn convert(self, rate: f64, from: &str, to: &str) -> Result<Money, MoneyOverflow> {
+        let rate: i128 = format!("{rate:.RATE_DIGITS$}")
+            .replace('.', "")
+            .parse()
+            .map_err(|_| MoneyOverflow)?;
+        let (from, to) = (minor_digits(from), minor_digits(to));
+        let mut divisor = i128::from(pow10(RATE_DIGITS as u32));
+        let mut product = i128::from(self.0).checked_mul(rate).ok_or(MoneyOverflow)?;
+        if to >= from {
+            product = product
+                .checked_mul(pow10(to - from).into())
+                .ok_or(MoneyOverflow)?;
+        } else {
+            divisor *= i128::from(pow10(from - to));
+        }
+        i64::try_from(round_div(product, divisor))
+            .map(Money)
+            .map_err(|_| MoneyOverflow)
+    }
+}
+
+/// Number of fractional digits in the minor unit of `currency`, a code as
+/// returned by [`normalize_currency`].
+pub fn minor_digits(currency: &str) -> u32 {
+    MINOR_DIGITS
+        .iter()
+        .find(|(_, codes)| codes.contains(&currency))
+        .map_or(DEFAULT_MINOR_DIGITS, |(digits, _)| *digits)
+}
+
+/// Normalizes a currency code for storage: ISO 4217 codes are upper-cased,
+/// provider-specific identifiers (SimpleFin allows URLs) are kept verbatim.
+pub fn normalize_currency(code: &str) -> String {
+    let code = code.trim();
+    if code.len() == 3 && code.bytes().all(|b| b.is_ascii_alphabetic()) {
+        code.to_ascii_uppercase()
+    } else {
+        code.to_string()
+    }
+}
+
+/// SQL for `amount_column` in units of [`MAX_MINOR_DIGITS`] fractional digits,
+/// taking each row's scale from `currency_column`, so that amounts in
+/// different currencies and thresholds can be compared.
+pub fn sql_comparable_amount(amount_column: &str, currency_column: &str) -> String {
+    let mut sql = format!("({} * CASE", amount_column);
+    for (digits, codes) in MINOR_DIGITS {
+        let codes: Vec<String> = codes.iter().map(|code| format!("'{}'", code)).collect();
+        sql.push_str(&format!(
+            " WHEN {} IN ({}) THEN {}",
+            currency_column,
+            codes.join(", "),
+            pow10(MAX_MINOR_DIGITS - digits)
+        ));
+    }
+    sql.push_str(&format!(
+        " ELSE {} END)",
+        pow10(MAX_MINOR_DIGITS - DEFAULT_MINOR_DIGITS)
+    ));
+    sql
+}
+
+/// A threshold in the units of [`sql_comparable_amount`], or `None` if it is
+/// too large to represent in them.
+pub fn comparable_threshold(threshold: Money) -> Option<i64> {
+    threshold
+        .0
+        .checked_mul(pow10(MAX_MINOR_DIGITS - THRESHOLD_DIGITS))
+}
+
+/// SQL that rewrites `column` from the minor units of `from` into those of
+/// `to`, keeping the value in major units and rounding half away from zero.
+pub fn sql_rescale(column: &str, from: &str, to: &str) -> String {
+    let (from, to) = (minor_digits(from), minor_digits(to));
+    if to >= from {
+        format!("{} * {}", column, pow10(to - from))
+    } else {
+        format!(
+            "CAST(ROUND({} / {}.0) AS INTEGER)",
+            column,
+            pow10(from - to)
+        )
+    }
+}
+
+/// Largest magnitude [`sql_rescale`] can rewrite from `from` into `to`
+/// without overflowing, or `None` when it never overflows.
+pub fn rescale_limit(from: &str, to: &str) -> Option<i64> {
+    let (from, to) = (minor_digits(from), minor_digits(to));
+    (to > from).then(|| i64::MAX / pow10(to - from))
+}
+
+const fn pow10(exponent: u32) -> i64 {
+    10i64.pow(exponent)
+}
+
+/// Divides, rounding half away from zero.
+fn round_div(value: i128, divisor: i128) -> i128 {
+    let (quotient, remainder) = (value / divisor, value % divisor);
+    if remainder.abs() * 2 >= divisor {
+        quotient + value.signum()
+    } else {
+        quotient
+    }
+}
+
+fn parse_decimal(s: &str, digits: u32) -> Result<Money, MoneyParseError> {
+    let err = |max_digits| MoneyParseError {
+        input: s.to_string(),
+        max_digits,
+    };
+    let trimmed = s.trim();
+
+    let (negative, unsigned) = match trimmed.as_bytes().first() {
+        Some(b'-') => (true, &trimmed[1..]),
+        Some(b'+') => (false, &trimmed[1..]),
+        _ => (false, trimmed),
+    };
+
+    let (whole, fraction) = match unsigned.split_once('.') {
+        Some((whole, fraction)) => (whole, fraction),
+        None => (unsigned, ""),
+    };
+
+    if whole.is_empty() && fraction.is_empty() {
+        return Err(err(None));
+    }
+    if !whole.bytes().all(|b| b.is_ascii_digit()) || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(err(None));
+    }
+
+    let (kept, dropped) = fraction.split_at(fraction.len().min(digits as usize));
+    if dropped.bytes().any(|b| b != b'0') {
+        return Err(err(Some(digits)));
+    }
+
+    let whole_value = if whole.is_empty() {
+        0
+    } else {
+        whole.parse::<i64>().map_err(|_| err(None))?
+    };
+    let fraction_value = if digits == 0 {
+        0
+    } else {
+        format!("{:0<width$}", kept, width = digits as usize)
+            .parse::<i64>()
+            .map_err(|_| err(None))?
+    };
+
+    let minor = whole_value
+        .checked_mul(pow10(digits))
+        .and_then(|v| v.checked_add(fraction_value))
+        .ok_or_else(|| err(None))?;
+
+    Ok(Money(if negative { -minor } else { minor }))
+}
+
+/// An amount with the number of fractional digits to show, from
+/// [`Money::display`]. Serializes as a decimal string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoneyDisplay {
+    minor: i64,
+    digits: u32,
+}
+
+impl fmt::Display for MoneyDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.minor < 0 { "-" } else { "" };
+        let abs = self.minor.unsigned_abs();
+        let scale = pow10(self.digits) as u64;
+        if self.digits == 0 {
+            write!(f, "{}{}", sign, abs)
+        } else {
+            write!(
+                f,
+                "{}{}.{:0width$}",
+                sign,
+                abs / scale,
+                abs % scale,
+                width = self.digits as usize
+            )
+        }
+    }
+}
+
+impl Serialize for MoneyDisplay {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Serde for optional thresholds (see [`THRESHOLD_DIGITS`]), as decimal
+/// strings; numbers are accepted on input.
+pub mod threshold {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<Money>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => serializer.serialize_some(&value.display_threshold()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Money>, D::Error> {
+        struct ThresholdVisitor;
+
+        impl<'de> de::Visitor<'de> for ThresholdVisitor {
+            type Value = Option<Money>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a decimal amount as a string or number")
+            }
+
+            fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+                Ok(None)
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+                Ok(None)
+            }
+
+            fn visit_some<D: Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> Result<Self::Value, D::Error> {
+                deserializer.deserialize_any(self)
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                Money::parse_threshold(v).map(Some).map_err(E::custom)
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                self.visit_str(&v.to_string())
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                self.visit_str(&v.to_string())
+            }
+
+            // JSON numbers arrive as f64; their shortest round-trip representation
+            // is the literal the client sent, so parse that text exactly.
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+                self.visit_str(&v.to_string())
+            }
+        }
+
+        deserializer.deserialize_option(ThresholdVisitor)
+    }
+}
+
+impl Type<Sqlite> for Money {
+    fn type_info() -> SqliteTypeInfo {
+        <i64 as Type<Sqlite>>::type_info()
+    }
+
+    fn compatible(ty: &SqliteTypeInfo) -> bool {
+        <i64 as Type<Sqlite>>::compatible(ty)
+    }
+}
+
+impl<'q> Encode<'q, Sqlite> for Money {
+    fn encode_by_ref(&self, buf: &mut Vec<SqliteArgumentValue<'q>>) -> IsNull {
+        <i64 as Encode<Sqlite>>::encode_by_ref(&self.0, buf)
+    }
+}
+
+impl<'r> Decode<'r, Sqlite> for Money {
+    fn decode(value: SqliteValueRef<'r>) -> Result<Self, BoxDynError> {
+        <i64 as Decode<Sqlite>>::decode(value).map(Money)
+    }
+}
+
+impl<'s> ToSchema<'s> for Money {
+    fn schema() -> (&'s str, RefOr<Schema>) {
+        (
+            "Money",
+            ObjectBuilder::new()
+                .schema_type(SchemaType::String)
+                .format(Some(SchemaFormat::Custom("decimal".to_string())))
+                .description(Some(
+                    "Exact decimal amount with as many fractional digits as its currency has",
+                ))
+                .example(Some(serde_json::json!("-12.34")))
+                .into(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str, currency: &str) -> i64 {
+        Money::parse(s, currency).unwrap().minor_units()
+    }
+
+    fn display(minor: i64, currency: &str) -> String {
+        Money::from_minor(minor).display(currency).to_string()
+    }
+
+    #[test]
+    fn minor_digits_follow_iso_4217() {
+        assert_eq!(minor_digits("USD"), 2);
+        assert_eq!(minor_digits("JPY"), 0);
+        assert_eq!(minor_digits("BHD"), 3);
+        assert_eq!(minor_digits("CLF"), 4);
+        // Provider-specific identifiers are treated like most currencies
+        assert_eq!(minor_digits("https://example.com/points"), 2);
+    }
+
+    #[test]
+    fn parses_two_digit_currencies() {
+        assert_eq!(parse("12.34", "USD"), 1234);
+        assert_eq!(parse("-12.5", "USD"), -1250);
+        assert_eq!(parse("+0.07", "USD"), 7);
+        assert_eq!(parse(".5", "USD"), 50);
+        assert_eq!(parse("7.", "USD"), 700);
+        assert_eq!(parse(" 1234 ", "USD"), 123400);
+        assert_eq!(parse("1.2300", "USD"), 123);
+    }
+
+    #[test]
+    fn parses_zero_digit_currencies() {
+        assert_eq!(parse("1234", "JPY"), 1234);
+        assert_eq!(parse("-500", "JPY"), -500);
+        assert_eq!(parse("1234.00", "JPY"), 1234);
+        assert_eq!(
+            Money::parse("1234.5", "JPY").unwrap_err().to_string(),
+            "\"1234.5\" must be a whole number"
+        );
+    }
+
+    #[test]
+    fn parses_three_digit_currencies() {
+        assert_eq!(parse("1.234", "BHD"), 1234);
+        assert_eq!(parse("-0.125", "BHD"), -125);
+        assert_eq!(parse("1.2", "BHD"), 1200);
+        assert_eq!(
+            Money::parse("1.2345", "BHD").unwrap_err().to_string(),
+            "\"1.2345\" has more than 3 decimal places"
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_amounts() {
+        for input in [
+            "", "-", ".", "1.2.3", "1,000.00", "12e3", "ten", "--1", "1 000",
+        ] {
+            assert!(Money::parse(input, "USD").is_err(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn rejects_amounts_that_overflow() {
+        assert_eq!(parse("92233720368547758.07", "USD"), i64::MAX);
+        assert!(Money::parse("92233720368547758.08", "USD").is_err());
+        assert!(Money::parse("9223372036854775808", "JPY").is_err());
+        assert!(Money::parse("9223372036854775.808", "BHD").is_err());
+        assert!(Money::from_major(i64::MAX, "USD").is_none());
+    }
+
+    #[test]
+    fn displays_with_the_digits_of_the_currency() {
+        assert_eq!(display(1234, "USD"), "12.34");
+        assert_eq!(display(-5, "USD"), "-0.05");
+        assert_eq!(display(0, "USD"), "0.00");
+        assert_eq!(display(1234, "JPY"), "1234");
+        assert_eq!(display(-1234, "JPY"), "-1234");
+        assert_eq!(display(1234, "BHD"), "1.234");
+        assert_eq!(display(-5, "BHD"), "-0.005");
+        assert_eq!(display(1, "CLF"), "0.0001");
+        assert_eq!(display(i64::MIN, "USD"), "-92233720368547758.08");
+    }
+
+    #[test]
+    fn display_round_trips_through_parse() {
+        for (minor, currency) in [(1234, "USD"), (-1, "USD"), (987, "JPY"), (-1001, "BHD")] {
+            let text = display(minor, currency);
+            assert_eq!(parse(&text, currency), minor);
+        }
+    }
+
+    #[test]
+    fn rescale_keeps_the_value_and_rounds_half_away_from_zero() {
+        let rescale = |minor, from, to| Money::from_minor(minor).rescale(from, to);
+        assert_eq!(rescale(1234, "JPY", "USD"), Some(Money::from_minor(123400)));
+        assert_eq!(rescale(1234, "USD", "BHD"), Some(Money::from_minor(12340)));
+        assert_eq!(rescale(1250, "USD", "JPY"), Some(Money::from_minor(13)));
+        assert_eq!(rescale(1249, "USD", "JPY"), Some(Money::from_minor(12)));
+        assert_eq!(rescale(-1250, "USD", "JPY"), Some(Money::from_minor(-13)));
+        assert_eq!(rescale(-1249, "USD", "JPY"), Some(Money::from_minor(-12)));
+        assert_eq!(rescale(i64::MAX, "JPY", "USD"), None);
+    }
+
+    #[test]
+    fn rescale_limit_is_the_largest_amount_that_fits() {
+        let limit = rescale_limit("JPY", "USD").unwrap();
+        assert!(Money::from_minor(limit).rescale("JPY", "USD").is_some());
+        assert!(Money::from_minor(limit + 1).rescale("JPY", "USD").is_none());
+        assert_eq!(rescale_limit("USD", "JPY"), None);
+        assert_eq!(rescale_limit("USD", "EUR"), None);
+    }
+
+    #[test]
+    fn compares_with_thresholds_in_major_units() {
+        let ten = Money::parse_threshold("10").unwrap();
+        assert!(Money::from_minor(10).cmp_threshold("JPY", ten).is_eq());
+        assert!(Money::from_minor(1000).cmp_threshold("USD", ten).is_eq());
+        assert!(Money::from_minor(10_001).cmp_threshold("BHD", ten).is_gt());
+        assert!(Money::from_minor(9).cmp_threshold("JPY", ten).is_lt());
+        assert!(
+            Money::from_minor(i64::MAX)
+                .cmp_threshold("JPY", ten)
+                .is_gt()
+        );
+    }
+
+    #[test]
+    fn comparable_thresholds_reject_overflow() {
+        let ten = Money::parse_threshold("10").unwrap();
+        assert!(comparable_threshold(ten).is_some());
+        assert_eq!(comparable_threshold(Money::from_minor(i64::MAX)), None);
+    }
+
+    #[test]
+    fn convert_moves_between_scales() {
+        // 1000 JPY at 0.0067 USD per yen
+        let yen = Money::from_minor(1000);
+        assert_eq!(
+            yen.convert(0.0067, "JPY", "USD"),
+            Ok(Money::from_minor(670))
+        );
+        // 12.34 USD at 150 yen per dollar
+        let dollars = Money::from_minor(1234);
+        assert_eq!(
+            dollars.convert(150.0, "USD", "JPY"),
+            Ok(Money::from_minor(1851))
+        );
+        // 1.000 BHD at 2.65 USD per dinar
+        let dinar = Money::from_minor(1000);
+        assert_eq!(
+            dinar.convert(2.65, "BHD", "USD"),
+            Ok(Money::from_minor(265))
+        );
+    }
+
+    #[test]
+    fn convert_applies_the_rate_as_a_decimal() {
+        // 100 * 1.015 is 101.49999999999999 in binary floating point
+        let dollar = Money::from_minor(100);
+        assert_eq!(
+            dollar.convert(1.015, "USD", "USD"),
+            Ok(Money::from_minor(102))
+        );
+        // An inverse rate rounded to RATE_DIGITS places still lands on the
+        // nearest minor unit
+        assert_eq!(
+            Money::from_minor(300).convert(1.0 / 3.0, "USD", "USD"),
+            Ok(Money::from_minor(100))
+        );
+        assert_eq!(
+            Money::from_minor(-5).convert(0.5, "USD", "USD"),
+            Ok(Money::from_minor(-3))
+        );
+    }
+
+    #[test]
+    fn out_of_range_arithmetic_is_an_error() {
+        let max = Money::from_minor(i64::MAX);
+        let min = Money::from_minor(i64::MIN);
+        assert_eq!(max.checked_add(Money::from_minor(1)), Err(MoneyOverflow));
+        assert_eq!(min.checked_sub(Money::from_minor(1)), Err(MoneyOverflow));
+        assert_eq!(
+            max.checked_sub(Money::from_minor(1)),
+            Ok(Money::from_minor(i64::MAX - 1))
+        );
+        assert_eq!(min.abs(), max);
+        assert_eq!(max.convert(2.0, "USD", "USD"), Err(MoneyOverflow));
+        assert_eq!(max.convert(1.0, "JPY", "BHD"), Err(MoneyOverflow));
+        assert_eq!(dollar_rate_of(1e300), Err(MoneyOverflow));
+        assert_eq!(dollar_rate_of(f64::NAN), Err(MoneyOverflow));
+    }
+
+    fn dollar_rate_of(rate: f64) -> Result<Money, MoneyOverflow> {
+        Money::from_minor(100).convert(rate, "USD", "USD")
+    }
+
+    #[test]
+    fn thresholds_round_trip_through_serde() {
+        #[derive(Serialize, serde::Deserialize)]
+        struct Bounds {
+            #[serde(default, with = "threshold")]
+            min: Option<Money>,
+        }
+
+        let bounds: Bounds = serde_json::from_str(r#"{"min": 12.5}"#).unwrap();
+        assert_eq!(bounds.min, Some(Money::from_minor(1250)));
+        assert_eq!(
+            serde_json::to_string(&bounds).unwrap(),
+            r#"{"min":"12.50"}"#
+        );
+        let bounds: Bounds = serde_json::from_str(r#"{"min": null}"#).unwrap();
+        assert_eq!(bounds.min, None);
+        assert!(serde_json::from_str::<Bounds>(r#"{"min": "1.234"}"#).is_err());
+    }
+}
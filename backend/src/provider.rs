@@ -0,0 +1,203 @@
+use anyhow::{Result, bail};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+#[cfg(any(test, feature = "mock-provider"))]
+use std::sync::{Arc, Mutex};
+
+use crate::credentials::Secret;
+use crate::models::Connection;
+use crate::money::Money;
+use crate::simplefin::SimplefinClient;
+
+/// Provider of SimpleFin connections and their accounts.
+pub const SIMPLEFIN: &str = "simplefin";
+
+/// Provider of [`MockProvider`] data. Connections cannot use it; it only
+/// serves [`crate::sync::SyncService::sync_provider`] in tests.
+#[cfg(any(test, feature = "mock-provider"))]
+pub const MOCK: &str = "mock";
+
+/// Which data to request from a provider.
+#[derive(Debug, Clone, Default)]
+pub struct FetchOptions {
+    /// Only transactions on or after this time
+    pub start_date: Option<DateTime<Utc>>,
+    /// Only transactions before this time
+    pub end_date: Option<DateTime<Utc>>,
+    /// Only the accounts with these external IDs; every account when empty
+    pub account_ids: Vec<String>,
+}
+
+impl FetchOptions {
+    /// Whether `time` falls within the requested range.
+    pub fn includes(&self, time: DateTime<Utc>) -> bool {
+        self.start_date.is_none_or(|start| time >= start)
+            && self.end_date.is_none_or(|end| time < end)
+    }
+}
+
+/// Splits `[start, end)` into consecutive windows no longer than `chunk`,
+/// oldest first, since providers limit the date range of one request.
+pub fn date_windows(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    chunk: Duration,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let mut windows = Vec::new();
+    let mut window_start = start;
+    while window_start < end {
+        let window_end = (window_start + chunk).min(end);
+        windows.push((window_start, window_end));
+        window_start = window_end;
+    }
+    windows
+}
+
+/// An account as reported by a provider.
+#[derive(Debug, Clone)]
+pub struct ProviderAccount {
+    /// The provider's ID, unique among its accounts
+    pub external_id: String,
+    pub name: String,
+    pub institution: String,
+    pub currency: String,
+    pub balance: Money,
+    pub available_balance: Option<Money>,
+    pub is_credit_card: bool,
+    /// `None` when the provider sent no transaction list, as opposed to an
+    /// empty one; only a list lets sync remove vanished pending transactions
+    pub transactions: Option<Vec<ProviderTransaction>>,
+    /// IDs of listed transactions that could not be read, such as ones with a
+    /// malformed amount; sync treats them as still in the feed
+    pub unreadable_transaction_ids: Vec<String>,
+    /// Current positions of an investment account; `None` when not reported
+    pub holdings: Option<Vec<ProviderHolding>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProviderTransaction {
+    /// The provider's ID, unique within the account
+    pub external_id: String,
+    pub amount: Money,
+    /// When it posted, or when it happened if it is still pending
+    pub posted_at: Option<DateTime<Utc>>,
+    pub description: String,
+    pub payee: Option<String>,
+    pub memo: Option<String>,
+    pub pending: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProviderHolding {
+    /// The provider's ID, unique within the account
+    pub external_id: String,
+    pub symbol: Option<String>,
+    pub description: String,
+    pub shares: f64,
+    pub market_value: Money,
+    pub cost_basis: Option<Money>,
+    pub currency: String,
+}
+
+/// A message for the user, e.g. that an institution needs reauthentication.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderMessage {
+    pub message: String,
+    /// External ID of the account it concerns, when the provider said
+    pub account_id: Option<String>,
+}
+
+/// Everything one request returned.
+#[derive(Debug, Clone, Default)]
+pub struct ProviderData {
+    pub accounts: Vec<ProviderAccount>,
+    pub messages: Vec<ProviderMessage>,
+}
+
+/// A source of bank data. Sync stores what a provider returns the same way
+/// whichever provider it is.
+#[async_trait]
+pub trait BankDataProvider: Send + Sync {
+    /// Stored with each account; external IDs are unique per provider.
+    fn name(&self) -> &'static str;
+
+    /// The accounts requested, with their transactions in the requested
+    /// range and their current holdings.
+    async fn fetch(&self, options: &FetchOptions) -> Result<ProviderData>;
+}
+
+/// The provider serving a stored connection.
+pub fn connect(connection: &Connection, access_url: &Secret) -> Result<Box<dyn BankDataProvider>> {
+    match connection.provider.as_str() {
+        SIMPLEFIN => Ok(Box::new(SimplefinClient::new(
+            access_url.expose().to_string(),
+        )?)),
+        other => bail!(
+            "connection {} uses unknown provider {:?}",
+            connection.name,
+            other
+        ),
+    }
+}
+
+/// Serves fixed data, for tests. Like SimpleFin, it returns only the
+/// requested accounts and the transactions in the requested range.
+#[cfg(any(test, feature = "mock-provider"))]
+#[derive(Debug, Clone, Default)]
+pub struct MockProvider {
+    data: ProviderData,
+    requests: Arc<Mutex<Vec<FetchOptions>>>,
+}
+
+#[cfg(any(test, feature = "mock-provider"))]
+impl MockProvider {
+    pub fn new(data: ProviderData) -> Self {
+        Self {
+            data,
+            requests: Arc::default(),
+        }
+    }
+
+    /// Every request received so far, oldest first.
+    pub fn requests(&self) -> Vec<FetchOptions> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[cfg(any(test, feature = "mock-provider"))]
+#[async_trait]
+impl BankDataProvider for MockProvider {
+    fn name(&self) -> &'static str {
+        MOCK
+    }
+
+    async fn fetch(&self, options: &FetchOptions) -> Result<ProviderData> {
+        self.requests.lock().unwrap().push(options.clone());
+
+        let accounts = self
+            .data
+            .accounts
+            .iter()
+            .filter(|account| {
+                options.account_ids.is_empty() || options.account_ids.contains(&account.external_id)
+            })
+            .map(|account| ProviderAccount {
+                transactions: account.transactions.as_ref().map(|transactions| {
+                    transactions
+                        .iter()
+                        .filter(|t| {
+                            t.posted_at
+                                .is_none_or(|posted_at| options.includes(posted_at))
+                        })
+                        .cloned()
+                        .collect()
+                }),
+                ..account.clone()
+            })
+            .collect();
+        Ok(ProviderData {
+            accounts,
+            messages: self.data.messages.clone(),
+        })
+    }
+}
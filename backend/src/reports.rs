@@ -0,0 +1,206 @@
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::SqlitePool;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::exchange_rates::CurrencyConverter;
+use crate::models::{
+    CurrencyTotal, MerchantSpending, MerchantSpendingQuery, MonthlyCashFlow, MonthlyReport,
+    NetWorthReport,
+};
+use crate::money::Money;
+
+// Every aggregate here groups by currency. Amounts in different currencies
+// are never summed together; conversion to a single figure is a separate step.
+
+pub async fn net_worth(pool: &SqlitePool) -> Result<Vec<CurrencyTotal>> {
+    let totals = sqlx::query_as::<_, CurrencyTotal>(
+        r#"
+        SELECT
+            currency,
+            SUM(CASE WHEN balance > 0 THEN balance ELSE 0 END) AS assets,
+            SUM(CASE WHEN balance < 0 THEN balance ELSE 0 END) AS liabilities,
+            SUM(balance) AS net
+        FROM accounts
+        GROUP BY currency
+        ORDER BY currency
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(totals)
+}
+
+/// Net worth per currency plus a single figure in the converter's base currency.
+///
+/// Each balance is converted at the rate for the date it was last updated.
+pub async fn net_worth_report(
+    pool: &SqlitePool,
+    converter: &mut CurrencyConverter,
+) -> Result<NetWorthReport> {
+    let by_currency = net_worth(pool).await?;
+
+    let balances = sqlx::query_as::<_, (String, Money, DateTime<Utc>)>(
+        "SELECT currency, balance, last_updated FROM accounts",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut base_total = Money::ZERO;
+    let mut missing_rates = BTreeSet::new();
+    for (currency, balance, last_updated) in balances {
+        match converter
+            .convert(balance, &currency, last_updated.date_naive())
+            .await?
+        {
+            Some(converted) => base_total = base_total.checked_add(converted.amount)?,
+            None => {
+                missing_rates.insert(currency);
+            }
+        }
+    }
+
+    Ok(NetWorthReport {
+        by_currency,
+        base_currency: converter.base_currency().to_string(),
+        base_total,
+        missing_rates: missing_rates.into_iter().collect(),
+    })
+}
+
+/// Monthly cash flow per currency plus the same months converted into the
+/// converter's base currency at each transaction date's rate.
+pub async fn monthly_report(
+    pool: &SqlitePool,
+    converter: &mut CurrencyConverter,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> Result<MonthlyReport> {
+    let by_currency = monthly_cash_flow(pool, from, to).await?;
+
+    let daily = sqlx::query_as::<_, (NaiveDate, String, Money, Money)>(
+        r#"
+        SELECT
+            transaction_date,
+            currency,
+            SUM(CASE WHEN amount > 0 THEN amount ELSE 0 END),
+            SUM(CASE WHEN amount < 0 THEN amount ELSE 0 END)
+        FROM transactions
+        WHERE NOT excluded
+          AND (? IS NULL OR transaction_date >= ?)
+          AND (? IS NULL OR transaction_date <= ?)
+        GROUP BY transaction_date, currency
+        "#,
+    )
+    .bind(from)
+    .bind(from)
+    .bind(to)
+    .bind(to)
+    .fetch_all(pool)
+    .await?;
+
+    let mut months: BTreeMap<String, (Money, Money)> = BTreeMap::new();
+    let mut missing_rates = BTreeSet::new();
+    for (date, currency, income, expenses) in daily {
+        let (Some(income), Some(expenses)) = (
+            converter.convert(income, &currency, date).await?,
+            converter.convert(expenses, &currency, date).await?,
+        ) else {
+            missing_rates.insert(currency);
+            continue;
+        };
+
+        let totals = months.entry(date.format("%Y-%m").to_string()).or_default();
+        totals.0 = totals.0.checked_add(income.amount)?;
+        totals.1 = totals.1.checked_add(expenses.amount)?;
+    }
+
+    let base_currency = converter.base_currency().to_string();
+    let converted = months
+        .into_iter()
+        .rev()
+        .map(|(month, (income, expenses))| {
+            Ok(MonthlyCashFlow {
+                month,
+                currency: base_currency.clone(),
+                income,
+                expenses,
+                net: income.checked_add(expenses)?,
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(MonthlyReport {
+        by_currency,
+        base_currency,
+        converted,
+        missing_rates: missing_rates.into_iter().collect(),
+    })
+}
+
+pub async fn monthly_cash_flow(
+    pool: &SqlitePool,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> Result<Vec<MonthlyCashFlow>> {
+    let flows = sqlx::query_as::<_, MonthlyCashFlow>(
+        r#"
+        SELECT
+            strftime('%Y-%m', transaction_date) AS month,
+            currency,
+            SUM(CASE WHEN amount > 0 THEN amount ELSE 0 END) AS income,
+            SUM(CASE WHEN amount < 0 THEN amount ELSE 0 END) AS expenses,
+            SUM(amount) AS net
+        FROM transactions
+        WHERE NOT excluded
+          AND (? IS NULL OR transaction_date >= ?)
+          AND (? IS NULL OR transaction_date <= ?)
+        GROUP BY month, currency
+        ORDER BY month DESC, currency
+        "#,
+    )
+    .bind(from)
+    .bind(from)
+    .bind(to)
+    .bind(to)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(flows)
+}
+
+pub async fn merchant_spending(
+    pool: &SqlitePool,
+    query: &MerchantSpendingQuery,
+) -> Result<Vec<MerchantSpending>> {
+    let spending = sqlx::query_as::<_, MerchantSpending>(
+        r#"
+        SELECT
+            m.id AS merchant_id,
+            m.name,
+            t.currency,
+            -SUM(t.amount) AS spent,
+            COUNT(*) AS transaction_count
+        FROM transactions t
+        JOIN merchants m ON m.id = t.merchant_id
+        WHERE t.amount < 0
+          AND NOT t.excluded
+          AND (? IS NULL OR t.transaction_date >= ?)
+          AND (? IS NULL OR t.transaction_date <= ?)
+          AND (? IS NULL OR t.account_id = ?)
+        GROUP BY m.id, t.currency
+        ORDER BY t.currency, spent DESC, m.name
+        "#,
+    )
+    .bind(query.from)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(query.to)
+    .bind(&query.account_id)
+    .bind(&query.account_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(spending)
+}
@@ -0,0 +1,98 @@
+use axum::{
+    Router,
+    routing::{get, post, put},
+};
+use tower_http::cors::CorsLayer;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::ApiDoc;
+use crate::app_state::AppState;
+use crate::handlers::*;
+
+/// Every API route, with the Swagger UI, served from `app_state`.
+pub fn router(app_state: AppState) -> Router {
+    Router::new()
+        .route("/api/accounts", get(get_accounts).post(create_account))
+        .route(
+            "/api/accounts/:id",
+            get(get_account).patch(update_account).delete(delete_account),
+        )
+        .route(
+            "/api/accounts/:id/transactions",
+            get(get_account_transactions),
+        )
+        .route("/api/accounts/:id/holdings", get(get_account_holdings))
+        .route(
+            "/api/transactions",
+            get(query_transactions).post(create_transaction),
+        )
+        .route("/api/transactions/suggestions", get(get_category_suggestions))
+        .route(
+            "/api/transactions/:id",
+            get(get_transaction)
+                .patch(update_transaction)
+                .delete(delete_transaction),
+        )
+        .route("/api/categories", get(get_categories).post(create_category))
+        .route(
+            "/api/categories/:id",
+            get(get_category)
+                .patch(update_category)
+                .delete(delete_category),
+        )
+        .route("/api/categories/:id/merge", post(merge_category))
+        .route("/api/rules", get(get_rules).post(create_rule))
+        .route("/api/rules/apply", post(apply_rules))
+        .route(
+            "/api/rules/:id",
+            get(get_rule).put(update_rule).delete(delete_rule),
+        )
+        .route("/api/merchants", get(get_merchants).post(create_merchant))
+        .route(
+            "/api/merchants/:id",
+            get(get_merchant).put(update_merchant).delete(delete_merchant),
+        )
+        .route("/api/merchants/:id/merge", post(merge_merchant))
+        .route(
+            "/api/exchange-rates",
+            get(get_exchange_rates).post(upsert_exchange_rates),
+        )
+        .route("/api/exchange-rates/import", post(import_exchange_rates))
+        .route(
+            "/api/exchange-rates/:id",
+            put(update_exchange_rate).delete(delete_exchange_rate),
+        )
+        .route("/api/reports/net-worth", get(get_net_worth))
+        .route("/api/reports/monthly", get(get_monthly_cash_flow))
+        .route("/api/reports/merchants", get(get_merchant_spending))
+        .route("/api/connections", get(get_connections).post(create_connection))
+        .route(
+            "/api/connections/:id",
+            get(get_connection)
+                .patch(update_connection)
+                .delete(delete_connection),
+        )
+        .route(
+            "/api/connections/simplefin/claim",
+            post(claim_simplefin_connection),
+        )
+        .route("/api/sync", post(trigger_sync))
+        .route("/api/sync/runs", get(get_sync_runs))
+        .route("/api/sync/status", get(get_sync_status))
+        .route(
+            "/api/sync/schedule",
+            get(get_sync_schedule)
+                .put(update_sync_schedule)
+                .delete(reset_sync_schedule),
+        )
+        .route("/api/sync/schedule/resume", post(resume_sync_schedule))
+        .route("/api/sync/backfill", get(get_backfills).post(start_backfill))
+        .route(
+            "/api/sync/backfill/:id",
+            get(get_backfill).delete(cancel_backfill),
+        )
+        .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
+        .layer(CorsLayer::permissive())
+        .with_state(app_state)
+}
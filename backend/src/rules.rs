@@ -0,0 +1,276 @@
+use anyhow::Result;
+use regex::{Regex, RegexBuilder};
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection, types::Json};
+
+use crate::models::{
+    AmountSign, ApplyRulesRequest, ApplyRulesResult, Rule, RuleChange, RuleFields, Transaction,
+};
+
+/// Compiled size limit for a rule pattern, well above anything hand-written.
+const PATTERN_SIZE_LIMIT: usize = 1 << 20;
+
+/// Compiles a rule pattern the way the engine matches it: case-insensitively.
+pub fn compile_pattern(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .size_limit(PATTERN_SIZE_LIMIT)
+        .build()
+}
+
+struct CompiledRule {
+    rule: Rule,
+    description: Option<Regex>,
+    payee: Option<Regex>,
+    memo: Option<Regex>,
+}
+
+impl CompiledRule {
+    fn compile(rule: Rule) -> Result<Self, regex::Error> {
+        let compile =
+            |pattern: &Option<String>| pattern.as_deref().map(compile_pattern).transpose();
+        Ok(Self {
+            description: compile(&rule.description_pattern)?,
+            payee: compile(&rule.payee_pattern)?,
+            memo: compile(&rule.memo_pattern)?,
+            rule,
+        })
+    }
+
+    fn matches(&self, transaction: &Transaction) -> bool {
+        let rule = &self.rule;
+        if rule
+            .account_id
+            .as_ref()
+            .is_some_and(|id| *id != transaction.account_id)
+        {
+            return false;
+        }
+
+        let amount = transaction.amount;
+        match rule.sign {
+            Some(AmountSign::Negative) if !amount.is_negative() => return false,
+            Some(AmountSign::Positive) if amount.is_negative() || amount.is_zero() => return false,
+            _ => {}
+        }
+        let currency = &transaction.currency;
+        if rule
+            .min_amount
+            .is_some_and(|min| amount.abs().cmp_threshold(currency, min).is_lt())
+            || rule
+                .max_amount
+                .is_some_and(|max| amount.abs().cmp_threshold(currency, max).is_gt())
+        {
+            return false;
+        }
+
+        pattern_matches(&self.description, Some(&transaction.description))
+            && pattern_matches(&self.payee, transaction.payee.as_deref())
+            && pattern_matches(&self.memo, transaction.memo.as_deref())
+    }
+}
+
+/// A missing pattern matches anything; a pattern never matches a missing field.
+fn pattern_matches(pattern: &Option<Regex>, text: Option<&str>) -> bool {
+    match pattern {
+        Some(pattern) => text.is_some_and(|text| pattern.is_match(text)),
+        None => true,
+    }
+}
+
+impl RuleFields {
+    pub fn of(transaction: &Transaction) -> Self {
+        Self {
+            category_id: transaction.category_id.clone(),
+            payee_override: transaction.payee_override.clone(),
+            tags: transaction.tags.0.clone(),
+            flagged: transaction.flagged,
+            excluded: transaction.excluded,
+        }
+    }
+}
+
+/// Outcome of running the rules against one transaction.
+pub struct Evaluation {
+    pub rule_ids: Vec<String>,
+    pub fields: RuleFields,
+}
+
+impl Evaluation {
+    pub fn changes(&self, transaction: &Transaction) -> bool {
+        self.fields != RuleFields::of(transaction)
+    }
+}
+
+/// Rules loaded from the database, in the order they run.
+pub struct RuleEngine {
+    rules: Vec<CompiledRule>,
+}
+
+impl RuleEngine {
+    /// Loads every enabled rule, or only `rule_id` (even when disabled, so it
+    /// can be previewed before being switched on).
+    pub async fn load(conn: &mut SqliteConnection, rule_id: Option<&str>) -> Result<Self> {
+        let rules = sqlx::query_as::<_, Rule>(
+            r#"
+            SELECT * FROM rules
+            WHERE (? IS NULL AND enabled) OR id = ?
+            ORDER BY priority, created_at, id
+            "#,
+        )
+        .bind(rule_id)
+        .bind(rule_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let rules = rules
+            .into_iter()
+            .filter_map(|rule| {
+                let id = rule.id.clone();
+                CompiledRule::compile(rule)
+                    .inspect_err(|e| {
+                        tracing::warn!("Skipping rule {} with invalid pattern: {}", id, e)
+                    })
+                    .ok()
+            })
+            .collect();
+
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Runs the matching rules in priority order. For each field the first
+    /// rule that sets it wins; tags from every matching rule are added.
+    /// A locked category is never changed.
+    pub fn evaluate(&self, transaction: &Transaction) -> Evaluation {
+        let mut fields = RuleFields::of(transaction);
+        let mut rule_ids = Vec::new();
+        let mut category_set = transaction.category_locked;
+        let mut payee_set = false;
+        let mut flagged_set = false;
+        let mut excluded_set = false;
+
+        for compiled in self.rules.iter().filter(|r| r.matches(transaction)) {
+            let rule = &compiled.rule;
+            rule_ids.push(rule.id.clone());
+
+            if !category_set && let Some(category_id) = &rule.set_category_id {
+                fields.category_id = Some(category_id.clone());
+                category_set = true;
+            }
+            if !payee_set && let Some(payee) = &rule.set_payee {
+                fields.payee_override = Some(payee.clone());
+                payee_set = true;
+            }
+            for tag in rule.add_tags.iter() {
+                if !fields.tags.contains(tag) {
+                    fields.tags.push(tag.clone());
+                }
+            }
+            if !flagged_set && let Some(flagged) = rule.set_flagged {
+                fields.flagged = flagged;
+                flagged_set = true;
+            }
+            if !excluded_set && let Some(excluded) = rule.set_excluded {
+                fields.excluded = excluded;
+                excluded_set = true;
+            }
+
+            if rule.stop_processing {
+                break;
+            }
+        }
+
+        Evaluation { rule_ids, fields }
+    }
+}
+
+pub async fn save_fields(
+    conn: &mut SqliteConnection,
+    transaction_id: &str,
+    fields: &RuleFields,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE transactions SET
+            category_source = CASE WHEN category_id IS ?1 THEN category_source ELSE 'rule' END,
+            category_id = ?1, payee_override = ?2, tags = ?3, flagged = ?4, excluded = ?5
+        WHERE id = ?6
+        "#,
+    )
+    .bind(&fields.category_id)
+    .bind(&fields.payee_override)
+    .bind(Json(&fields.tags))
+    .bind(fields.flagged)
+    .bind(fields.excluded)
+    .bind(transaction_id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Re-runs the rules over existing transactions, saving the results unless
+/// `request.dry_run` is set.
+pub async fn apply_rules(
+    conn: &mut SqliteConnection,
+    request: &ApplyRulesRequest,
+) -> Result<ApplyRulesResult> {
+    let engine = RuleEngine::load(conn, request.rule_id.as_deref()).await?;
+
+    let mut builder: QueryBuilder<Sqlite> =
+        QueryBuilder::new("SELECT * FROM transactions WHERE 1 = 1");
+    if let Some(account_id) = &request.account_id {
+        builder
+            .push(" AND account_id = ")
+            .push_bind(account_id.clone());
+    }
+    if let Some(from) = request.from {
+        builder.push(" AND transaction_date >= ").push_bind(from);
+    }
+    if let Some(to) = request.to {
+        builder.push(" AND transaction_date <= ").push_bind(to);
+    }
+    builder.push(" ORDER BY transaction_date, created_at, id");
+
+    let transactions = builder
+        .build_query_as::<Transaction>()
+        .fetch_all(&mut *conn)
+        .await?;
+
+    let mut result = ApplyRulesResult {
+        dry_run: request.dry_run,
+        scanned: transactions.len() as u32,
+        matched: 0,
+        changes: Vec::new(),
+    };
+    if engine.is_empty() {
+        return Ok(result);
+    }
+
+    for transaction in transactions {
+        let evaluation = engine.evaluate(&transaction);
+        if evaluation.rule_ids.is_empty() {
+            continue;
+        }
+        result.matched += 1;
+
+        if !evaluation.changes(&transaction) {
+            continue;
+        }
+        if !request.dry_run {
+            save_fields(conn, &transaction.id, &evaluation.fields).await?;
+        }
+        result.changes.push(RuleChange {
+            before: RuleFields::of(&transaction),
+            transaction_id: transaction.id,
+            description: transaction.description,
+            rule_ids: evaluation.rule_ids,
+            after: evaluation.fields,
+        });
+    }
+
+    Ok(result)
+}
@@ -1,64 +1,504 @@
-use tokio::time::{interval, Duration};
 use anyhow::Result;
-use std::sync::Arc;
+use chrono::{DateTime, Duration, Local, NaiveTime, TimeZone, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use utoipa::ToSchema;
 
-use crate::sync::SyncService;
+use crate::connections;
+use crate::cron::CronSchedule;
+use crate::simplefin::AuthenticationError;
+use crate::sync::{SyncService, SyncStats};
+use crate::sync_runs::SyncTrigger;
 
+/// Delay before the first retry after a failed sync; it doubles with every
+/// further consecutive failure.
+const BACKOFF_BASE: Duration = Duration::minutes(1);
+const BACKOFF_MAX: Duration = Duration::hours(6);
+
+/// A daily window in the server's local time during which scheduled syncs
+/// do not start. The window may span midnight, e.g. 22:00 to 06:00.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct QuietHours {
+    #[schema(value_type = String, example = "22:00")]
+    pub start: NaiveTime,
+    #[schema(value_type = String, example = "06:00")]
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    /// Moves a run that falls in the window to the end of the window.
+    fn postpone(&self, at: DateTime<Utc>) -> DateTime<Utc> {
+        self.postpone_in(&Local, at)
+    }
+
+    /// [`Self::postpone`] with the window in `timezone`. An end skipped by a
+    /// daylight-saving change moves to the same wall time an hour later.
+    fn postpone_in<Tz: TimeZone>(&self, timezone: &Tz, at: DateTime<Utc>) -> DateTime<Utc> {
+        let local = at.with_timezone(timezone);
+        if !self.contains(local.time()) {
+            return at;
+        }
+        let mut end_date = local.date_naive();
+        if self.start > self.end && local.time() >= self.start {
+            end_date = end_date.succ_opt().unwrap_or(end_date);
+        }
+        let end = end_date.and_time(self.end);
+        timezone
+            .from_local_datetime(&end)
+            .earliest()
+            .or_else(|| {
+                timezone
+                    .from_local_datetime(&(end + Duration::hours(1)))
+                    .earliest()
+            })
+            .map_or(at, |end| end.with_timezone(&Utc))
+    }
+}
+
+/// Parses `HH:MM-HH:MM`, as in `SYNC_QUIET_HOURS=22:00-06:00`.
+impl FromStr for QuietHours {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s
+            .split_once('-')
+            .ok_or_else(|| "expected a range such as 22:00-06:00".to_string())?;
+        let time = |text: &str| {
+            NaiveTime::parse_from_str(text.trim(), "%H:%M")
+                .map_err(|_| format!("{:?} is not a time such as 22:00", text.trim()))
+        };
+        Ok(Self {
+            start: time(start)?,
+            end: time(end)?,
+        })
+    }
+}
+
+/// When scheduled syncs run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct SyncSchedule {
+    /// Minutes between the end of one sync and the start of the next; ignored when `cron` is set
+    pub interval_minutes: u32,
+    /// Five-field cron expression in the server's local time, e.g. `0 */6 * * *`
+    pub cron: Option<String>,
+    pub quiet_hours: Option<QuietHours>,
+}
+
+/// Why scheduling stopped.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SyncPause {
+    #[schema(value_type = String, format = DateTime)]
+    pub since: DateTime<Utc>,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SyncScheduleState {
+    pub schedule: SyncSchedule,
+    /// False when the schedule comes from the environment
+    pub customized: bool,
+    /// Set when SimpleFin rejected the credentials. No scheduled syncs run
+    /// until scheduling is resumed or a manual sync succeeds.
+    pub paused: Option<SyncPause>,
+    pub consecutive_failures: u32,
+    /// Absent while paused, or until a connection is added
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub next_run_at: Option<DateTime<Utc>>,
+}
+
+#[derive(sqlx::FromRow)]
+struct ScheduleRow {
+    customized: bool,
+    interval_minutes: Option<u32>,
+    cron: Option<String>,
+    quiet_hours_start: Option<NaiveTime>,
+    quiet_hours_end: Option<NaiveTime>,
+    paused_at: Option<DateTime<Utc>>,
+    pause_reason: Option<String>,
+}
+
+struct SchedulerState {
+    schedule: SyncSchedule,
+    cron: Option<CronSchedule>,
+    customized: bool,
+    paused: Option<SyncPause>,
+    consecutive_failures: u32,
+    last_run_at: DateTime<Utc>,
+    /// Earliest retry after a failure, jitter included
+    retry_at: Option<DateTime<Utc>>,
+    next_run_at: Option<DateTime<Utc>>,
+}
+
+impl SchedulerState {
+    fn set_schedule(&mut self, schedule: SyncSchedule) {
+        self.cron = schedule.cron.as_deref().and_then(|cron| {
+            CronSchedule::parse(cron)
+                .inspect_err(|e| tracing::error!("Ignoring sync cron {:?}: {}", cron, e))
+                .ok()
+        });
+        self.schedule = schedule;
+    }
+
+    /// When the next scheduled sync starts, or `None` while paused.
+    fn next_run(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if self.paused.is_some() {
+            return None;
+        }
+        let mut next = match &self.cron {
+            Some(cron) => cron
+                .next_after(&now.with_timezone(&Local))?
+                .with_timezone(&Utc),
+            None => {
+                let interval = Duration::minutes(self.schedule.interval_minutes.into());
+                (self.last_run_at + interval).max(now)
+            }
+        };
+        if let Some(retry_at) = self.retry_at {
+            next = next.max(retry_at);
+        }
+        if let Some(quiet_hours) = &self.schedule.quiet_hours {
+            next = quiet_hours.postpone(next);
+        }
+        Some(next)
+    }
+
+    fn state(&self) -> SyncScheduleState {
+        SyncScheduleState {
+            schedule: self.schedule.clone(),
+            customized: self.customized,
+            paused: self.paused.clone(),
+            consecutive_failures: self.consecutive_failures,
+            next_run_at: self.next_run_at,
+        }
+    }
+}
+
+/// Exponential backoff for the given number of consecutive failures, with
+/// the delay drawn between half and all of it so clients do not retry in step.
+fn backoff(failures: u32) -> Duration {
+    let exponent = failures.saturating_sub(1).min(16);
+    let delay = (BACKOFF_BASE * 2i32.pow(exponent)).min(BACKOFF_MAX);
+    let seconds = delay.num_seconds();
+    Duration::seconds(rand::thread_rng().gen_range(seconds / 2..=seconds))
+}
+
+/// Runs syncs on a schedule stored in `sync_schedule`, falling back to the
+/// schedule given at startup.
 pub struct SyncScheduler {
     sync_service: Arc<SyncService>,
-    interval_duration: Duration,
+    pool: SqlitePool,
+    default_schedule: SyncSchedule,
+    state: Mutex<SchedulerState>,
+    /// Wakes the scheduling loop to recompute the next run
+    wake: Notify,
+    /// Set once the loop runs; it does not start until there is a connection
+    started: AtomicBool,
 }
 
 impl SyncScheduler {
-    pub fn new(sync_service: Arc<SyncService>, interval_minutes: u64) -> Self {
-        Self {
-            sync_service,
-            interval_duration: Duration::from_secs(interval_minutes * 60),
-        }
-    }
-
-    pub async fn start_background_sync(&self) -> Result<()> {
-        let sync_service = self.sync_service.clone();
-        let interval_duration = self.interval_duration;
-
-        tokio::spawn(async move {
-            let mut ticker = interval(interval_duration);
-            
-            loop {
-                ticker.tick().await;
-                
-                tracing::info!("Starting scheduled SimpleFin sync...");
-                
-                match sync_service.sync_all().await {
-                    Ok(stats) => {
-                        tracing::info!(
-                            "Scheduled sync completed successfully: {} accounts created, {} accounts updated, {} transactions created",
-                            stats.accounts_created,
-                            stats.accounts_updated,
-                            stats.transactions_created
-                        );
-                    }
-                    Err(e) => {
-                        tracing::error!("Scheduled sync failed: {}", e);
-                        // Continue running despite errors - we'll try again next interval
-                    }
-                }
+    /// Loads the saved schedule and pause state.
+    pub async fn load(
+        sync_service: Arc<SyncService>,
+        pool: SqlitePool,
+        default_schedule: SyncSchedule,
+    ) -> Result<Arc<Self>> {
+        let row = sqlx::query_as::<_, ScheduleRow>("SELECT * FROM sync_schedule WHERE id = 1")
+            .fetch_one(&pool)
+            .await?;
+
+        let schedule = if row.customized {
+            SyncSchedule {
+                interval_minutes: row
+                    .interval_minutes
+                    .unwrap_or(default_schedule.interval_minutes),
+                cron: row.cron,
+                quiet_hours: row
+                    .quiet_hours_start
+                    .zip(row.quiet_hours_end)
+                    .map(|(start, end)| QuietHours { start, end }),
             }
+        } else {
+            default_schedule.clone()
+        };
+        let paused = row.paused_at.map(|since| SyncPause {
+            since,
+            reason: row.pause_reason.unwrap_or_default(),
         });
+        if let Some(pause) = &paused {
+            tracing::warn!(
+                "Scheduled syncs are paused since {}: {}",
+                pause.since,
+                pause.reason
+            );
+        }
 
-        tracing::info!(
-            "Background sync scheduler started with interval of {} minutes", 
-            interval_duration.as_secs() / 60
-        );
+        let mut state = SchedulerState {
+            schedule: default_schedule.clone(),
+            cron: None,
+            customized: row.customized,
+            paused,
+            consecutive_failures: 0,
+            last_run_at: Utc::now(),
+            retry_at: None,
+            next_run_at: None,
+        };
+        state.set_schedule(schedule);
+        state.next_run_at = state.next_run(Utc::now());
+
+        Ok(Arc::new(Self {
+            sync_service,
+            pool,
+            default_schedule,
+            state: Mutex::new(state),
+            wake: Notify::new(),
+            started: AtomicBool::new(false),
+        }))
+    }
+
+    pub fn state(&self) -> SyncScheduleState {
+        let mut state = self.state.lock().unwrap().state();
+        state.next_run_at = state.next_run_at.filter(|_| self.is_started());
+        state
+    }
+
+    pub fn next_run_at(&self) -> Option<DateTime<Utc>> {
+        self.state().next_run_at
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.started.load(Ordering::Relaxed)
+    }
+
+    pub fn paused(&self) -> Option<SyncPause> {
+        self.state.lock().unwrap().paused.clone()
+    }
+
+    /// Replaces the schedule, or goes back to the startup schedule for `None`.
+    /// The caller validates the schedule.
+    pub async fn set_schedule(&self, schedule: Option<SyncSchedule>) -> Result<SyncScheduleState> {
+        let customized = schedule.is_some();
+        let schedule = schedule.unwrap_or_else(|| self.default_schedule.clone());
+        let quiet_hours = schedule.quiet_hours.as_ref();
+        sqlx::query(
+            r#"
+            UPDATE sync_schedule
+            SET customized = ?, interval_minutes = ?, cron = ?,
+                quiet_hours_start = ?, quiet_hours_end = ?
+            WHERE id = 1
+            "#,
+        )
+        .bind(customized)
+        .bind(customized.then_some(schedule.interval_minutes))
+        .bind(schedule.cron.as_deref().filter(|_| customized))
+        .bind(quiet_hours.filter(|_| customized).map(|q| q.start))
+        .bind(quiet_hours.filter(|_| customized).map(|q| q.end))
+        .execute(&self.pool)
+        .await?;
+
+        {
+            let mut state = self.state.lock().unwrap();
+            state.customized = customized;
+            state.set_schedule(schedule);
+        }
+        self.reschedule();
+        Ok(self.state())
+    }
+
+    /// Clears a pause, e.g. after the user fixed the SimpleFin connection,
+    /// and lets scheduled syncs try every connection's credentials again.
+    pub async fn resume(&self) -> Result<SyncScheduleState> {
+        connections::clear_auth_failure(&self.pool, None).await?;
+        self.set_paused(None).await?;
+        self.reschedule();
+        Ok(self.state())
+    }
 
+    async fn set_paused(&self, pause: Option<SyncPause>) -> Result<()> {
+        sqlx::query("UPDATE sync_schedule SET paused_at = ?, pause_reason = ? WHERE id = 1")
+            .bind(pause.as_ref().map(|p| p.since))
+            .bind(pause.as_ref().map(|p| p.reason.as_str()))
+            .execute(&self.pool)
+            .await?;
+        let mut state = self.state.lock().unwrap();
+        state.paused = pause;
+        state.consecutive_failures = 0;
+        state.retry_at = None;
         Ok(())
     }
+
+    /// Updates the schedule after a sync of any kind.
+    ///
+    /// Rejected credentials pause scheduling; a success clears a pause.
+    /// Other failures of startup and scheduled syncs back off exponentially.
+    pub async fn record(&self, result: &Result<SyncStats>, trigger: SyncTrigger) {
+        let now = Utc::now();
+        let was_paused = self.paused().is_some();
+        let outcome = match result {
+            Ok(_) if was_paused => {
+                tracing::info!("Sync succeeded; resuming scheduled syncs");
+                self.set_paused(None).await
+            }
+            Ok(_) => {
+                let mut state = self.state.lock().unwrap();
+                state.consecutive_failures = 0;
+                state.retry_at = None;
+                Ok(())
+            }
+            Err(e) => match e.downcast_ref::<AuthenticationError>() {
+                Some(auth) if !was_paused => {
+                    tracing::error!(
+                        "{}. Scheduled syncs are paused until the connection is fixed and scheduling is resumed",
+                        auth
+                    );
+                    self.set_paused(Some(SyncPause {
+                        since: now,
+                        reason: auth.to_string(),
+                    }))
+                    .await
+                }
+                Some(_) => Ok(()),
+                None if trigger == SyncTrigger::Manual => Ok(()),
+                None => {
+                    let mut state = self.state.lock().unwrap();
+                    state.consecutive_failures += 1;
+                    let delay = backoff(state.consecutive_failures);
+                    state.retry_at = Some(now + delay);
+                    tracing::warn!(
+                        "Sync failed ({} in a row); retrying in {} seconds at the earliest",
+                        state.consecutive_failures,
+                        delay.num_seconds()
+                    );
+                    Ok(())
+                }
+            },
+        };
+        if let Err(e) = outcome {
+            tracing::error!("Failed to save the sync pause state: {}", e);
+        }
+
+        self.state.lock().unwrap().last_run_at = now;
+        self.reschedule();
+    }
+
+    /// Recomputes the next run and wakes the loop to wait for it instead.
+    fn reschedule(&self) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.next_run_at = state.next_run(Utc::now());
+        }
+        self.wake.notify_one();
+    }
+
+    /// Starts the scheduling loop. The first run follows the schedule from
+    /// now; the startup sync has just run.
+    pub fn start(self: &Arc<Self>) {
+        if self.started.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        let scheduler = self.clone();
+        tokio::spawn(async move { scheduler.run().await });
+
+        let state = self.state();
+        match &state.schedule.cron {
+            Some(cron) => {
+                tracing::info!("Background sync scheduler started with schedule {:?}", cron)
+            }
+            None => tracing::info!(
+                "Background sync scheduler started with interval of {} minutes",
+                state.schedule.interval_minutes
+            ),
+        }
+    }
+
+    /// Starts the scheduling loop once a connection is added to a server that
+    /// started without one. With no startup sync to count from, an interval
+    /// schedule runs the first sync right away.
+    pub fn start_late(self: &Arc<Self>) {
+        if self.is_started() {
+            return;
+        }
+        self.state.lock().unwrap().last_run_at = DateTime::<Utc>::MIN_UTC;
+        self.start();
+        self.reschedule();
+    }
+
+    async fn run(&self) {
+        loop {
+            let next = {
+                let mut state = self.state.lock().unwrap();
+                state.next_run_at = state.next_run(Utc::now());
+                state.next_run_at
+            };
+
+            let Some(next) = next else {
+                // Paused, or a cron expression that never matches
+                self.wake.notified().await;
+                continue;
+            };
+            let wait = (next - Utc::now()).to_std().unwrap_or_default();
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = self.wake.notified() => continue,
+            }
+
+            // Every connection may have been disabled or removed since
+            match connections::any_enabled(&self.pool).await {
+                Ok(false) => {
+                    tracing::info!("Skipping scheduled sync; no connection is enabled");
+                    self.state.lock().unwrap().last_run_at = Utc::now();
+                    continue;
+                }
+                Ok(true) => {}
+                Err(e) => tracing::error!("Failed to load connections: {}", e),
+            }
+
+            tracing::info!("Starting scheduled SimpleFin sync...");
+
+            let result = self.sync_service.sync_all(SyncTrigger::Scheduled).await;
+            match &result {
+                Ok(stats) => {
+                    tracing::info!(
+                        "Scheduled sync completed successfully: {} accounts created, {} accounts updated, {} transactions created",
+                        stats.accounts_created,
+                        stats.accounts_updated,
+                        stats.transactions_created
+                    );
+                }
+                Err(e) => {
+                    tracing::error!("Scheduled sync failed: {}", e);
+                }
+            }
+            self.record(&result, SyncTrigger::Scheduled).await;
+        }
+    }
 }
 
-pub async fn perform_initial_sync(sync_service: &SyncService) -> Result<()> {
+pub async fn perform_initial_sync(scheduler: &SyncScheduler) -> Result<()> {
+    if let Some(pause) = scheduler.paused() {
+        tracing::warn!(
+            "Skipping initial sync; scheduled syncs are paused: {}",
+            pause.reason
+        );
+        return Ok(());
+    }
+
     tracing::info!("Performing initial SimpleFin sync...");
-    
-    match sync_service.sync_all().await {
+
+    let result = scheduler.sync_service.sync_all(SyncTrigger::Startup).await;
+    match &result {
         Ok(stats) => {
             tracing::info!(
                 "Initial sync completed: {} accounts created, {} accounts updated, {} transactions created in {}ms",
@@ -67,13 +507,122 @@ pub async fn perform_initial_sync(sync_service: &SyncService) -> Result<()> {
                 stats.transactions_created,
                 stats.sync_duration_ms
             );
-            Ok(())
         }
         Err(e) => {
             tracing::warn!("Initial sync failed, but server will continue: {}", e);
             // Don't fail server startup if initial sync fails
             // The background scheduler will retry
-            Ok(())
         }
     }
-}
\ No newline at end of file
+    scheduler.record(&result, SyncTrigger::Startup).await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cron::tests::{SpringForward, date};
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    fn time(text: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(text, "%H:%M").unwrap()
+    }
+
+    fn quiet(start: &str, end: &str) -> QuietHours {
+        QuietHours {
+            start: time(start),
+            end: time(end),
+        }
+    }
+
+    fn at(day: NaiveDate, clock: &str) -> NaiveDateTime {
+        day.and_time(time(clock))
+    }
+
+    /// Postpones a local time in [`SpringForward`], returning local time.
+    fn postpone(hours: &QuietHours, local: NaiveDateTime) -> NaiveDateTime {
+        let at = SpringForward
+            .from_local_datetime(&local)
+            .unwrap()
+            .with_timezone(&Utc);
+        hours
+            .postpone_in(&SpringForward, at)
+            .with_timezone(&SpringForward)
+            .naive_local()
+    }
+
+    #[test]
+    fn quiet_hours_parse_from_a_range() {
+        assert_eq!("22:00-06:00".parse(), Ok(quiet("22:00", "06:00")));
+        assert_eq!(" 08:00 - 09:30 ".parse(), Ok(quiet("08:00", "09:30")));
+        assert!("22:00".parse::<QuietHours>().is_err());
+        assert!("25:00-06:00".parse::<QuietHours>().is_err());
+        assert!("22:00-6pm".parse::<QuietHours>().is_err());
+    }
+
+    #[test]
+    fn quiet_hours_may_span_midnight() {
+        let hours = quiet("22:00", "06:00");
+        assert!(hours.contains(time("22:00")));
+        assert!(hours.contains(time("23:59")));
+        assert!(hours.contains(time("00:00")));
+        assert!(hours.contains(time("05:59")));
+        assert!(!hours.contains(time("06:00")));
+        assert!(!hours.contains(time("12:00")));
+
+        let hours = quiet("12:00", "13:00");
+        assert!(hours.contains(time("12:30")));
+        assert!(!hours.contains(time("13:00")));
+        assert!(!hours.contains(time("23:00")));
+    }
+
+    #[test]
+    fn runs_in_quiet_hours_move_to_their_end() {
+        let hours = quiet("22:00", "06:00");
+        let day = date(2024, 1, 10);
+        let next_day = date(2024, 1, 11);
+        // Before midnight, the window ends the next morning
+        assert_eq!(postpone(&hours, at(day, "23:00")), at(next_day, "06:00"));
+        assert_eq!(postpone(&hours, at(day, "22:00")), at(next_day, "06:00"));
+        // After midnight, the same morning
+        assert_eq!(
+            postpone(&hours, at(next_day, "01:30")),
+            at(next_day, "06:00")
+        );
+        // Outside the window nothing moves
+        assert_eq!(postpone(&hours, at(day, "06:00")), at(day, "06:00"));
+        assert_eq!(postpone(&hours, at(day, "12:00")), at(day, "12:00"));
+    }
+
+    #[test]
+    fn quiet_hours_ending_in_a_daylight_saving_gap_end_an_hour_later() {
+        // 02:30 does not exist on 2024-03-10
+        let hours = quiet("01:00", "02:30");
+        let change = date(2024, 3, 10);
+        assert_eq!(postpone(&hours, at(change, "01:15")), at(change, "03:30"));
+        assert_eq!(
+            postpone(&hours, at(date(2024, 3, 11), "01:15")),
+            at(date(2024, 3, 11), "02:30")
+        );
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let within = |failures: u32, max: Duration| {
+            for _ in 0..50 {
+                let delay = backoff(failures);
+                assert!(
+                    delay >= max / 2 && delay <= max,
+                    "{failures} failures: {delay}"
+                );
+            }
+        };
+        within(0, BACKOFF_BASE);
+        within(1, BACKOFF_BASE);
+        within(2, BACKOFF_BASE * 2);
+        within(4, BACKOFF_BASE * 8);
+        within(9, BACKOFF_BASE * 256);
+        within(10, BACKOFF_MAX);
+        within(u32::MAX, BACKOFF_MAX);
+    }
+}
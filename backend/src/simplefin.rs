@@ -1,10 +1,46 @@
 use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use base64::{Engine, engine::general_purpose::STANDARD};
 use chrono::{DateTime, Utc};
-use reqwest::Client;
+use reqwest::{Client, StatusCode, header::CONTENT_LENGTH};
 use serde::Deserialize;
-use std::collections::HashMap;
 use url::Url;
 
+use crate::credentials::Secret;
+use crate::money::{Money, MoneyParseError, normalize_currency};
+use crate::provider::{
+    self, BankDataProvider, FetchOptions, ProviderAccount, ProviderData, ProviderHolding,
+    ProviderMessage, ProviderTransaction,
+};
+
+/// SimpleFin rejected the access URL's credentials, e.g. because access was
+/// revoked. Retrying will not help until the user reconnects.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("SimpleFin rejected the access credentials ({0})")]
+pub struct AuthenticationError(pub StatusCode);
+
+/// Why a setup token could not be exchanged for an access URL.
+#[derive(Debug, thiserror::Error)]
+pub enum ClaimError {
+    #[error("not a SimpleFin setup token: {0}")]
+    InvalidToken(&'static str),
+    #[error("SimpleFin rejected the setup token ({0}); it may have been claimed already")]
+    Rejected(StatusCode),
+    /// The request failed. The error's URL is removed, since the claim URL
+    /// is as good as the token.
+    #[error("could not reach SimpleFin")]
+    Request(#[source] reqwest::Error),
+    /// The token was claimed, so the URL is the only copy of the credentials.
+    #[error("SimpleFin returned an invalid access URL")]
+    InvalidAccessUrl(Secret),
+}
+
+impl From<reqwest::Error> for ClaimError {
+    fn from(err: reqwest::Error) -> Self {
+        ClaimError::Request(err.without_url())
+    }
+}
+
 // SimpleFin API Response Types
 #[derive(Debug, Deserialize, Clone)]
 pub struct SimplefinTransaction {
@@ -29,21 +65,76 @@ pub struct SimplefinAccount {
     pub id: String,
     pub name: String,
     pub org: Option<SimplefinOrganization>,
+    pub currency: Option<String>,
     pub balance: String,
     #[serde(rename = "available-balance")]
     pub available_balance_raw: Option<String>,
     #[serde(skip)]
-    pub available_balance: f64,
+    pub available_balance: Option<Money>,
     #[serde(skip)]
     pub is_credit_card: bool,
     pub transactions: Option<Vec<SimplefinTransaction>>,
+    pub holdings: Option<Vec<SimplefinHolding>>,
+}
+
+/// A position in an investment account.
+#[derive(Debug, Deserialize, Clone)]
+pub struct SimplefinHolding {
+    pub id: String,
+    pub currency: Option<String>,
+    pub symbol: Option<String>,
+    #[serde(default)]
+    pub description: String,
+    pub shares: String,
+    pub market_value: String,
+    pub cost_basis: Option<String>,
+}
+
+/// Structured form of an `errors` message, sent by newer SimpleFin servers.
+#[derive(Debug, Deserialize, Clone)]
+pub struct SimplefinError {
+    pub code: Option<String>,
+    pub msg: String,
+    /// SimpleFin ID of the account the error concerns
+    pub account_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct SimplefinAccountSet {
+    /// Messages for the user, e.g. that an institution needs reauthentication
+    #[serde(default)]
+    pub errors: Vec<String>,
+    #[serde(default)]
+    pub errlist: Vec<SimplefinError>,
     pub accounts: Vec<SimplefinAccount>,
 }
 
+impl SimplefinAccountSet {
+    /// Every reported message with the SimpleFin account it concerns, when
+    /// the server said. Messages sent in both forms are returned once.
+    pub fn messages(&self) -> Vec<(String, Option<String>)> {
+        let mut messages: Vec<(String, Option<String>)> = self
+            .errlist
+            .iter()
+            .map(|error| (error.msg.trim().to_string(), error.account_id.clone()))
+            .collect();
+        for error in &self.errors {
+            let error = error.trim();
+            if !messages.iter().any(|(message, _)| message == error) {
+                messages.push((error.to_string(), None));
+            }
+        }
+        messages.retain(|(message, _)| !message.is_empty());
+        messages
+    }
+}
+
+fn http_client() -> reqwest::Result<Client> {
+    Client::builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .build()
+}
+
 pub struct SimplefinClient {
     client: Client,
     base_url: String,
@@ -63,16 +154,16 @@ impl SimplefinClient {
             return Err(anyhow!("SimpleFin access URL must contain username"));
         }
 
-        let base_url = format!(
-            "{}://{}{}",
-            parsed.scheme(),
-            parsed.host_str().unwrap_or(""),
-            parsed.path().trim_end_matches('/')
-        );
+        // Keep the scheme, host, port and path; credentials go in the auth header
+        let mut base = parsed.clone();
+        base.set_username("")
+            .and_then(|_| base.set_password(None))
+            .map_err(|_| anyhow!("Invalid SimpleFin access URL"))?;
+        base.set_query(None);
+        base.set_fragment(None);
+        let base_url = base.as_str().trim_end_matches('/').to_string();
 
-        let client = Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .build()?;
+        let client = http_client()?;
 
         Ok(Self {
             client,
@@ -82,17 +173,63 @@ impl SimplefinClient {
         })
     }
 
-    pub async fn fetch_accounts(&self) -> Result<SimplefinAccountSet> {
-        let days_back = 30;
-        let start_timestamp = (chrono::Utc::now().timestamp() - (days_back * 86_400)) as i64;
+    /// Exchanges a setup token, the base64-encoded claim URL the user gets
+    /// from their SimpleFin bridge, for an access URL. A token can be
+    /// claimed only once.
+    pub async fn claim(setup_token: &str) -> Result<Secret, ClaimError> {
+        let decoded = STANDARD
+            .decode(setup_token.trim())
+            .map_err(|_| ClaimError::InvalidToken("not base64"))?;
+        let claim_url = String::from_utf8(decoded)
+            .ok()
+            .and_then(|url| Url::parse(url.trim()).ok())
+            .filter(|url| matches!(url.scheme(), "http" | "https"))
+            .ok_or(ClaimError::InvalidToken("does not contain a claim URL"))?;
 
-        let mut params = HashMap::new();
-        params.insert("start-date", start_timestamp.to_string());
-        params.insert("pending", "1".to_string());
+        tracing::info!("Claiming SimpleFin access URL from {}", claim_url.origin().ascii_serialization());
+
+        let response = http_client()?
+            .post(claim_url)
+            .header(CONTENT_LENGTH, "0")
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(ClaimError::Rejected(response.status()));
+        }
+
+        let access_url = Secret::new(response.text().await?.trim().to_string());
+        if Self::new(access_url.expose().to_string()).is_err() {
+            return Err(ClaimError::InvalidAccessUrl(access_url));
+        }
+        Ok(access_url)
+    }
+
+    /// The server this client talks to, without credentials. Sync progress
+    /// is recorded against it.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub async fn fetch_accounts(&self, options: &FetchOptions) -> Result<SimplefinAccountSet> {
+        let mut params = vec![("pending", "1".to_string())];
+        if let Some(start_date) = options.start_date {
+            params.push(("start-date", start_date.timestamp().to_string()));
+        }
+        if let Some(end_date) = options.end_date {
+            params.push(("end-date", end_date.timestamp().to_string()));
+        }
+        for account_id in &options.account_ids {
+            params.push(("account", account_id.clone()));
+        }
 
         let url = format!("{}/accounts", self.base_url);
 
-        tracing::info!("Fetching accounts from SimpleFin: {}", url);
+        tracing::info!(
+            "Fetching accounts from SimpleFin: {} (from {:?} to {:?})",
+            url,
+            options.start_date,
+            options.end_date
+        );
 
         let response = self
             .client
@@ -103,6 +240,9 @@ impl SimplefinClient {
             .await
             .map_err(|e| anyhow!("Failed to fetch from SimpleFin: {}", e))?;
 
+        if matches!(response.status(), StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) {
+            return Err(AuthenticationError(response.status()).into());
+        }
         if !response.status().is_success() {
             let status = response.status();
             let text = response.text().await.unwrap_or_default();
@@ -117,12 +257,13 @@ impl SimplefinClient {
         // Post-process accounts to normalize fields and detect credit cards
         for account in &mut account_set.accounts {
             // Parse available balance
-            if let Some(ref avail_balance) = account.available_balance_raw {
-                account.available_balance = avail_balance.parse::<f64>().unwrap_or(0.0);
-            }
+            account.available_balance = account
+                .available_balance_raw
+                .as_deref()
+                .and_then(|raw| Money::parse(raw, &account.currency_code()).ok());
 
             // Detect credit cards: available balance of 0 typically indicates credit card
-            account.is_credit_card = account.available_balance == 0.0;
+            account.is_credit_card = account.available_balance.is_none_or(Money::is_zero);
         }
 
         tracing::info!(
@@ -134,20 +275,119 @@ impl SimplefinClient {
     }
 }
 
+#[async_trait]
+impl BankDataProvider for SimplefinClient {
+    fn name(&self) -> &'static str {
+        provider::SIMPLEFIN
+    }
+
+    async fn fetch(&self, options: &FetchOptions) -> Result<ProviderData> {
+        let account_set = self.fetch_accounts(options).await?;
+        let messages = account_set
+            .messages()
+            .into_iter()
+            .map(|(message, account_id)| ProviderMessage {
+                message,
+                account_id,
+            })
+            .collect();
+        let accounts = account_set
+            .accounts
+            .into_iter()
+            .filter_map(|account| {
+                account
+                    .normalize()
+                    .inspect_err(|e| tracing::warn!("Skipping SimpleFin account {}: {}", account.id, e))
+                    .ok()
+            })
+            .collect();
+        Ok(ProviderData { accounts, messages })
+    }
+}
+
 impl SimplefinTransaction {
+    /// When the transaction posted, or when it happened if it has not posted
+    /// yet. SimpleFin reports `posted` as 0 for pending transactions.
     pub fn to_posted_date(&self) -> Option<DateTime<Utc>> {
-        let timestamp = self.posted.or(self.transacted_at)?;
+        let timestamp = self
+            .posted
+            .filter(|&posted| posted > 0)
+            .or(self.transacted_at)?;
         DateTime::from_timestamp(timestamp, 0)
     }
 
-    pub fn amount_as_f64(&self) -> f64 {
-        self.amount.parse::<f64>().unwrap_or(0.0)
+    /// The amount in `currency`, the currency of the account.
+    pub fn amount(&self, currency: &str) -> Result<Money, MoneyParseError> {
+        Money::parse(&self.amount, currency)
     }
 }
 
 impl SimplefinAccount {
-    pub fn balance_as_f64(&self) -> f64 {
-        self.balance.parse::<f64>().unwrap_or(0.0)
+    pub fn balance(&self) -> Result<Money, MoneyParseError> {
+        Money::parse(&self.balance, &self.currency_code())
+    }
+
+    /// Converts to the provider-neutral form, leaving out transactions and
+    /// holdings that cannot be parsed.
+    fn normalize(&self) -> Result<ProviderAccount, MoneyParseError> {
+        let currency = self.currency_code();
+        let mut unreadable_transaction_ids = Vec::new();
+        let transactions = self.transactions.as_ref().map(|transactions| {
+            transactions
+                .iter()
+                .filter_map(|transaction| {
+                    let amount = match transaction.amount(&currency) {
+                        Ok(amount) => amount,
+                        Err(e) => {
+                            tracing::warn!("Skipping SimpleFin transaction {}: {}", transaction.id, e);
+                            unreadable_transaction_ids.push(transaction.id.clone());
+                            return None;
+                        }
+                    };
+                    Some(ProviderTransaction {
+                        external_id: transaction.id.clone(),
+                        amount,
+                        posted_at: transaction.to_posted_date(),
+                        description: transaction.description.clone(),
+                        payee: transaction.payee.clone(),
+                        memo: transaction.memo.clone(),
+                        pending: transaction.pending.unwrap_or(false),
+                    })
+                })
+                .collect()
+        });
+        let holdings = self.holdings.as_ref().map(|holdings| {
+            holdings
+                .iter()
+                .filter_map(|holding| {
+                    holding
+                        .normalize(&currency)
+                        .inspect_err(|e| tracing::warn!("Skipping SimpleFin holding {}: {}", holding.id, e))
+                        .ok()
+                })
+                .collect()
+        });
+
+        Ok(ProviderAccount {
+            external_id: self.id.clone(),
+            name: self.name.clone(),
+            institution: self.institution_name(),
+            currency,
+            balance: self.balance()?,
+            available_balance: self.available_balance,
+            is_credit_card: self.is_credit_card,
+            transactions,
+            unreadable_transaction_ids,
+            holdings,
+        })
+    }
+
+    /// The account's currency, falling back to USD for bridges that omit it.
+    pub fn currency_code(&self) -> String {
+        self.currency
+            .as_deref()
+            .map(normalize_currency)
+            .unwrap_or_else(|| "USD".to_string())
     }
 
     pub fn institution_name(&self) -> String {
@@ -157,3 +397,31 @@ impl SimplefinAccount {
             .unwrap_or_else(|| "Unknown".to_string())
     }
 }
+
+impl SimplefinHolding {
+    fn normalize(&self, account_currency: &str) -> Result<ProviderHolding> {
+        let currency = self
+            .currency
+            .as_deref()
+            .filter(|currency| !currency.is_empty())
+            .map_or_else(|| account_currency.to_string(), normalize_currency);
+        Ok(ProviderHolding {
+            external_id: self.id.clone(),
+            symbol: self.symbol.clone().filter(|symbol| !symbol.is_empty()),
+            description: self.description.clone(),
+            shares: self
+                .shares
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("invalid share count {:?}", self.shares))?,
+            market_value: Money::parse(&self.market_value, &currency)?,
+            cost_basis: self
+                .cost_basis
+                .as_deref()
+                .filter(|cost| !cost.trim().is_empty())
+                .map(|cost| Money::parse(cost, &currency))
+                .transpose()?,
+            currency,
+        })
+    }
+}
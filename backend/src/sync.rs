@@ -1,89 +1,409 @@
 use sqlx::SqlitePool;
-use anyhow::Result;
-use chrono::Utc;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Days, Duration, NaiveDate, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::watch;
 use uuid::Uuid;
 use serde::{Serialize, Deserialize};
 use utoipa::ToSchema;
 
-use crate::simplefin::{SimplefinClient, SimplefinAccount, SimplefinTransaction};
-use crate::models::Account;
+use crate::simplefin::AuthenticationError;
+use crate::backfill::{BackfillJob, BackfillJobs, BackfillStatus};
+use crate::classifier::{self, Classifier};
+use crate::merchants::MerchantMatcher;
+use crate::connections;
+use crate::credentials::CredentialCipher;
+use crate::models::{Account, Connection, AccountType, CategorySource, Transaction};
+use crate::money::{Money, MoneyParseError};
+use crate::provider::{
+    self, BankDataProvider, FetchOptions, ProviderAccount, ProviderData, ProviderHolding, ProviderMessage,
+    ProviderTransaction, date_windows,
+};
+use crate::rules::{self, RuleEngine};
+use crate::sync_runs::{self, SyncTrigger};
 
-#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
 pub struct SyncStats {
     pub accounts_updated: u32,
     pub accounts_created: u32,
     pub transactions_created: u32,
+    /// New transactions changed by at least one rule
+    pub transactions_ruled: u32,
+    /// New transactions categorized by a confident suggestion
+    pub transactions_auto_categorized: u32,
+    /// Existing transactions whose pending flag, amount or posted date changed
+    pub transactions_updated: u32,
+    /// Pending transactions that disappeared from the feed and were removed
+    pub pending_removed: u32,
+    /// Pending transactions replaced by their posted version under a new ID
+    pub pending_merges: Vec<PendingMerge>,
+    /// Problems the provider reported, such as a bank that needs reauthentication
+    pub issues: Vec<SyncIssue>,
+    /// Connections that could not be synced while others could; nothing
+    /// from them was stored
+    pub failed_connections: Vec<ConnectionFailure>,
     pub balance_records_created: u32,
     pub sync_duration_ms: u64,
 }
 
+/// How far a posted transaction's amount may differ from the pending one it
+/// replaces, e.g. after a tip is added.
+const PENDING_AMOUNT_TOLERANCE_PERCENT: i64 = 30;
+/// Smallest tolerance, in major units of the account's currency.
+const PENDING_AMOUNT_TOLERANCE_MIN: i64 = 1;
+/// How many days before the posted date a matching pending transaction may be dated.
+const PENDING_MATCH_DAYS: u64 = 7;
+
+/// A pending transaction that posted under a new external ID. The existing
+/// row is updated in place so the user's edits are kept.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(into = "PendingMergeRecord", try_from = "PendingMergeRecord")]
+pub struct PendingMerge {
+    pub transaction_id: String,
+    pub account_id: String,
+    pub pending_external_id: String,
+    pub posted_external_id: String,
+    pub pending_amount: Money,
+    pub posted_amount: Money,
+    /// Currency of the account and both amounts
+    pub currency: String,
+}
+
+/// A [`PendingMerge`] as stored with its sync run, with the amounts written in
+/// the scale of its currency.
+#[derive(Serialize, Deserialize)]
+struct PendingMergeRecord {
+    transaction_id: String,
+    account_id: String,
+    pending_external_id: String,
+    posted_external_id: String,
+    pending_amount: String,
+    posted_amount: String,
+    /// Absent from runs recorded before amounts were scaled by currency,
+    /// when every amount had two fractional digits
+    #[serde(default)]
+    currency: String,
+}
+
+impl From<PendingMerge> for PendingMergeRecord {
+    fn from(merge: PendingMerge) -> Self {
+        Self {
+            transaction_id: merge.transaction_id,
+            account_id: merge.account_id,
+            pending_external_id: merge.pending_external_id,
+            posted_external_id: merge.posted_external_id,
+            pending_amount: merge.pending_amount.display(&merge.currency).to_string(),
+            posted_amount: merge.posted_amount.display(&merge.currency).to_string(),
+            currency: merge.currency,
+        }
+    }
+}
+
+impl TryFrom<PendingMergeRecord> for PendingMerge {
+    type Error = MoneyParseError;
+
+    fn try_from(record: PendingMergeRecord) -> Result<Self, Self::Error> {
+        Ok(Self {
+            pending_amount: Money::parse(&record.pending_amount, &record.currency)?,
+            posted_amount: Money::parse(&record.posted_amount, &record.currency)?,
+            transaction_id: record.transaction_id,
+            account_id: record.account_id,
+            pending_external_id: record.pending_external_id,
+            posted_external_id: record.posted_external_id,
+            currency: record.currency,
+        })
+    }
+}
+
+/// A message the provider reported, e.g. SimpleFin's `errors` list.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SyncIssue {
+    pub message: String,
+    pub connection_id: String,
+    /// Local account the message concerns, when the provider named one;
+    /// otherwise the message belongs to the connection as a whole
+    pub account_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ConnectionFailure {
+    pub connection_id: String,
+    pub name: String,
+    pub error: String,
+}
+
+/// A connection to import from, with the accounts to request.
+pub struct BackfillTarget {
+    pub connection: Connection,
+    /// External IDs; every account of the connection when empty
+    pub external_ids: Vec<String>,
+}
+
+/// What `upsert_transaction` did with one provider transaction.
+enum Upsert {
+    Created(Box<Transaction>),
+    Merged(PendingMerge),
+    /// An existing row's pending flag, amount or posted date changed
+    Updated,
+    Unchanged,
+}
+
+/// One provider response and the request that produced it.
+struct Fetched {
+    options: FetchOptions,
+    data: ProviderData,
+}
+
+/// How much history a sync requests.
+#[derive(Debug, Clone, Copy)]
+pub struct SyncWindow {
+    /// Re-fetched before the last successful sync, to pick up transactions that post late
+    pub overlap: Duration,
+    /// How far back the first sync of a connection or account reaches
+    pub history: Duration,
+    /// Longest date range requested from a provider at once
+    pub chunk: Duration,
+}
+
+impl Default for SyncWindow {
+    fn default() -> Self {
+        Self {
+            overlap: Duration::days(7),
+            history: Duration::days(730),
+            chunk: Duration::days(60),
+        }
+    }
+}
+
+/// Result of a finished sync as seen by the requests that joined it.
+type SyncOutcome = Result<SyncStats, SyncFailure>;
+
+/// A sync error that can be handed to every request that joined the run.
+#[derive(Debug, Clone)]
+struct SyncFailure {
+    message: String,
+    authentication: Option<AuthenticationError>,
+}
+
+impl SyncFailure {
+    fn new(error: &anyhow::Error) -> Self {
+        Self {
+            message: format!("{:#}", error),
+            authentication: error.downcast_ref::<AuthenticationError>().cloned(),
+        }
+    }
+
+    /// Rebuilds the error, keeping authentication failures recognizable.
+    fn into_error(self) -> anyhow::Error {
+        match self.authentication {
+            Some(error) => error.into(),
+            None => anyhow::Error::msg(self.message),
+        }
+    }
+}
+
+/// The sync currently running; later requests wait on `outcome` instead of
+/// starting a second run.
+struct InFlight {
+    run_id: String,
+    outcome: watch::Receiver<Option<SyncOutcome>>,
+}
+
 pub struct SyncService {
     pool: SqlitePool,
-    simplefin_client: SimplefinClient,
+    cipher: Arc<CredentialCipher>,
+    window: SyncWindow,
+    backfills: BackfillJobs,
+    in_flight: Mutex<Option<InFlight>>,
+    /// Held while a sync runs or a backfill stores a window, so the two never
+    /// import at the same time
+    importing: tokio::sync::Mutex<()>,
+    /// Minimum confidence for applying a suggested category to new transactions
+    auto_categorize_threshold: Option<f64>,
 }
 
 impl SyncService {
-    pub fn new(pool: SqlitePool, simplefin_access_url: String) -> Result<Self> {
-        let simplefin_client = SimplefinClient::new(simplefin_access_url)?;
-        Ok(Self {
+    /// Syncs every enabled connection in `connections`, read afresh for each sync.
+    pub fn new(pool: SqlitePool, cipher: Arc<CredentialCipher>) -> Self {
+        Self {
             pool,
-            simplefin_client,
-        })
+            cipher,
+            window: SyncWindow::default(),
+            backfills: BackfillJobs::default(),
+            in_flight: Mutex::new(None),
+            importing: tokio::sync::Mutex::new(()),
+            auto_categorize_threshold: None,
+        }
     }
 
-    pub async fn sync_all(&self) -> Result<SyncStats> {
-        let start_time = std::time::Instant::now();
-        let mut stats = SyncStats {
-            accounts_updated: 0,
-            accounts_created: 0,
-            transactions_created: 0,
-            balance_records_created: 0,
-            sync_duration_ms: 0,
+    pub fn with_window(mut self, window: SyncWindow) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Categorizes new transactions that no rule categorized when the
+    /// classifier is at least `threshold` confident.
+    pub fn with_auto_categorize(mut self, threshold: f64) -> Self {
+        self.auto_categorize_threshold = Some(threshold);
+        self
+    }
+
+    /// Fetches everything since the last successful sync and stores it,
+    /// recording the run in `sync_runs`.
+    ///
+    /// Only one sync runs at a time: a call made while one is running waits
+    /// for that run and returns its result. The run itself is spawned, so it
+    /// completes even if every caller goes away.
+    ///
+    /// Each connection is stored on its own: when some fail, the others are
+    /// still stored and the failures are listed in the stats. The sync only
+    /// fails when every connection does. A connection whose credentials are
+    /// rejected is marked as needing reauthentication and skipped by startup
+    /// and scheduled syncs; manual syncs still try it.
+    ///
+    /// The first sync of a connection, and of any account seen for the first
+    /// time, backfills `SyncWindow::history` in chunks.
+    pub async fn sync_all(self: &Arc<Self>, trigger: SyncTrigger) -> Result<SyncStats> {
+        let mut outcome = {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            match &*in_flight {
+                Some(run) => {
+                    tracing::info!("Sync {} is already running; waiting for it", run.run_id);
+                    run.outcome.clone()
+                }
+                None => {
+                    let run_id = Uuid::new_v4().to_string();
+                    let (sender, outcome) = watch::channel(None);
+                    *in_flight = Some(InFlight {
+                        run_id: run_id.clone(),
+                        outcome: outcome.clone(),
+                    });
+
+                    let service = self.clone();
+                    tokio::spawn(async move {
+                        let result = service.clone().run(&run_id, trigger).await;
+                        *service.in_flight.lock().unwrap() = None;
+                        sender.send_replace(Some(result.map_err(|e| SyncFailure::new(&e))));
+                    });
+                    outcome
+                }
+            }
         };
 
-        tracing::info!("Starting SimpleFin sync...");
+        let outcome = outcome
+            .wait_for(Option::is_some)
+            .await
+            .context("Sync task stopped without a result")?
+            .clone()
+            .expect("waited for an outcome");
+        outcome.map_err(SyncFailure::into_error)
+    }
 
-        // Fetch account data from SimpleFin
-        let account_set = self.simplefin_client.fetch_accounts().await?;
+    /// ID of the sync running now, if any.
+    pub fn running_run_id(&self) -> Option<String> {
+        self.in_flight
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|run| run.run_id.clone())
+    }
 
-        // Start database transaction
-        let mut tx = self.pool.begin().await?;
+    /// Records a sync in `sync_runs` around running it. The sync gets a task
+    /// of its own, so a panic in it fails the run instead of leaving it
+    /// running and the `in_flight` slot taken.
+    async fn run(self: Arc<Self>, run_id: &str, trigger: SyncTrigger) -> Result<SyncStats> {
+        sync_runs::start(&self.pool, run_id, trigger).await?;
+        let service = self.clone();
+        let sync = async move {
+            let _importing = service.importing.lock().await;
+            service.sync(trigger).await
+        };
+        let result = match tokio::spawn(sync).await {
+            Ok(result) => result,
+            Err(e) => Err(anyhow::anyhow!("Sync stopped unexpectedly: {}", e)),
+        };
+        if let Err(e) = sync_runs::finish(&self.pool, run_id, &result).await {
+            tracing::error!("Failed to record sync run {}: {}", run_id, e);
+        }
+        result
+    }
 
-        for simplefin_account in account_set.accounts {
-            // Upsert account
-            let (account_created, local_account) = self.upsert_account(&mut tx, &simplefin_account).await?;
-            
-            if account_created {
-                stats.accounts_created += 1;
-            } else {
-                stats.accounts_updated += 1;
-            }
+    async fn sync(&self, trigger: SyncTrigger) -> Result<SyncStats> {
+        let start_time = std::time::Instant::now();
+        let started_at = Utc::now();
+        let mut stats = SyncStats::default();
 
-            // Record balance history if balance changed
-            if self.record_balance_history(&mut tx, &local_account).await? {
-                stats.balance_records_created += 1;
-            }
+        let mut connections = connections::enabled(&self.pool).await?;
+        // Only a manual sync retries credentials the provider rejected
+        if trigger != SyncTrigger::Manual {
+            connections.retain(|connection| {
+                if connection.auth_failed_at.is_some() {
+                    tracing::info!("Skipping connection {} until it is reauthenticated", connection.name);
+                }
+                connection.auth_failed_at.is_none()
+            });
+        }
+        tracing::info!("Starting sync of {} connections...", connections.len());
 
-            // Sync transactions if any
-            if let Some(transactions) = simplefin_account.transactions {
-                for simplefin_tx in transactions {
-                    if self.upsert_transaction(&mut tx, &local_account.id, &simplefin_tx).await? {
-                        stats.transactions_created += 1;
-                    }
+        let mut first_error = None;
+        let mut first_auth_error = None;
+        for connection in &connections {
+            // Counts from a connection that fails are rolled back with its data
+            let before = stats.clone();
+            let result = self.sync_connection(connection, started_at, &mut stats).await;
+            let rejected = result
+                .as_ref()
+                .err()
+                .and_then(|e| e.downcast_ref::<AuthenticationError>())
+                .map(ToString::to_string);
+            let recorded = match (&rejected, &result) {
+                (Some(error), _) => connections::record_auth_failure(&self.pool, &connection.id, error).await,
+                (None, Ok(())) if connection.auth_failed_at.is_some() => {
+                    connections::clear_auth_failure(&self.pool, Some(&connection.id)).await
+                }
+                _ => Ok(()),
+            };
+            if let Err(e) = recorded {
+                tracing::error!("Failed to record whether connection {} needs reauthentication: {:#}", connection.name, e);
+            }
+            if let Err(e) = result {
+                let e = e.context(format!("Connection {} failed", connection.name));
+                tracing::error!("{:#}", e);
+                stats = before;
+                stats.failed_connections.push(ConnectionFailure {
+                    connection_id: connection.id.clone(),
+                    name: connection.name.clone(),
+                    error: format!("{:#}", e),
+                });
+                if rejected.is_some() {
+                    first_auth_error.get_or_insert(e);
+                } else {
+                    first_error.get_or_insert(e);
                 }
             }
         }
-
-        // Commit transaction
-        tx.commit().await?;
+        // Rejected credentials only fail the sync, pausing scheduling, when
+        // they are all that went wrong; other failures are retried
+        if stats.failed_connections.len() == connections.len()
+            && let Some(e) = first_error.or(first_auth_error)
+        {
+            return Err(e);
+        }
 
         stats.sync_duration_ms = start_time.elapsed().as_millis() as u64;
 
         tracing::info!(
-            "SimpleFin sync completed: {} accounts created, {} accounts updated, {} transactions created, {} balance records created in {}ms",
+            "Sync completed: {} accounts created, {} accounts updated, {} transactions created ({} changed by rules, {} auto-categorized), {} updated, {} pending merged, {} pending removed, {} balance records created in {}ms",
             stats.accounts_created,
             stats.accounts_updated, 
             stats.transactions_created,
+            stats.transactions_ruled,
+            stats.transactions_auto_categorized,
+            stats.transactions_updated,
+            stats.pending_merges.len(),
+            stats.pending_removed,
             stats.balance_records_created,
             stats.sync_duration_ms
         );
@@ -91,46 +411,493 @@ impl SyncService {
         Ok(stats)
     }
 
+    async fn sync_connection(
+        &self,
+        connection: &Connection,
+        started_at: DateTime<Utc>,
+        stats: &mut SyncStats,
+    ) -> Result<()> {
+        let access_url = self.cipher.decrypt(&connection.encrypted_access_url, &connection.id)?;
+        let provider = provider::connect(connection, &access_url)?;
+        self.sync_from(connection, provider.as_ref(), started_at, stats).await
+    }
+
+    /// Syncs `connection` from `provider` instead of the provider it is
+    /// configured with, e.g. from a `MockProvider` in tests. The
+    /// run is not recorded in `sync_runs`, and does not wait for or block
+    /// `sync_all`.
+    pub async fn sync_provider(
+        &self,
+        connection: &Connection,
+        provider: &dyn BankDataProvider,
+    ) -> Result<SyncStats> {
+        let start_time = std::time::Instant::now();
+        let mut stats = SyncStats::default();
+        self.sync_from(connection, provider, Utc::now(), &mut stats).await?;
+        stats.sync_duration_ms = start_time.elapsed().as_millis() as u64;
+        Ok(stats)
+    }
+
+    async fn sync_from(
+        &self,
+        connection: &Connection,
+        provider: &dyn BankDataProvider,
+        started_at: DateTime<Utc>,
+        stats: &mut SyncStats,
+    ) -> Result<()> {
+        // Fetch account data from the provider
+        let responses = self.fetch_since_last_sync(provider, connection, started_at).await?;
+
+        // Start database transaction
+        let mut tx = self.pool.begin().await?;
+        let synced_accounts = self.import(&mut tx, &connection.id, provider.name(), responses, stats).await?;
+        self.record_progress(&mut tx, &connection.id, provider.name(), started_at, &synced_accounts).await?;
+
+        // Commit transaction
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub fn backfills(&self) -> &BackfillJobs {
+        &self.backfills
+    }
+
+    /// Starts importing `[from, to)` from `targets` in the background, one
+    /// date window at a time, and returns the job to poll. `account_ids` are
+    /// the local IDs of the requested accounts, for reporting.
+    ///
+    /// High-water marks are left alone, so regular syncs are unaffected.
+    /// Windows are stored between syncs, never while one is running.
+    pub fn start_backfill(
+        self: &Arc<Self>,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        account_ids: Vec<String>,
+        targets: Vec<BackfillTarget>,
+    ) -> BackfillJob {
+        let windows = date_windows(from, to, self.window.chunk);
+        let job = BackfillJob {
+            id: Uuid::new_v4().to_string(),
+            status: BackfillStatus::Running,
+            from,
+            to,
+            account_ids,
+            windows_total: windows.len() as u32,
+            windows_completed: 0,
+            accounts_created: 0,
+            transactions_created: 0,
+            error: None,
+            started_at: Utc::now(),
+            finished_at: None,
+        };
+        let cancel = self.backfills.insert(job.clone());
+
+        let service = self.clone();
+        let job_id = job.id.clone();
+        tokio::spawn(async move {
+            let result = service.run_backfill(&job_id, &cancel, windows, targets).await;
+            service.backfills.update(&job_id, |job| {
+                job.finished_at = Some(Utc::now());
+                match result {
+                    Ok(()) if cancel.load(Ordering::Relaxed) => job.status = BackfillStatus::Cancelled,
+                    Ok(()) => job.status = BackfillStatus::Completed,
+                    Err(e) => {
+                        tracing::error!("Backfill {} failed: {:#}", job.id, e);
+                        job.status = BackfillStatus::Failed;
+                        job.error = Some(format!("{:#}", e));
+                    }
+                }
+            });
+        });
+
+        job
+    }
+
+    async fn run_backfill(
+        &self,
+        job_id: &str,
+        cancel: &AtomicBool,
+        windows: Vec<(DateTime<Utc>, DateTime<Utc>)>,
+        targets: Vec<BackfillTarget>,
+    ) -> Result<()> {
+        let providers = targets
+            .iter()
+            .map(|target| {
+                let access_url = connections::access_url_of(&self.cipher, &target.connection)?;
+                provider::connect(&target.connection, &access_url)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        for (start, end) in windows {
+            // Wait for any running sync before storing the window
+            let _importing = self.importing.lock().await;
+            if cancel.load(Ordering::Relaxed) {
+                tracing::info!("Backfill {} cancelled", job_id);
+                return Ok(());
+            }
+
+            // Store each window on its own so progress survives a later failure
+            let mut stats = SyncStats::default();
+            let mut tx = self.pool.begin().await?;
+            for (target, provider) in targets.iter().zip(&providers) {
+                let options = FetchOptions {
+                    start_date: Some(start),
+                    end_date: Some(end),
+                    account_ids: target.external_ids.clone(),
+                };
+                let data = provider.fetch(&options).await?;
+                let responses = vec![Fetched { options, data }];
+                self.import(&mut tx, &target.connection.id, provider.name(), responses, &mut stats).await?;
+            }
+            tx.commit().await?;
+
+            self.backfills.update(job_id, |job| {
+                job.windows_completed += 1;
+                job.accounts_created += stats.accounts_created;
+                job.transactions_created += stats.transactions_created;
+            });
+        }
+        Ok(())
+    }
+
+    /// Requests `[start, end)` in windows no longer than `SyncWindow::chunk`.
+    /// Without an `end` the newest window is left open-ended, so pending
+    /// transactions dated slightly in the future are not cut off.
+    async fn fetch_range(
+        &self,
+        provider: &dyn BankDataProvider,
+        start: DateTime<Utc>,
+        end: Option<DateTime<Utc>>,
+        account_ids: Vec<String>,
+    ) -> Result<Vec<Fetched>> {
+        let windows = date_windows(start, end.unwrap_or_else(Utc::now), self.window.chunk);
+        let last = windows.len().saturating_sub(1);
+
+        let mut responses = Vec::with_capacity(windows.len());
+        for (i, (window_start, window_end)) in windows.into_iter().enumerate() {
+            let options = FetchOptions {
+                start_date: Some(window_start),
+                end_date: (i < last || end.is_some()).then_some(window_end),
+                account_ids: account_ids.clone(),
+            };
+            let data = provider.fetch(&options).await?;
+            responses.push(Fetched { options, data });
+        }
+        Ok(responses)
+    }
+
+    /// Fetches from the connection's high-water mark minus the overlap, plus
+    /// the older history of accounts that are behind it. Oldest data first.
+    async fn fetch_since_last_sync(
+        &self,
+        provider: &dyn BankDataProvider,
+        connection: &Connection,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<Fetched>> {
+        let history_start = now - self.window.history;
+        let Some(last_synced_at) = connection.last_synced_at else {
+            tracing::info!(
+                "No previous sync for connection {}; backfilling {} days of history",
+                connection.name,
+                self.window.history.num_days()
+            );
+            return self.fetch_range(provider, history_start, None, Vec::new()).await;
+        };
+
+        let start = (last_synced_at - self.window.overlap).max(history_start);
+        let mut responses = self.fetch_range(provider, start, None, Vec::new()).await?;
+
+        // Accounts that are new, were missing from earlier syncs, or were
+        // last synced through another connection need their older history too
+        let marks: HashMap<String, Option<DateTime<Utc>>> = sqlx::query_as(
+            "SELECT external_id, last_synced_at FROM accounts WHERE connection_id = ? AND external_id IS NOT NULL"
+        )
+        .bind(&connection.id)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .collect();
+
+        let mut behind = Vec::new();
+        let mut catch_up_start = start;
+        for account in responses.iter().flat_map(|fetched| &fetched.data.accounts) {
+            let account_start = marks
+                .get(&account.external_id)
+                .copied()
+                .flatten()
+                .map_or(history_start, |mark| (mark - self.window.overlap).max(history_start));
+            if account_start < start && !behind.contains(&account.external_id) {
+                behind.push(account.external_id.clone());
+                catch_up_start = catch_up_start.min(account_start);
+            }
+        }
+
+        if !behind.is_empty() {
+            tracing::info!(
+                "Fetching older history for {} accounts from {}",
+                behind.len(),
+                catch_up_start
+            );
+            let mut older = self.fetch_range(provider, catch_up_start, Some(start), behind).await?;
+            older.append(&mut responses);
+            responses = older;
+        }
+
+        Ok(responses)
+    }
+
+    /// Stores fetched accounts, their holdings and their new transactions,
+    /// running rules, merchant normalization and (if enabled)
+    /// auto-categorization on each new transaction. Returns the external IDs
+    /// of the accounts stored.
+    async fn import(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        connection_id: &str,
+        provider: &str,
+        responses: Vec<Fetched>,
+        stats: &mut SyncStats,
+    ) -> Result<HashSet<String>> {
+        let rules = RuleEngine::load(tx, None).await?;
+        let merchants = MerchantMatcher::load(tx).await?;
+        let classifier = match self.auto_categorize_threshold {
+            Some(_) => {
+                classifier::sync_model(tx).await?;
+                Some(Classifier::load(tx).await?)
+            }
+            None => None,
+        };
+
+        let mut seen = HashSet::new();
+        // Local account by external ID, for attributing messages
+        let mut synced_accounts: HashMap<String, Account> = HashMap::new();
+        let mut messages: Vec<ProviderMessage> = Vec::new();
+        for Fetched { options, data } in responses {
+            for message in data.messages {
+                if !messages.contains(&message) {
+                    messages.push(message);
+                }
+            }
+
+            // Only the open-ended window of a sync sees every current pending transaction
+            let vanished_since = match options.end_date {
+                None => options.start_date.map(|start| start.date_naive()),
+                Some(_) => None,
+            };
+            for provider_account in data.accounts {
+                // Upsert account
+                let (account_created, local_account) = self.upsert_account(tx, connection_id, provider, &provider_account).await?;
+
+                // An account appears once per fetched window; count it once
+                if seen.insert(provider_account.external_id.clone()) {
+                    if account_created {
+                        stats.accounts_created += 1;
+                    } else {
+                        stats.accounts_updated += 1;
+                    }
+
+                    // Record balance history if balance changed
+                    if self.record_balance_history(tx, &local_account).await? {
+                        stats.balance_records_created += 1;
+                    }
+                    if let Some(holdings) = &provider_account.holdings {
+                        self.replace_holdings(tx, &local_account, holdings).await?;
+                    }
+                    synced_accounts.insert(provider_account.external_id.clone(), local_account.clone());
+                }
+
+                // Sync transactions if any
+                if let Some(transactions) = &provider_account.transactions {
+                    // Unreadable entries are still in the feed, so their pending rows are kept
+                    let feed_ids: HashSet<&str> = transactions
+                        .iter()
+                        .map(|t| t.external_id.as_str())
+                        .chain(provider_account.unreadable_transaction_ids.iter().map(String::as_str))
+                        .collect();
+                    for provider_tx in transactions {
+                        let transaction = match self.upsert_transaction(tx, &merchants, &local_account, provider_tx, &feed_ids).await? {
+                            Upsert::Created(transaction) => transaction,
+                            Upsert::Merged(merge) => {
+                                stats.pending_merges.push(merge);
+                                continue;
+                            }
+                            Upsert::Updated => {
+                                stats.transactions_updated += 1;
+                                continue;
+                            }
+                            Upsert::Unchanged => continue,
+                        };
+                        stats.transactions_created += 1;
+
+                        let evaluation = rules.evaluate(&transaction);
+                        if evaluation.changes(&transaction) {
+                            rules::save_fields(tx, &transaction.id, &evaluation.fields).await?;
+                            stats.transactions_ruled += 1;
+                        }
+
+                        if evaluation.fields.category_id.is_none()
+                            && let (Some(classifier), Some(threshold)) = (&classifier, self.auto_categorize_threshold)
+                            && let Some(suggestion) = classifier.suggest(&transaction)
+                            && suggestion.confidence >= threshold
+                        {
+                            sqlx::query("UPDATE transactions SET category_id = ?, category_source = ? WHERE id = ?")
+                                .bind(&suggestion.category_id)
+                                .bind(CategorySource::Suggestion)
+                                .bind(&transaction.id)
+                                .execute(&mut **tx)
+                                .await?;
+                            stats.transactions_auto_categorized += 1;
+                        }
+                    }
+
+                    if let Some(since) = vanished_since {
+                        self.remove_vanished_pending(tx, connection_id, &local_account, since, &feed_ids, stats)
+                            .await?;
+                    }
+                }
+            }
+        }
+
+        self.record_issues(tx, connection_id, messages, &synced_accounts, stats).await?;
+
+        // Learn from the categories rules assigned to the new transactions
+        classifier::sync_model(tx).await?;
+
+        Ok(seen)
+    }
+
+    /// Adds the provider's messages to the stats and sets the `sync_error` of
+    /// each synced account, and of the connection for messages naming no
+    /// account, to the messages that concern it, clearing old ones.
+    async fn record_issues(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        connection_id: &str,
+        messages: Vec<ProviderMessage>,
+        accounts: &HashMap<String, Account>,
+        stats: &mut SyncStats,
+    ) -> Result<()> {
+        let mut account_errors: HashMap<&str, Vec<&str>> = HashMap::new();
+        let mut connection_errors = Vec::new();
+        for ProviderMessage { message, account_id } in &messages {
+            tracing::warn!("Provider reported: {}", message);
+            let account_id = account_id
+                .as_deref()
+                .and_then(|id| accounts.get(id))
+                .map(|account| account.id.clone());
+            stats.issues.push(SyncIssue {
+                message: message.clone(),
+                connection_id: connection_id.to_string(),
+                account_id,
+            });
+        }
+        for issue in stats.issues.iter().filter(|issue| issue.connection_id == connection_id) {
+            match &issue.account_id {
+                Some(account_id) => account_errors
+                    .entry(account_id.as_str())
+                    .or_default()
+                    .push(issue.message.as_str()),
+                None => connection_errors.push(issue.message.as_str()),
+            }
+        }
+
+        let connection_error = (!connection_errors.is_empty()).then(|| connection_errors.join("; "));
+        sqlx::query("UPDATE connections SET sync_error = ? WHERE id = ?")
+            .bind(connection_error)
+            .bind(connection_id)
+            .execute(&mut **tx)
+            .await?;
+
+        for account in accounts.values() {
+            let error = account_errors
+                .get(account.id.as_str())
+                .map(|messages| messages.join("; "));
+            if error != account.sync_error {
+                sqlx::query("UPDATE accounts SET sync_error = ? WHERE id = ?")
+                    .bind(&error)
+                    .bind(&account.id)
+                    .execute(&mut **tx)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves the high-water marks of the connection and the synced accounts
+    /// to the time the sync started.
+    async fn record_progress(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        connection_id: &str,
+        provider: &str,
+        started_at: DateTime<Utc>,
+        external_ids: &HashSet<String>,
+    ) -> Result<()> {
+        sqlx::query("UPDATE connections SET last_synced_at = ? WHERE id = ?")
+            .bind(started_at)
+            .bind(connection_id)
+            .execute(&mut **tx)
+            .await?;
+
+        for external_id in external_ids {
+            sqlx::query("UPDATE accounts SET last_synced_at = ? WHERE provider = ? AND external_id = ?")
+                .bind(started_at)
+                .bind(provider)
+                .bind(external_id)
+                .execute(&mut **tx)
+                .await?;
+        }
+
+        Ok(())
+    }
+
     async fn upsert_account(
         &self,
         tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
-        simplefin_account: &SimplefinAccount,
+        connection_id: &str,
+        provider: &str,
+        provider_account: &ProviderAccount,
     ) -> Result<(bool, Account)> {
         // Check if account exists
         let existing_account = sqlx::query_as::<_, Account>(
-            "SELECT * FROM accounts WHERE simplefin_id = ?"
+            "SELECT * FROM accounts WHERE provider = ? AND external_id = ?"
         )
-        .bind(&simplefin_account.id)
+        .bind(provider)
+        .bind(&provider_account.external_id)
         .fetch_optional(&mut **tx)
         .await?;
 
-        let balance = simplefin_account.balance_as_f64();
         let now = Utc::now();
 
         let account = if let Some(mut existing) = existing_account {
-            // Update existing account
-            existing.name = simplefin_account.name.clone();
-            existing.institution = simplefin_account.institution_name();
-            existing.balance = balance;
-            existing.available_balance = Some(simplefin_account.available_balance);
-            existing.is_credit_card = Some(simplefin_account.is_credit_card);
+            // Update existing account. User edits live in display_name and
+            // account_type, which are deliberately left alone here.
+            existing.name = provider_account.name.clone();
+            existing.institution = provider_account.institution.clone();
+            existing.balance = provider_account.balance;
+            existing.currency = provider_account.currency.clone();
+            existing.available_balance = provider_account.available_balance;
+            existing.is_credit_card = Some(provider_account.is_credit_card);
             existing.last_updated = now;
+            existing.connection_id = Some(connection_id.to_string());
 
             sqlx::query(
                 r#"
                 UPDATE accounts SET 
-                    name = ?, institution = ?, balance = ?, available_balance = ?,
-                    is_credit_card = ?, last_updated = ?
-                WHERE simplefin_id = ?
+                    name = ?, institution = ?, balance = ?, currency = ?, available_balance = ?,
+                    is_credit_card = ?, last_updated = ?, connection_id = ?
+                WHERE id = ?
                 "#
             )
             .bind(&existing.name)
             .bind(&existing.institution)
             .bind(existing.balance)
+            .bind(&existing.currency)
             .bind(existing.available_balance)
             .bind(existing.is_credit_card)
             .bind(existing.last_updated)
-            .bind(&simplefin_account.id)
+            .bind(&existing.connection_id)
+            .bind(&existing.id)
             .execute(&mut **tx)
             .await?;
 
@@ -138,42 +905,53 @@ impl SyncService {
         } else {
             // Create new account
             let id = Uuid::new_v4().to_string();
-            let account_type = if simplefin_account.is_credit_card {
-                "credit".to_string()
+            let account_type = if provider_account.is_credit_card {
+                AccountType::Credit
             } else {
-                "checking".to_string() // Default assumption
+                AccountType::Checking // Default assumption
             };
 
             let new_account = Account {
                 id: id.clone(),
-                name: simplefin_account.name.clone(),
-                institution: simplefin_account.institution_name(),
+                name: provider_account.name.clone(),
+                display_name: None,
+                institution: provider_account.institution.clone(),
                 account_type,
-                balance,
+                balance: provider_account.balance,
+                currency: provider_account.currency.clone(),
                 last_updated: now,
                 created_at: now,
-                simplefin_id: Some(simplefin_account.id.clone()),
-                available_balance: Some(simplefin_account.available_balance),
-                is_credit_card: Some(simplefin_account.is_credit_card),
+                provider: Some(provider.to_string()),
+                external_id: Some(provider_account.external_id.clone()),
+                available_balance: provider_account.available_balance,
+                is_credit_card: Some(provider_account.is_credit_card),
+                last_synced_at: None,
+                sync_error: None,
+                connection_id: Some(connection_id.to_string()),
+                converted_balance: None,
             };
 
             sqlx::query(
                 r#"
-                INSERT INTO accounts (id, name, institution, account_type, balance, 
-                                    last_updated, created_at, simplefin_id, available_balance, is_credit_card)
-                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                INSERT INTO accounts (id, name, institution, account_type, balance, currency,
+                                    last_updated, created_at, provider, external_id, available_balance,
+                                    is_credit_card, connection_id)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
                 "#
             )
             .bind(&new_account.id)
             .bind(&new_account.name)
             .bind(&new_account.institution)
-            .bind(&new_account.account_type)
+            .bind(new_account.account_type)
             .bind(new_account.balance)
+            .bind(&new_account.currency)
             .bind(new_account.last_updated)
             .bind(new_account.created_at)
-            .bind(&new_account.simplefin_id)
+            .bind(&new_account.provider)
+            .bind(&new_account.external_id)
             .bind(new_account.available_balance)
             .bind(new_account.is_credit_card)
+            .bind(&new_account.connection_id)
             .execute(&mut **tx)
             .await?;
 
@@ -183,13 +961,57 @@ impl SyncService {
         Ok(account)
     }
 
+    /// Replaces the account's holdings with the ones just reported.
+    async fn replace_holdings(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        account: &Account,
+        holdings: &[ProviderHolding],
+    ) -> Result<()> {
+        let now = Utc::now();
+        for holding in holdings {
+            sqlx::query(
+                r#"
+                INSERT INTO holdings (id, account_id, external_id, symbol, description, shares,
+                                      market_value, cost_basis, currency, updated_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                ON CONFLICT (account_id, external_id) DO UPDATE SET
+                    symbol = excluded.symbol, description = excluded.description,
+                    shares = excluded.shares, market_value = excluded.market_value,
+                    cost_basis = excluded.cost_basis, currency = excluded.currency,
+                    updated_at = excluded.updated_at
+                "#
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(&account.id)
+            .bind(&holding.external_id)
+            .bind(&holding.symbol)
+            .bind(&holding.description)
+            .bind(holding.shares)
+            .bind(holding.market_value)
+            .bind(holding.cost_basis)
+            .bind(&holding.currency)
+            .bind(now)
+            .execute(&mut **tx)
+            .await?;
+        }
+
+        // Positions that were sold off
+        sqlx::query("DELETE FROM holdings WHERE account_id = ? AND updated_at < ?")
+            .bind(&account.id)
+            .bind(now)
+            .execute(&mut **tx)
+            .await?;
+        Ok(())
+    }
+
     async fn record_balance_history(
         &self,
         tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
         account: &Account,
     ) -> Result<bool> {
         // Check if we already have a recent balance record (within last hour)
-        let recent_balance = sqlx::query_as::<_, (f64,)>(
+        let recent_balance = sqlx::query_as::<_, (Money,)>(
             r#"
             SELECT balance FROM balances_history 
             WHERE account_id = ? AND timestamp > datetime('now', '-1 hour')
@@ -202,10 +1024,10 @@ impl SyncService {
         .await?;
 
         // Only record if balance has changed or no recent record exists
-        if let Some((recent_balance,)) = recent_balance {
-            if (recent_balance - account.balance).abs() < 0.01 {
-                return Ok(false); // Balance hasn't changed significantly
-            }
+        if let Some((recent_balance,)) = recent_balance
+            && recent_balance == account.balance
+        {
+            return Ok(false);
         }
 
         // Record new balance
@@ -228,53 +1050,288 @@ impl SyncService {
     async fn upsert_transaction(
         &self,
         tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
-        account_id: &str,
-        simplefin_tx: &SimplefinTransaction,
-    ) -> Result<bool> {
-        // Check if transaction already exists
-        let exists = sqlx::query_as::<_, (String,)>(
-            "SELECT id FROM transactions WHERE simplefin_id = ?"
+        merchants: &MerchantMatcher,
+        account: &Account,
+        provider_tx: &ProviderTransaction,
+        feed_ids: &HashSet<&str>,
+    ) -> Result<Upsert> {
+        // Deleted by the user, or a pending transaction already merged into its posted version
+        let tombstoned = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM transaction_tombstones WHERE account_id = ? AND external_id = ?"
+        )
+        .bind(&account.id)
+        .bind(&provider_tx.external_id)
+        .fetch_one(&mut **tx)
+        .await?;
+        if tombstoned > 0 {
+            return Ok(Upsert::Unchanged);
+        }
+
+        let amount = provider_tx.amount;
+        let posted_date = provider_tx.posted_at;
+        let transaction_date = posted_date
+            .map(|dt| dt.date_naive())
+            .unwrap_or_else(|| Utc::now().date_naive());
+        let pending = provider_tx.pending;
+
+        let existing = sqlx::query_as::<_, Transaction>(
+            "SELECT * FROM transactions WHERE account_id = ? AND external_id = ?"
         )
-        .bind(&simplefin_tx.id)
+        .bind(&account.id)
+        .bind(&provider_tx.external_id)
         .fetch_optional(&mut **tx)
-        .await?
-        .is_some();
+        .await?;
 
-        if exists {
-            return Ok(false); // Transaction already exists
+        if let Some(existing) = existing {
+            // A pending transaction can post with a different amount (tips) and date
+            if existing.pending == Some(pending)
+                && existing.amount == amount
+                && existing.posted_date == posted_date
+            {
+                return Ok(Upsert::Unchanged);
+            }
+
+            sqlx::query(
+                r#"
+                UPDATE transactions SET pending = ?, amount = ?, posted_date = ?, transaction_date = ?
+                WHERE id = ?
+                "#
+            )
+            .bind(pending)
+            .bind(amount)
+            .bind(posted_date)
+            .bind(transaction_date)
+            .bind(&existing.id)
+            .execute(&mut **tx)
+            .await?;
+
+            return Ok(Upsert::Updated);
+        }
+
+        if !pending
+            && let Some(stale) = self
+                .find_stale_pending(tx, account, amount, transaction_date, feed_ids)
+                .await?
+        {
+            return self
+                .merge_pending(tx, merchants, stale, provider_tx, amount, posted_date, transaction_date)
+                .await
+                .map(Upsert::Merged);
         }
 
         // Create new transaction
         let id = Uuid::new_v4().to_string();
-        let amount = simplefin_tx.amount_as_f64();
-        let posted_date = simplefin_tx.to_posted_date();
-        let transaction_date = posted_date
-            .map(|dt| dt.date_naive())
-            .unwrap_or_else(|| Utc::now().date_naive());
         let now = Utc::now();
+        let merchant_id = merchants
+            .resolve(tx, &provider_tx.description, provider_tx.payee.as_deref())
+            .await?;
 
-        sqlx::query(
+        let transaction = sqlx::query_as::<_, Transaction>(
             r#"
             INSERT INTO transactions (
-                id, account_id, amount, description, transaction_date, created_at,
-                simplefin_id, posted_date, payee, memo, pending
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                id, account_id, amount, currency, description, transaction_date, created_at,
+                external_id, posted_date, payee, memo, pending, merchant_id
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            RETURNING *
             "#
         )
         .bind(&id)
-        .bind(account_id)
+        .bind(&account.id)
         .bind(amount)
-        .bind(&simplefin_tx.description)
+        .bind(&account.currency)
+        .bind(&provider_tx.description)
         .bind(transaction_date)
         .bind(now)
-        .bind(&simplefin_tx.id)
+        .bind(&provider_tx.external_id)
         .bind(posted_date)
-        .bind(&simplefin_tx.payee)
-        .bind(&simplefin_tx.memo)
-        .bind(simplefin_tx.pending.unwrap_or(false))
+        .bind(&provider_tx.payee)
+        .bind(&provider_tx.memo)
+        .bind(pending)
+        .bind(merchant_id)
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Ok(Upsert::Created(Box::new(transaction)))
+    }
+
+    /// Finds a pending transaction that is no longer in the feed and most
+    /// likely posted as this one under a new ID: same account and sign, an
+    /// amount within the tolerance and a date shortly before.
+    async fn find_stale_pending(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        account: &Account,
+        amount: Money,
+        transaction_date: NaiveDate,
+        feed_ids: &HashSet<&str>,
+    ) -> Result<Option<Transaction>> {
+        let candidates = sqlx::query_as::<_, Transaction>(
+            r#"
+            SELECT * FROM transactions
+            WHERE account_id = ? AND pending AND external_id IS NOT NULL
+              AND transaction_date BETWEEN ? AND ?
+            "#
+        )
+        .bind(&account.id)
+        .bind(transaction_date - Days::new(PENDING_MATCH_DAYS))
+        .bind(transaction_date + Days::new(1))
+        .fetch_all(&mut **tx)
+        .await?;
+
+        let tolerance_min = Money::from_major(PENDING_AMOUNT_TOLERANCE_MIN, &account.currency)
+            .unwrap_or(Money::ZERO);
+        let tolerance = |pending: Money| {
+            (pending.abs().minor_units().saturating_mul(PENDING_AMOUNT_TOLERANCE_PERCENT) / 100)
+                .max(tolerance_min.minor_units())
+        };
+        // Candidates share the sign of `amount`, so the difference cannot
+        // overflow; an error only drops the candidate.
+        let distance = |candidate: &Transaction| {
+            candidate.amount.checked_sub(amount).map(Money::abs)
+        };
+        let best = candidates
+            .into_iter()
+            .filter(|candidate| {
+                candidate
+                    .external_id
+                    .as_deref()
+                    .is_some_and(|id| !feed_ids.contains(id))
+            })
+            .filter(|candidate| candidate.amount.is_negative() == amount.is_negative())
+            .filter(|candidate| {
+                distance(candidate)
+                    .is_ok_and(|d| d.minor_units() <= tolerance(candidate.amount))
+            })
+            .min_by_key(|candidate| {
+                (
+                    distance(candidate).unwrap_or(Money::from_minor(i64::MAX)),
+                    (transaction_date - candidate.transaction_date).num_days().abs(),
+                )
+            });
+
+        Ok(best)
+    }
+
+    /// Turns a stale pending row into its posted version, keeping the row and
+    /// with it the user's category, note and tags.
+    #[allow(clippy::too_many_arguments)]
+    async fn merge_pending(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        merchants: &MerchantMatcher,
+        stale: Transaction,
+        provider_tx: &ProviderTransaction,
+        amount: Money,
+        posted_date: Option<DateTime<Utc>>,
+        transaction_date: NaiveDate,
+    ) -> Result<PendingMerge> {
+        // The description may change; the classifier relearns the row after the update
+        classifier::forget(tx, &stale).await?;
+
+        let merged = sqlx::query_as::<_, Transaction>(
+            r#"
+            UPDATE transactions SET
+                external_id = ?, amount = ?, description = ?, payee = ?, memo = ?,
+                pending = FALSE, posted_date = ?, transaction_date = ?
+            WHERE id = ?
+            RETURNING *
+            "#
+        )
+        .bind(&provider_tx.external_id)
+        .bind(amount)
+        .bind(&provider_tx.description)
+        .bind(&provider_tx.payee)
+        .bind(&provider_tx.memo)
+        .bind(posted_date)
+        .bind(transaction_date)
+        .bind(&stale.id)
+        .fetch_one(&mut **tx)
+        .await?;
+        merchants.assign(tx, &merged).await?;
+
+        let pending_external_id = stale.external_id.unwrap_or_default();
+        sqlx::query(
+            "INSERT OR IGNORE INTO transaction_tombstones (account_id, external_id) VALUES (?, ?)"
+        )
+        .bind(&stale.account_id)
+        .bind(&pending_external_id)
         .execute(&mut **tx)
         .await?;
 
-        Ok(true)
+        tracing::info!(
+            "Merged pending transaction {} into posted transaction {}",
+            pending_external_id,
+            provider_tx.external_id
+        );
+
+        Ok(PendingMerge {
+            transaction_id: merged.id,
+            account_id: merged.account_id,
+            pending_external_id,
+            posted_external_id: provider_tx.external_id.clone(),
+            pending_amount: stale.amount,
+            posted_amount: amount,
+            currency: stale.currency,
+        })
     }
-}
\ No newline at end of file
+
+    /// Deletes pending transactions on or after `since` that the feed no
+    /// longer returns; the bank dropped them (e.g. a released authorization).
+    /// Rows the user has edited are kept and reported as an issue instead, so
+    /// that a note or category is never lost without a trace.
+    async fn remove_vanished_pending(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        connection_id: &str,
+        account: &Account,
+        since: NaiveDate,
+        feed_ids: &HashSet<&str>,
+        stats: &mut SyncStats,
+    ) -> Result<()> {
+        let pending = sqlx::query_as::<_, Transaction>(
+            r#"
+            SELECT * FROM transactions
+            WHERE account_id = ? AND pending AND external_id IS NOT NULL AND transaction_date >= ?
+            "#
+        )
+        .bind(&account.id)
+        .bind(since)
+        .fetch_all(&mut **tx)
+        .await?;
+
+        for transaction in pending {
+            if transaction.external_id.as_deref().is_some_and(|id| feed_ids.contains(id)) {
+                continue;
+            }
+            if has_user_edits(&transaction) {
+                stats.issues.push(SyncIssue {
+                    message: format!(
+                        "Pending transaction \"{}\" on {} is no longer reported by the bank; \
+                         it was kept because it has your edits",
+                        transaction.display_description.as_deref().unwrap_or(&transaction.description),
+                        transaction.transaction_date
+                    ),
+                    connection_id: connection_id.to_string(),
+                    account_id: Some(account.id.clone()),
+                });
+                continue;
+            }
+            classifier::forget(tx, &transaction).await?;
+            sqlx::query("DELETE FROM transactions WHERE id = ?")
+                .bind(&transaction.id)
+                .execute(&mut **tx)
+                .await?;
+            stats.pending_removed += 1;
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether the user has changed the transaction through the API: its
+/// category, note or description (and with it the merchant).
+fn has_user_edits(transaction: &Transaction) -> bool {
+    transaction.category_locked
+        || transaction.note.is_some()
+        || transaction.display_description.is_some()
+}
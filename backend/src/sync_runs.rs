@@ -0,0 +1,165 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use sqlx::types::Json;
+use utoipa::ToSchema;
+
+use crate::connections;
+use crate::scheduler::SyncPause;
+use crate::sync::{ConnectionFailure, SyncIssue, SyncStats};
+
+/// What started a sync.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum SyncTrigger {
+    Startup,
+    Scheduled,
+    Manual,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum SyncRunStatus {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+pub struct SyncRun {
+    pub id: String,
+    pub trigger: SyncTrigger,
+    pub status: SyncRunStatus,
+    #[schema(value_type = String, format = DateTime)]
+    pub started_at: DateTime<Utc>,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub finished_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+    /// Set once the run succeeds
+    #[schema(value_type = Option<SyncStats>)]
+    pub stats: Option<Json<SyncStats>>,
+}
+
+/// Overview of sync health for the frontend.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SyncStatus {
+    /// False while no connection is enabled; sync endpoints then answer 503
+    pub configured: bool,
+    /// The run in progress, if any
+    pub running: Option<SyncRun>,
+    pub last_success: Option<SyncRun>,
+    pub last_failure: Option<SyncRun>,
+    /// When the scheduler starts its next sync; absent when sync is not configured or paused
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub next_scheduled_at: Option<DateTime<Utc>>,
+    /// Set when scheduled syncs stopped because SimpleFin rejected the credentials
+    pub paused: Option<SyncPause>,
+    /// What SimpleFin reported in the last successful sync, e.g. banks that
+    /// need reauthentication
+    pub issues: Vec<SyncIssue>,
+    /// Enabled connections whose credentials were rejected; scheduled syncs
+    /// skip them until they are reconnected
+    pub reauth_required: Vec<ConnectionFailure>,
+}
+
+/// Records the start of a run.
+pub async fn start(pool: &SqlitePool, id: &str, trigger: SyncTrigger) -> Result<()> {
+    sqlx::query("INSERT INTO sync_runs (id, trigger, status, started_at) VALUES (?, ?, ?, ?)")
+        .bind(id)
+        .bind(trigger)
+        .bind(SyncRunStatus::Running)
+        .bind(Utc::now())
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Records how a run ended.
+pub async fn finish(pool: &SqlitePool, id: &str, result: &Result<SyncStats>) -> Result<()> {
+    let (status, error, stats) = match result {
+        Ok(stats) => (SyncRunStatus::Succeeded, None, Some(Json(stats))),
+        Err(e) => (SyncRunStatus::Failed, Some(format!("{:#}", e)), None),
+    };
+    sqlx::query(
+        "UPDATE sync_runs SET status = ?, finished_at = ?, error = ?, stats = ? WHERE id = ?",
+    )
+    .bind(status)
+    .bind(Utc::now())
+    .bind(error)
+    .bind(stats)
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Marks runs left running by a previous process as failed. Returns how many there were.
+pub async fn fail_interrupted(pool: &SqlitePool) -> Result<u64> {
+    let result =
+        sqlx::query("UPDATE sync_runs SET status = ?, finished_at = ?, error = ? WHERE status = ?")
+            .bind(SyncRunStatus::Failed)
+            .bind(Utc::now())
+            .bind("interrupted by server shutdown")
+            .bind(SyncRunStatus::Running)
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected())
+}
+
+/// Runs newest first, optionally only those with `status`.
+pub async fn list(
+    pool: &SqlitePool,
+    status: Option<SyncRunStatus>,
+    limit: u32,
+) -> Result<Vec<SyncRun>> {
+    let runs = sqlx::query_as::<_, SyncRun>(
+        r#"
+        SELECT * FROM sync_runs
+        WHERE ? IS NULL OR status = ?
+        ORDER BY started_at DESC
+        LIMIT ?
+        "#,
+    )
+    .bind(status)
+    .bind(status)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(runs)
+}
+
+async fn latest(pool: &SqlitePool, status: SyncRunStatus) -> Result<Option<SyncRun>> {
+    Ok(list(pool, Some(status), 1).await?.pop())
+}
+
+/// Run history part of the status; the caller fills in the schedule.
+pub async fn status(pool: &SqlitePool) -> Result<SyncStatus> {
+    let last_success = latest(pool, SyncRunStatus::Succeeded).await?;
+    let issues = last_success
+        .as_ref()
+        .and_then(|run| run.stats.as_ref())
+        .map(|stats| stats.issues.clone())
+        .unwrap_or_default();
+    let reauth_required = connections::awaiting_reauth(pool)
+        .await?
+        .into_iter()
+        .map(|connection| ConnectionFailure {
+            connection_id: connection.id,
+            name: connection.name,
+            error: connection.auth_error.unwrap_or_default(),
+        })
+        .collect();
+    Ok(SyncStatus {
+        configured: connections::any_enabled(pool).await?,
+        running: latest(pool, SyncRunStatus::Running).await?,
+        last_success,
+        last_failure: latest(pool, SyncRunStatus::Failed).await?,
+        next_scheduled_at: None,
+        paused: None,
+        issues,
+        reauth_required,
+    })
+}
@@ -0,0 +1,304 @@
+use anyhow::{Context, Result};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, QueryBuilder, Row, Sqlite, SqlitePool};
+
+use crate::models::{Transaction, TransactionQuery, TransactionSort};
+use crate::money;
+
+pub const DEFAULT_PAGE_SIZE: u32 = 100;
+pub const MAX_PAGE_SIZE: u32 = 500;
+
+/// Position after the last row of a page, in the sort order that produced it.
+///
+/// `created_at` is kept as the raw stored text because keyset comparisons run
+/// against the column as SQLite stores it. `amount` is in the units of
+/// [`money::sql_comparable_amount`], which amount sorts order by.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransactionCursor {
+    sort: TransactionSort,
+    amount: i64,
+    transaction_date: String,
+    created_at: String,
+    id: String,
+}
+
+#[derive(Debug)]
+pub struct InvalidCursor;
+
+impl TransactionCursor {
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).expect("cursor serializes to JSON");
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    /// Decodes a cursor, rejecting ones produced under a different sort order.
+    pub fn decode(raw: &str, sort: TransactionSort) -> Result<Self, InvalidCursor> {
+        let json = URL_SAFE_NO_PAD.decode(raw).map_err(|_| InvalidCursor)?;
+        let cursor: Self = serde_json::from_slice(&json).map_err(|_| InvalidCursor)?;
+        if cursor.sort != sort {
+            return Err(InvalidCursor);
+        }
+        Ok(cursor)
+    }
+}
+
+pub struct TransactionPage {
+    pub transactions: Vec<Transaction>,
+    pub next_cursor: Option<String>,
+}
+
+/// Runs a filtered, keyset-paginated query over all transactions.
+///
+/// Rows are ordered by the sort key followed by `(transaction_date, created_at, id)`
+/// so every row has a unique position and pages never skip or repeat rows.
+pub async fn query_transactions(
+    pool: &SqlitePool,
+    query: &TransactionQuery,
+    cursor: Option<&TransactionCursor>,
+) -> Result<TransactionPage> {
+    let sort = query.sort.unwrap_or_default();
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    // Amounts in different currencies are filtered and sorted by their value
+    // in major units
+    let amount = money::sql_comparable_amount("amount", "currency");
+
+    let mut builder: QueryBuilder<Sqlite> = QueryBuilder::new(format!(
+        "SELECT *, CAST(created_at AS TEXT) AS cursor_created_at, {} AS cursor_amount \
+         FROM transactions WHERE 1 = 1",
+        amount
+    ));
+
+    if let Some(account_ids) = &query.account_ids {
+        let ids: Vec<&str> = account_ids
+            .split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .collect();
+        if !ids.is_empty() {
+            builder.push(" AND account_id IN (");
+            let mut separated = builder.separated(", ");
+            for id in ids {
+                separated.push_bind(id.to_string());
+            }
+            separated.push_unseparated(")");
+        }
+    }
+    if let Some(from) = query.from {
+        builder.push(" AND transaction_date >= ").push_bind(from);
+    }
+    if let Some(to) = query.to {
+        builder.push(" AND transaction_date <= ").push_bind(to);
+    }
+    if let Some(min_amount) = query.min_amount {
+        builder
+            .push(format!(" AND {} >= ", amount))
+            .push_bind(
+                money::comparable_threshold(min_amount).context("min_amount is too large to compare")?,
+            );
+    }
+    if let Some(max_amount) = query.max_amount {
+        builder
+            .push(format!(" AND {} <= ", amount))
+            .push_bind(
+                money::comparable_threshold(max_amount).context("max_amount is too large to compare")?,
+            );
+    }
+    if let Some(category_id) = &query.category_id {
+        builder
+            .push(
+                " AND category_id IN (WITH RECURSIVE subtree (id) AS (SELECT ",
+            )
+            .push_bind(category_id.clone())
+            .push(
+                " UNION ALL SELECT c.id FROM categories c JOIN subtree s ON c.parent_id = s.id) \
+                 SELECT id FROM subtree)",
+            );
+    }
+    if let Some(merchant_id) = &query.merchant_id {
+        builder
+            .push(" AND merchant_id = ")
+            .push_bind(merchant_id.clone());
+    }
+    if let Some(pending) = query.pending {
+        builder
+            .push(" AND COALESCE(pending, FALSE) = ")
+            .push_bind(pending);
+    }
+    if let Some(payee) = &query.payee {
+        builder
+            .push(" AND payee LIKE ")
+            .push_bind(like_pattern(payee))
+            .push(" ESCAPE '\\'");
+    }
+    if let Some(text) = &query.q {
+        let pattern = like_pattern(text);
+        builder.push(" AND (");
+        for (i, column) in [
+            "description",
+            "display_description",
+            "payee",
+            "payee_override",
+            "memo",
+            "note",
+        ]
+        .iter()
+        .enumerate()
+        {
+            if i > 0 {
+                builder.push(" OR ");
+            }
+            builder
+                .push(format!("{} LIKE ", column))
+                .push_bind(pattern.clone())
+                .push(" ESCAPE '\\'");
+        }
+        builder.push(")");
+    }
+
+    let (by_amount, direction, comparison) = match sort {
+        TransactionSort::DateDesc => (false, "DESC", "<"),
+        TransactionSort::DateAsc => (false, "ASC", ">"),
+        TransactionSort::AmountDesc => (true, "DESC", "<"),
+        TransactionSort::AmountAsc => (true, "ASC", ">"),
+    };
+
+    if let Some(cursor) = cursor {
+        if by_amount {
+            builder
+                .push(format!(
+                    " AND ({}, transaction_date, created_at, id) {} (",
+                    amount, comparison
+                ))
+                .push_bind(cursor.amount)
+                .push(", ");
+        } else {
+            builder.push(format!(
+                " AND (transaction_date, created_at, id) {} (",
+                comparison
+            ));
+        }
+        builder
+            .push_bind(cursor.transaction_date.clone())
+            .push(", ")
+            .push_bind(cursor.created_at.clone())
+            .push(", ")
+            .push_bind(cursor.id.clone())
+            .push(")");
+    }
+
+    builder.push(" ORDER BY ");
+    if by_amount {
+        builder.push(format!("{} {}, ", amount, direction));
+    }
+    builder.push(format!(
+        "transaction_date {d}, created_at {d}, id {d} LIMIT ",
+        d = direction
+    ));
+    // Fetch one extra row to learn whether another page exists
+    builder.push_bind(i64::from(limit) + 1);
+
+    let rows = builder.build().fetch_all(pool).await?;
+    let has_more = rows.len() > limit as usize;
+
+    let rows = &rows[..rows.len().min(limit as usize)];
+
+    let transactions = rows
+        .iter()
+        .map(Transaction::from_row)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let next_cursor = match (has_more, rows.last(), transactions.last()) {
+        (true, Some(row), Some(last)) => Some(TransactionCursor {
+            sort,
+            amount: row.try_get("cursor_amount")?,
+            transaction_date: last.transaction_date.to_string(),
+            created_at: row.try_get("cursor_created_at")?,
+            id: last.id.clone(),
+        }),
+        _ => None,
+    };
+
+    Ok(TransactionPage {
+        transactions,
+        next_cursor: next_cursor.map(|cursor| cursor.encode()),
+    })
+}
+
+/// Builds a `LIKE` pattern matching `text` anywhere, with wildcards escaped.
+fn like_pattern(text: &str) -> String {
+    let escaped = text
+        .trim()
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cursor(sort: TransactionSort) -> TransactionCursor {
+        TransactionCursor {
+            sort,
+            amount: -125_000,
+            transaction_date: "2024-01-31".to_string(),
+            created_at: "2024-01-31 12:00:00.5+00:00".to_string(),
+            id: "tx/1+?".to_string(),
+        }
+    }
+
+    #[test]
+    fn cursors_round_trip_as_url_safe_text() {
+        let encoded = cursor(TransactionSort::AmountAsc).encode();
+        assert!(
+            encoded
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+            "{encoded}"
+        );
+
+        let decoded = TransactionCursor::decode(&encoded, TransactionSort::AmountAsc).unwrap();
+        assert_eq!(decoded.sort, TransactionSort::AmountAsc);
+        assert_eq!(decoded.amount, -125_000);
+        assert_eq!(decoded.transaction_date, "2024-01-31");
+        assert_eq!(decoded.created_at, "2024-01-31 12:00:00.5+00:00");
+        assert_eq!(decoded.id, "tx/1+?");
+    }
+
+    #[test]
+    fn cursors_only_continue_their_own_sort() {
+        let encoded = cursor(TransactionSort::DateDesc).encode();
+        for sort in [
+            TransactionSort::DateAsc,
+            TransactionSort::AmountDesc,
+            TransactionSort::AmountAsc,
+        ] {
+            assert!(TransactionCursor::decode(&encoded, sort).is_err());
+        }
+    }
+
+    #[test]
+    fn malformed_cursors_are_rejected() {
+        let sort = TransactionSort::DateDesc;
+        assert!(TransactionCursor::decode("", sort).is_err());
+        assert!(TransactionCursor::decode("not a cursor!", sort).is_err());
+        // Valid base64, but not a cursor
+        let json = URL_SAFE_NO_PAD.encode(r#"{"sort":"date_desc"}"#);
+        assert!(TransactionCursor::decode(&json, sort).is_err());
+        // Padded base64 is not what encode produces
+        let padded = format!("{}==", cursor(sort).encode());
+        assert!(TransactionCursor::decode(&padded, sort).is_err());
+    }
+
+    #[test]
+    fn like_patterns_escape_wildcards() {
+        assert_eq!(like_pattern(" coffee "), "%coffee%");
+        assert_eq!(like_pattern("50%_off\\"), "%50\\%\\_off\\\\%");
+    }
+}
@@ -0,0 +1,577 @@
+use chrono::{Days, Local, NaiveDate, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::cron::CronSchedule;
+use crate::error::{AppError, FieldError};
+use crate::models::{
+    BackfillRequest, ClaimConnectionRequest, CreateAccountRequest, CreateCategoryRequest,
+    CreateConnectionRequest, CreateTransactionRequest, MerchantInput, RuleInput,
+    TransactionQuery, UpdateAccountRequest, UpdateCategoryRequest, UpdateConnectionRequest,
+    UpdateTransactionRequest,
+};
+use crate::money::{self, Money, MoneyParseError, normalize_currency};
+use crate::rules;
+use crate::scheduler::SyncSchedule;
+use crate::simplefin::SimplefinClient;
+
+pub const MAX_NAME_LEN: usize = 100;
+pub const MAX_DESCRIPTION_LEN: usize = 500;
+pub const MAX_NOTE_LEN: usize = 2000;
+pub const MAX_ICON_LEN: usize = 50;
+pub const MAX_TAG_LEN: usize = 50;
+pub const MAX_PATTERN_LEN: usize = 500;
+pub const MAX_SETUP_TOKEN_LEN: usize = 2000;
+
+/// Largest balance or amount accepted from a client, in either direction, as
+/// a threshold in major units of any currency.
+pub const MAX_AMOUNT: Money = Money::from_minor(1_000_000_000_000 * 100);
+
+/// How far ahead of today a transaction may be dated, to allow for scheduled payments.
+pub const MAX_FUTURE_DAYS: u64 = 366;
+
+/// Longest interval between scheduled syncs, one week.
+pub const MAX_SYNC_INTERVAL_MINUTES: u32 = 7 * 24 * 60;
+
+/// A request field accepted as any JSON value and parsed during validation,
+/// so that a malformed value is reported as a field error together with every
+/// other invalid field instead of rejecting the whole body.
+///
+/// Mark these fields `#[serde(default)]`: an omitted field then reads as
+/// `null`, which is valid for an `Option` and "is required" otherwise.
+pub struct Lenient<T> {
+    raw: Value,
+    target: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> Lenient<T> {
+    pub fn new(raw: Value) -> Self {
+        Self {
+            raw,
+            target: PhantomData,
+        }
+    }
+
+    pub fn raw(&self) -> &Value {
+        &self.raw
+    }
+
+    /// Parses the value, or describes what is wrong with it.
+    pub fn parse(&self) -> Result<T, String> {
+        T::deserialize(&self.raw).map_err(|e| {
+            if self.raw.is_null() {
+                "is required".to_string()
+            } else {
+                format!("is invalid: {}", e)
+            }
+        })
+    }
+
+    /// The parsed value of a field that passed validation.
+    ///
+    /// # Panics
+    ///
+    /// When the value does not parse; call [`Validate::validate`] first.
+    pub fn value(&self) -> T {
+        self.parse().expect("field is checked by validate()")
+    }
+}
+
+impl<T> Default for Lenient<T> {
+    fn default() -> Self {
+        Self {
+            raw: Value::Null,
+            target: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for Lenient<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.raw.fmt(f)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Lenient<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Self {
+            raw: Value::deserialize(deserializer)?,
+            target: PhantomData,
+        })
+    }
+}
+
+impl<T> Serialize for Lenient<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.raw.serialize(serializer)
+    }
+}
+
+impl Lenient<Money> {
+    /// Parses an amount in `currency`, or describes what is wrong with it.
+    pub fn parse_in(&self, currency: &str) -> Result<Money, String> {
+        parse_money(&self.raw, |s| Money::parse(s, currency))?
+            .ok_or_else(|| "is required".to_string())
+    }
+
+    /// The parsed amount of a field that passed validation in `currency`.
+    pub fn value_in(&self, currency: &str) -> Money {
+        self.parse_in(currency).expect("field is checked by validate_in()")
+    }
+}
+
+impl Lenient<Option<Money>> {
+    /// Parses an optional amount in `currency`, or describes what is wrong with it.
+    pub fn parse_in(&self, currency: &str) -> Result<Option<Money>, String> {
+        parse_money(&self.raw, |s| Money::parse(s, currency))
+    }
+
+    /// The parsed amount of a field that passed validation in `currency`.
+    pub fn value_in(&self, currency: &str) -> Option<Money> {
+        self.parse_in(currency).expect("field is checked by validate_in()")
+    }
+
+    /// Parses an optional threshold, or describes what is wrong with it.
+    pub fn parse_threshold(&self) -> Result<Option<Money>, String> {
+        parse_money(&self.raw, Money::parse_threshold)
+    }
+
+    /// The parsed threshold of a field that passed validation.
+    pub fn threshold(&self) -> Option<Money> {
+        self.parse_threshold().expect("field is checked by validate()")
+    }
+}
+
+/// Reads an amount sent as a decimal string or a JSON number; `null` is `None`.
+fn parse_money(
+    raw: &Value,
+    parse: impl FnOnce(&str) -> Result<Money, MoneyParseError>,
+) -> Result<Option<Money>, String> {
+    let text = match raw {
+        Value::Null => return Ok(None),
+        Value::String(text) => text.clone(),
+        // A number's text is the literal the client sent, so it parses exactly
+        Value::Number(number) => number.to_string(),
+        _ => return Err("must be a decimal amount as a string or number".to_string()),
+    };
+    parse(&text)
+        .map(Some)
+        .map_err(|e| format!("is invalid: {}", e))
+}
+
+/// Checks a request payload before it reaches the database.
+pub trait Validate {
+    /// Returns a validation error listing every invalid field.
+    fn validate(&self) -> Result<(), AppError>;
+}
+
+/// Checks a request payload whose amounts are in the currency of a stored
+/// record, such as the account a transaction belongs to.
+pub trait ValidateIn {
+    /// Returns a validation error listing every invalid field, reading
+    /// amounts in `currency`.
+    fn validate_in(&self, currency: &str) -> Result<(), AppError>;
+}
+
+/// Collects field errors so a single response can report all of them.
+#[derive(Debug, Default)]
+pub struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn error(&mut self, field: &str, message: impl Into<String>) {
+        self.errors.push(FieldError::new(field, message));
+    }
+
+    /// Parses a [`Lenient`] field, recording why it is invalid if it is.
+    pub fn parse<T: DeserializeOwned>(&mut self, field: &str, value: &Lenient<T>) -> Option<T> {
+        match value.parse() {
+            Ok(value) => Some(value),
+            Err(message) => {
+                self.error(field, message);
+                None
+            }
+        }
+    }
+
+    /// Like [`Validator::parse`], with a message that shows the expected format.
+    pub fn date<T: DeserializeOwned>(&mut self, field: &str, value: &Lenient<T>) -> Option<T> {
+        match value.parse() {
+            Ok(value) => Some(value),
+            Err(_) if value.raw().is_null() => {
+                self.error(field, "is required");
+                None
+            }
+            Err(_) => {
+                self.error(field, "must be a date in YYYY-MM-DD format");
+                None
+            }
+        }
+    }
+
+    /// Requires non-blank text of at most `max_len` characters.
+    pub fn text(&mut self, field: &str, value: &str, max_len: usize) {
+        let value = value.trim();
+        if value.is_empty() {
+            self.error(field, "must not be empty");
+        } else if value.chars().count() > max_len {
+            self.error(field, format!("must be at most {} characters", max_len));
+        }
+    }
+
+    /// Like [`Validator::text`], but blank values are allowed because they clear the field.
+    pub fn optional_text(&mut self, field: &str, value: Option<&str>, max_len: usize) {
+        if let Some(value) = value
+            && value.trim().chars().count() > max_len
+        {
+            self.error(field, format!("must be at most {} characters", max_len));
+        }
+    }
+
+    /// Requires a three-letter ISO 4217 code. Provider-specific identifiers
+    /// only ever come from sync, never from a client.
+    pub fn currency(&mut self, field: &str, code: &str) {
+        let code = normalize_currency(code);
+        if code.len() != 3 || !code.bytes().all(|b| b.is_ascii_uppercase()) {
+            self.error(field, "must be a three-letter ISO 4217 currency code");
+        }
+    }
+
+    /// Requires a `#rrggbb` hex colour.
+    pub fn color(&mut self, field: &str, color: &str) {
+        let hex = color.trim().strip_prefix('#').unwrap_or_default();
+        if hex.len() != 6 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            self.error(field, "must be a hex colour such as #4caf50");
+        }
+    }
+
+    /// Parses an amount in `currency` and checks it against [`MAX_AMOUNT`].
+    pub fn money(&mut self, field: &str, value: &Lenient<Money>, currency: &str) -> Option<Money> {
+        match value.parse_in(currency) {
+            Ok(amount) => {
+                self.amount(field, amount, currency);
+                Some(amount)
+            }
+            Err(message) => {
+                self.error(field, message);
+                None
+            }
+        }
+    }
+
+    /// Like [`Validator::money`], for a field that may be left out.
+    pub fn optional_money(
+        &mut self,
+        field: &str,
+        value: &Lenient<Option<Money>>,
+        currency: &str,
+    ) -> Option<Money> {
+        match value.parse_in(currency) {
+            Ok(amount) => {
+                if let Some(amount) = amount {
+                    self.amount(field, amount, currency);
+                }
+                amount
+            }
+            Err(message) => {
+                self.error(field, message);
+                None
+            }
+        }
+    }
+
+    /// Parses an optional, non-negative threshold within [`MAX_AMOUNT`].
+    pub fn threshold(&mut self, field: &str, value: &Lenient<Option<Money>>) -> Option<Money> {
+        match value.parse_threshold() {
+            Ok(Some(threshold)) if threshold.is_negative() => {
+                self.error(field, "must not be negative; use sign to match debits or credits");
+                None
+            }
+            Ok(Some(threshold)) if threshold > MAX_AMOUNT => {
+                self.error(field, format!("must be at most {}", MAX_AMOUNT.display_threshold()));
+                None
+            }
+            Ok(threshold) => threshold,
+            Err(message) => {
+                self.error(field, message);
+                None
+            }
+        }
+    }
+
+    fn amount(&mut self, field: &str, amount: Money, currency: &str) {
+        if amount.abs().cmp_threshold(currency, MAX_AMOUNT).is_gt() {
+            let max = MAX_AMOUNT.display_threshold();
+            self.error(field, format!("must be between -{} and {}", max, max));
+        }
+    }
+
+    /// Requires a rule pattern that compiles.
+    pub fn pattern(&mut self, field: &str, pattern: &str) {
+        if pattern.trim().is_empty() {
+            self.error(field, "must not be empty");
+        } else if pattern.len() > MAX_PATTERN_LEN {
+            self.error(field, format!("must be at most {} characters", MAX_PATTERN_LEN));
+        } else if let Err(e) = rules::compile_pattern(pattern) {
+            self.error(field, format!("is not a valid regular expression: {}", e));
+        }
+    }
+
+    pub fn transaction_date(&mut self, field: &str, date: NaiveDate) {
+        let earliest = NaiveDate::from_ymd_opt(1900, 1, 1).expect("valid date");
+        let latest = Utc::now().date_naive() + Days::new(MAX_FUTURE_DAYS);
+        if date < earliest {
+            self.error(field, format!("must not be before {}", earliest));
+        } else if date > latest {
+            self.error(
+                field,
+                format!(
+                    "must not be more than {} days in the future",
+                    MAX_FUTURE_DAYS
+                ),
+            );
+        }
+    }
+
+    pub fn finish(self) -> Result<(), AppError> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::Validation(self.errors))
+        }
+    }
+}
+
+impl Validate for CreateAccountRequest {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut v = Validator::new();
+        v.text("name", &self.name, MAX_NAME_LEN);
+        v.text("institution", &self.institution, MAX_NAME_LEN);
+        v.parse("account_type", &self.account_type);
+        v.money("balance", &self.balance, &normalize_currency(&self.currency));
+        v.currency("currency", &self.currency);
+        v.finish()
+    }
+}
+
+/// `currency` is the account's; a new currency in the request replaces it.
+impl ValidateIn for UpdateAccountRequest {
+    fn validate_in(&self, currency: &str) -> Result<(), AppError> {
+        let mut v = Validator::new();
+        if let Some(name) = &self.name {
+            v.text("name", name, MAX_NAME_LEN);
+        }
+        if let Some(institution) = &self.institution {
+            v.text("institution", institution, MAX_NAME_LEN);
+        }
+        v.parse("account_type", &self.account_type);
+        let currency = self
+            .currency
+            .as_deref()
+            .map_or_else(|| currency.to_string(), normalize_currency);
+        v.optional_money("balance", &self.balance, &currency);
+        if let Some(currency) = &self.currency {
+            v.currency("currency", currency);
+        }
+        v.finish()
+    }
+}
+
+/// `currency` is the account's.
+impl ValidateIn for CreateTransactionRequest {
+    fn validate_in(&self, currency: &str) -> Result<(), AppError> {
+        let mut v = Validator::new();
+        if self.account_id.trim().is_empty() {
+            v.error("account_id", "must not be empty");
+        }
+        v.money("amount", &self.amount, currency);
+        v.text("description", &self.description, MAX_DESCRIPTION_LEN);
+        if let Some(transaction_date) = v.date("transaction_date", &self.transaction_date) {
+            v.transaction_date("transaction_date", transaction_date);
+        }
+        v.finish()
+    }
+}
+
+/// `currency` is the transaction's.
+impl ValidateIn for UpdateTransactionRequest {
+    fn validate_in(&self, currency: &str) -> Result<(), AppError> {
+        let mut v = Validator::new();
+        if let Some(description) = &self.description {
+            v.text("description", description, MAX_DESCRIPTION_LEN);
+        }
+        if let Some(note) = &self.note {
+            v.optional_text("note", note.as_deref(), MAX_NOTE_LEN);
+        }
+        v.optional_money("amount", &self.amount, currency);
+        if let Some(Some(transaction_date)) = v.date("transaction_date", &self.transaction_date) {
+            v.transaction_date("transaction_date", transaction_date);
+        }
+        v.finish()
+    }
+}
+
+impl Validate for CreateCategoryRequest {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut v = Validator::new();
+        v.text("name", &self.name, MAX_NAME_LEN);
+        if let Some(color) = &self.color {
+            v.color("color", color);
+        }
+        v.optional_text("icon", self.icon.as_deref(), MAX_ICON_LEN);
+        v.finish()
+    }
+}
+
+impl Validate for UpdateCategoryRequest {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut v = Validator::new();
+        if let Some(name) = &self.name {
+            v.text("name", name, MAX_NAME_LEN);
+        }
+        if let Some(Some(color)) = &self.color {
+            v.color("color", color);
+        }
+        if let Some(icon) = &self.icon {
+            v.optional_text("icon", icon.as_deref(), MAX_ICON_LEN);
+        }
+        v.finish()
+    }
+}
+
+impl Validate for RuleInput {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut v = Validator::new();
+        v.text("name", &self.name, MAX_NAME_LEN);
+        for (field, pattern) in [
+            ("description_pattern", &self.description_pattern),
+            ("payee_pattern", &self.payee_pattern),
+            ("memo_pattern", &self.memo_pattern),
+        ] {
+            if let Some(pattern) = pattern {
+                v.pattern(field, pattern);
+            }
+        }
+        let min_amount = v.threshold("min_amount", &self.min_amount);
+        let max_amount = v.threshold("max_amount", &self.max_amount);
+        if let (Some(min), Some(max)) = (min_amount, max_amount)
+            && min > max
+        {
+            v.error("max_amount", "must not be less than min_amount");
+        }
+        v.parse("sign", &self.sign);
+        if let Some(payee) = &self.set_payee {
+            v.text("set_payee", payee, MAX_NAME_LEN);
+        }
+        for (index, tag) in self.add_tags.iter().enumerate() {
+            v.text(&format!("add_tags[{}]", index), tag, MAX_TAG_LEN);
+        }
+        v.finish()
+    }
+}
+
+impl Validate for MerchantInput {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut v = Validator::new();
+        v.text("name", &self.name, MAX_NAME_LEN);
+        for (index, alias) in self.aliases.iter().enumerate() {
+            v.pattern(&format!("aliases[{}]", index), alias);
+        }
+        v.finish()
+    }
+}
+
+impl Validate for BackfillRequest {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut v = Validator::new();
+        let from = v.date("from", &self.from);
+        if let Some(from) = from {
+            v.transaction_date("from", from);
+        }
+        if let Some(Some(to)) = v.date("to", &self.to) {
+            v.transaction_date("to", to);
+            if from.is_some_and(|from| to < from) {
+                v.error("to", "must not be before from");
+            }
+        }
+        v.finish()
+    }
+}
+
+impl Validate for TransactionQuery {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut v = Validator::new();
+        let thresholds = [("min_amount", self.min_amount), ("max_amount", self.max_amount)];
+        for (field, threshold) in thresholds {
+            if threshold.is_some_and(|threshold| money::comparable_threshold(threshold).is_none()) {
+                v.error(field, "is too large to compare");
+            }
+        }
+        v.finish()
+    }
+}
+
+impl Validate for ClaimConnectionRequest {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut v = Validator::new();
+        v.text("setup_token", self.setup_token.expose(), MAX_SETUP_TOKEN_LEN);
+        v.optional_text("name", self.name.as_deref(), MAX_NAME_LEN);
+        v.finish()
+    }
+}
+
+impl Validate for CreateConnectionRequest {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut v = Validator::new();
+        if let Err(e) = SimplefinClient::new(self.access_url.expose().trim().to_string()) {
+            v.error("access_url", e.to_string());
+        }
+        v.optional_text("name", self.name.as_deref(), MAX_NAME_LEN);
+        v.finish()
+    }
+}
+
+impl Validate for UpdateConnectionRequest {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut v = Validator::new();
+        if let Some(name) = &self.name {
+            v.text("name", name, MAX_NAME_LEN);
+        }
+        v.finish()
+    }
+}
+
+impl Validate for SyncSchedule {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut v = Validator::new();
+        if !(1..=MAX_SYNC_INTERVAL_MINUTES).contains(&self.interval_minutes) {
+            v.error(
+                "interval_minutes",
+                format!("must be between 1 and {}", MAX_SYNC_INTERVAL_MINUTES),
+            );
+        }
+        if let Some(cron) = &self.cron {
+            match CronSchedule::parse(cron) {
+                Ok(schedule) if schedule.next_after(&Local::now()).is_none() => {
+                    v.error("cron", "never matches");
+                }
+                Ok(_) => {}
+                Err(e) => v.error("cron", e.to_string()),
+            }
+        }
+        if let Some(quiet_hours) = &self.quiet_hours
+            && quiet_hours.start == quiet_hours.end
+        {
+            v.error("quiet_hours.end", "must differ from start");
+        }
+        v.finish()
+    }
+}
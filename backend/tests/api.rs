@@ -0,0 +1,328 @@
+//! The API over HTTP, against an in-memory database.
+
+mod support;
+
+use serde_json::{Value, json};
+
+use support::app::{TestApp, invalid_fields};
+
+#[tokio::test]
+async fn malformed_account_fields_are_all_reported() {
+    let app = TestApp::start().await;
+
+    let (status, body) = app
+        .post(
+            "/api/accounts",
+            json!({
+                "institution": "Bank",
+                "account_type": "piggy_bank",
+                "balance": "12.3.4",
+                "currency": "dollars",
+            }),
+        )
+        .await;
+
+    assert_eq!(status, 422);
+    assert_eq!(body["success"], false);
+    assert_eq!(
+        invalid_fields(&body),
+        ["name", "account_type", "balance", "currency"]
+    );
+}
+
+#[tokio::test]
+async fn malformed_transaction_fields_are_all_reported() {
+    let app = TestApp::start().await;
+
+    let (status, body) = app
+        .post(
+            "/api/transactions",
+            json!({
+                "account_id": "acc-1",
+                "amount": "twelve",
+                "description": "Lunch",
+                "transaction_date": "31/01/2024",
+            }),
+        )
+        .await;
+
+    assert_eq!(status, 422);
+    assert_eq!(invalid_fields(&body), ["amount", "transaction_date"]);
+
+    let (status, body) = app.post("/api/transactions", json!({})).await;
+
+    assert_eq!(status, 422);
+    assert_eq!(
+        invalid_fields(&body),
+        ["account_id", "amount", "description", "transaction_date"]
+    );
+    let messages: Vec<&str> = body["error"]["details"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|detail| detail["message"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            "must not be empty",
+            "is required",
+            "must not be empty",
+            "is required"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn well_formed_account_is_created() {
+    let app = TestApp::start().await;
+
+    let (status, body) = app
+        .post(
+            "/api/accounts",
+            json!({
+                "name": "Wallet",
+                "institution": "Cash",
+                "account_type": "checking",
+                "balance": "12.30",
+                "currency": "usd",
+            }),
+        )
+        .await;
+
+    assert_eq!(status, 200, "{body}");
+    assert_eq!(body["data"]["balance"], "12.30");
+    assert_eq!(body["data"]["currency"], "USD");
+}
+
+#[tokio::test]
+async fn amounts_are_read_and_written_in_the_accounts_currency() {
+    let app = TestApp::start().await;
+    let (_, body) = app
+        .post(
+            "/api/accounts",
+            json!({
+                "name": "Travel",
+                "institution": "Cash",
+                "account_type": "cash",
+                "balance": "1234",
+                "currency": "JPY",
+            }),
+        )
+        .await;
+    assert_eq!(body["data"]["balance"], "1234");
+    let account_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    let transaction = |amount: &str| {
+        json!({
+            "account_id": account_id,
+            "amount": amount,
+            "description": "Ramen",
+            "transaction_date": "2024-01-31",
+        })
+    };
+    let (status, body) = app.post("/api/transactions", transaction("-12.5")).await;
+    assert_eq!(status, 422);
+    assert_eq!(invalid_fields(&body), ["amount"]);
+
+    let (status, body) = app.post("/api/transactions", transaction("-850")).await;
+    assert_eq!(status, 200, "{body}");
+    assert_eq!(body["data"]["amount"], "-850");
+
+    // Changing the currency keeps every amount's value in major units
+    let (status, body) = app
+        .patch(
+            &format!("/api/accounts/{}", account_id),
+            json!({ "currency": "bhd" }),
+        )
+        .await;
+    assert_eq!(status, 200, "{body}");
+    assert_eq!(body["data"]["balance"], "1234.000");
+    let (_, body) = app.get("/api/transactions").await;
+    assert_eq!(body["data"][0]["amount"], "-850.000");
+    assert_eq!(body["data"][0]["currency"], "BHD");
+}
+
+#[tokio::test]
+async fn edited_transactions_train_the_classifier() {
+    let app = TestApp::start().await;
+    let (_, body) = app
+        .post(
+            "/api/accounts",
+            json!({
+                "name": "Wallet",
+                "institution": "Cash",
+                "account_type": "cash",
+                "balance": "0",
+                "currency": "USD",
+            }),
+        )
+        .await;
+    let account_id = body["data"]["id"].as_str().unwrap().to_string();
+    let mut category_ids = Vec::new();
+    for name in ["Dining", "Groceries"] {
+        let (_, body) = app.post("/api/categories", json!({ "name": name })).await;
+        category_ids.push(body["data"]["id"].as_str().unwrap().to_string());
+    }
+    let trained = || async {
+        sqlx::query_as::<_, (String, i64, i64)>(
+            "SELECT category_id, documents, tokens FROM classifier_categories ORDER BY category_id",
+        )
+        .fetch_all(&app.pool)
+        .await
+        .unwrap()
+    };
+
+    let (status, body) = app
+        .post(
+            "/api/transactions",
+            json!({
+                "account_id": account_id,
+                "amount": "-8.50",
+                "description": "Corner Cafe",
+                "transaction_date": "2024-01-31",
+                "category_id": category_ids[0],
+            }),
+        )
+        .await;
+    assert_eq!(status, 200, "{body}");
+    assert_eq!(trained().await, [(category_ids[0].clone(), 1, 2)]);
+
+    let path = format!("/api/transactions/{}", body["data"]["id"].as_str().unwrap());
+    let (status, body) = app
+        .patch(
+            &path,
+            json!({ "category_id": category_ids[1], "description": "Corner Market" }),
+        )
+        .await;
+    assert_eq!(status, 200, "{body}");
+    assert_eq!(trained().await, [(category_ids[1].clone(), 1, 2)]);
+    let tokens: Vec<String> =
+        sqlx::query_scalar("SELECT token FROM classifier_tokens ORDER BY token")
+            .fetch_all(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(tokens, ["corner", "market"]);
+}
+
+#[tokio::test]
+async fn transactions_show_their_category_path() {
+    let app = TestApp::start().await;
+    let (_, body) = app
+        .post(
+            "/api/accounts",
+            json!({
+                "name": "Wallet",
+                "institution": "Cash",
+                "account_type": "cash",
+                "balance": "0",
+                "currency": "USD",
+            }),
+        )
+        .await;
+    let account_id = body["data"]["id"].as_str().unwrap().to_string();
+    let (_, body) = app.post("/api/categories", json!({ "name": "Food" })).await;
+    let food_id = body["data"]["id"].as_str().unwrap().to_string();
+    let (_, body) = app
+        .post(
+            "/api/categories",
+            json!({ "name": "Groceries", "parent_id": food_id }),
+        )
+        .await;
+    let groceries_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    let (status, body) = app
+        .post(
+            "/api/transactions",
+            json!({
+                "account_id": account_id,
+                "amount": "-42.10",
+                "description": "Market",
+                "transaction_date": "2024-01-31",
+                "category_id": groceries_id,
+            }),
+        )
+        .await;
+    assert_eq!(status, 200, "{body}");
+    assert_eq!(body["data"]["category"], "Food:Groceries");
+    let path = format!("/api/transactions/{}", body["data"]["id"].as_str().unwrap());
+
+    let (_, body) = app.get("/api/transactions").await;
+    assert_eq!(body["data"][0]["category"], "Food:Groceries");
+
+    // The path is derived from category_id and cannot be set
+    let (status, body) = app
+        .patch(
+            &path,
+            json!({ "category_id": food_id, "category": "Other" }),
+        )
+        .await;
+    assert_eq!(status, 200, "{body}");
+    assert_eq!(body["data"]["category"], "Food");
+
+    let (_, body) = app.patch(&path, json!({ "category_id": null })).await;
+    assert_eq!(body["data"]["category"], Value::Null);
+    let (_, body) = app.get(&path).await;
+    assert_eq!(body["data"]["category"], Value::Null);
+}
+
+#[tokio::test]
+async fn amount_filters_too_large_to_compare_are_reported() {
+    let app = TestApp::start().await;
+
+    let (status, body) = app
+        .get("/api/transactions?min_amount=1000000000000000&max_amount=10")
+        .await;
+    assert_eq!(status, 422);
+    assert_eq!(invalid_fields(&body), ["min_amount"]);
+
+    let (status, _) = app.get("/api/transactions?max_amount=999999999999").await;
+    assert_eq!(status, 200);
+}
+
+#[tokio::test]
+async fn currency_change_that_would_overflow_amounts_is_rejected() {
+    let app = TestApp::start().await;
+    let (_, body) = app
+        .post(
+            "/api/accounts",
+            json!({
+                "name": "Wallet",
+                "institution": "Cash",
+                "account_type": "cash",
+                "balance": "0",
+                "currency": "JPY",
+            }),
+        )
+        .await;
+    let path = format!("/api/accounts/{}", body["data"]["id"].as_str().unwrap());
+    // Larger than the API accepts, e.g. from before amounts were checked
+    sqlx::query(
+        "INSERT INTO transactions (id, account_id, amount, currency, description, transaction_date) \
+         VALUES ('tx-1', ?, ?, 'JPY', 'Legacy', '2024-01-31')",
+    )
+    .bind(body["data"]["id"].as_str().unwrap())
+    .bind(i64::MAX / 10)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let (status, body) = app.patch(&path, json!({ "currency": "USD" })).await;
+
+    assert_eq!(status, 422);
+    assert_eq!(invalid_fields(&body), ["currency"]);
+    let amount: i64 = sqlx::query_scalar("SELECT amount FROM transactions WHERE id = 'tx-1'")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(amount, i64::MAX / 10);
+
+    let (status, _) = app.patch(&path, json!({ "currency": "EUR" })).await;
+    assert_eq!(status, 422);
+    sqlx::query("UPDATE transactions SET amount = -1000")
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    let (status, body) = app.patch(&path, json!({ "currency": "USD" })).await;
+    assert_eq!(status, 200, "{body}");
+}
@@ -0,0 +1,122 @@
+//! Claiming SimpleFin setup tokens from the mock bridge in `support::bridge`,
+//! directly and through the API.
+
+mod support;
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use budget_tracker_backend::connections;
+use budget_tracker_backend::models::Connection;
+use budget_tracker_backend::simplefin::{ClaimError, SimplefinClient};
+use serde_json::json;
+
+use support::app::{TestApp, invalid_fields};
+use support::bridge::MockSimplefin;
+
+const CLAIM_PATH: &str = "/api/connections/simplefin/claim";
+
+#[tokio::test]
+async fn setup_token_is_exchanged_for_the_access_url_once() {
+    let bridge = MockSimplefin::start().await;
+    let token = bridge.setup_token();
+
+    let access_url = SimplefinClient::claim(&token).await.unwrap();
+    assert_eq!(access_url.expose(), bridge.access_url());
+
+    let err = SimplefinClient::claim(&token).await.unwrap_err();
+    assert!(matches!(err, ClaimError::Rejected(status) if status.as_u16() == 403));
+}
+
+#[tokio::test]
+async fn invalid_setup_tokens_are_rejected() {
+    let bridge = MockSimplefin::start().await;
+    bridge.setup_token();
+
+    // Well-formed, but never issued by the bridge
+    let unknown = STANDARD.encode(format!("{}/claim/forged", bridge.base_url()));
+    let err = SimplefinClient::claim(&unknown).await.unwrap_err();
+    assert!(matches!(err, ClaimError::Rejected(_)));
+
+    let err = SimplefinClient::claim("not a token").await.unwrap_err();
+    assert!(matches!(err, ClaimError::InvalidToken(_)));
+    let err = SimplefinClient::claim(&STANDARD.encode("ftp://bridge/claim/1"))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ClaimError::InvalidToken(_)));
+}
+
+#[tokio::test]
+async fn claimed_token_is_stored_as_a_connection() {
+    let app = TestApp::start().await;
+    let bridge = MockSimplefin::start().await;
+
+    let (status, body) = app
+        .post(
+            CLAIM_PATH,
+            json!({ "setup_token": bridge.setup_token(), "name": "Mock Bank" }),
+        )
+        .await;
+
+    assert_eq!(status, 201);
+    assert_eq!(body["data"]["name"], "Mock Bank");
+    assert!(body["data"].get("encrypted_access_url").is_none());
+    let connection: Connection = sqlx::query_as("SELECT * FROM connections")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(body["data"]["id"], connection.id);
+    let access_url = connections::access_url_of(&app.cipher, &connection).unwrap();
+    assert_eq!(access_url.expose(), bridge.access_url());
+}
+
+#[tokio::test]
+async fn used_or_invalid_tokens_are_field_errors() {
+    let app = TestApp::start().await;
+    let bridge = MockSimplefin::start().await;
+    let token = bridge.setup_token();
+
+    let (status, _) = app.post(CLAIM_PATH, json!({ "setup_token": token })).await;
+    assert_eq!(status, 201);
+
+    let (status, body) = app.post(CLAIM_PATH, json!({ "setup_token": token })).await;
+    assert_eq!(status, 422);
+    assert_eq!(invalid_fields(&body), ["setup_token"]);
+
+    let (status, body) = app
+        .post(CLAIM_PATH, json!({ "setup_token": "not a token" }))
+        .await;
+    assert_eq!(status, 422);
+    assert_eq!(invalid_fields(&body), ["setup_token"]);
+
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM connections")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+}
+
+#[tokio::test]
+async fn unusable_access_url_is_returned_with_the_error() {
+    let app = TestApp::start().await;
+    let bridge = MockSimplefin::start().await;
+    bridge.answer_claims_with("not a url");
+
+    let (status, body) = app
+        .post(CLAIM_PATH, json!({ "setup_token": bridge.setup_token() }))
+        .await;
+
+    assert_eq!(status, 500);
+    assert_eq!(body["error"]["code"], "access_url_not_stored");
+    assert_eq!(body["error"]["access_url"], "not a url");
+}
+
+#[tokio::test]
+async fn unreachable_bridge_errors_do_not_echo_the_token() {
+    let app = TestApp::start().await;
+    // Nothing listens on port 9
+    let token = STANDARD.encode("http://127.0.0.1:9/claim/secret-token");
+
+    let (status, body) = app.post(CLAIM_PATH, json!({ "setup_token": token })).await;
+
+    assert_eq!(status, 503);
+    assert_eq!(body["error"]["message"], "could not reach SimpleFin");
+}
@@ -0,0 +1,94 @@
+//! Upgrading a database created before the current schema.
+
+use std::borrow::Cow;
+use std::path::PathBuf;
+
+use budget_tracker_backend::database;
+use sqlx::SqlitePool;
+use sqlx::migrate::Migrator;
+
+/// Creates a database file migrated up to, but not including, `version`.
+async fn database_before(version: i64) -> (SqlitePool, PathBuf) {
+    let path = std::env::temp_dir().join(format!("budget-{}.db", uuid::Uuid::new_v4()));
+    let url = format!("sqlite://{}?mode=rwc", path.display());
+    let pool = SqlitePool::connect(&url).await.unwrap();
+    let mut migrator: Migrator = sqlx::migrate!("./migrations");
+    migrator.migrations = Cow::Owned(
+        migrator
+            .migrations
+            .iter()
+            .filter(|migration| migration.version < version)
+            .cloned()
+            .collect(),
+    );
+    migrator.run(&pool).await.unwrap();
+    (pool, path)
+}
+
+#[tokio::test]
+async fn legacy_categories_become_category_paths() {
+    let (pool, path) = database_before(9).await;
+    sqlx::query(
+        "INSERT INTO accounts (id, name, institution, account_type) \
+         VALUES ('acc-1', 'Checking', 'Bank', 'checking')",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    for (id, category) in [
+        ("t-1", "Food:Groceries"),
+        ("t-2", "food:groceries"),
+        ("t-3", ":"),
+        ("t-4", " :: "),
+        ("t-5", "   "),
+    ] {
+        sqlx::query(
+            "INSERT INTO transactions (id, account_id, description, transaction_date, category) \
+             VALUES (?, 'acc-1', 'Purchase', '2024-01-31', ?)",
+        )
+        .bind(id)
+        .bind(category)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+    pool.close().await;
+
+    let url = format!("sqlite://{}", path.display());
+    let pool = database::create_pool(&url).await.unwrap();
+    let paths: Vec<(String, Option<String>)> = sqlx::query_as(
+        "SELECT t.id, \
+                CASE WHEN p.name IS NULL THEN c.name ELSE p.name || ':' || c.name END \
+         FROM transactions t \
+         LEFT JOIN categories c ON c.id = t.category_id \
+         LEFT JOIN categories p ON p.id = c.parent_id \
+         ORDER BY t.id",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    let paths: Vec<(&str, Option<&str>)> = paths
+        .iter()
+        .map(|(id, path)| (id.as_str(), path.as_deref()))
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            ("t-1", Some("Food:Groceries")),
+            ("t-2", Some("Food:Groceries")),
+            ("t-3", Some(":")),
+            ("t-4", Some("::")),
+            ("t-5", None),
+        ]
+    );
+    let leftover: Option<String> = sqlx::query_scalar(
+        "SELECT name FROM sqlite_master WHERE name = 'legacy_unsplit_categories'",
+    )
+    .fetch_optional(&pool)
+    .await
+    .unwrap();
+    assert_eq!(leftover, None);
+
+    pool.close().await;
+    std::fs::remove_file(path).unwrap();
+}
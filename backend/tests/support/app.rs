@@ -0,0 +1,104 @@
+//! The API served over HTTP from an in-memory database.
+
+use std::sync::Arc;
+
+use budget_tracker_backend::app_state::AppState;
+use budget_tracker_backend::credentials::{CredentialCipher, KEY_LEN};
+use budget_tracker_backend::database;
+use budget_tracker_backend::routes;
+use budget_tracker_backend::scheduler::{SyncSchedule, SyncScheduler};
+use budget_tracker_backend::sync::SyncService;
+use serde_json::Value;
+use sqlx::SqlitePool;
+use tokio::net::TcpListener;
+
+pub struct TestApp {
+    pub pool: SqlitePool,
+    pub cipher: Arc<CredentialCipher>,
+    pub scheduler: Arc<SyncScheduler>,
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl TestApp {
+    /// Serves the API on a free local port. The scheduler is not started.
+    pub async fn start() -> Self {
+        let pool = database::create_pool("sqlite::memory:").await.unwrap();
+        let cipher = Arc::new(CredentialCipher::new(&[7; KEY_LEN]).unwrap());
+        let service = Arc::new(SyncService::new(pool.clone(), cipher.clone()));
+        let schedule = SyncSchedule {
+            interval_minutes: 60,
+            cron: None,
+            quiet_hours: None,
+        };
+        let scheduler = SyncScheduler::load(service.clone(), pool.clone(), schedule)
+            .await
+            .unwrap();
+        let app_state = AppState::new(
+            pool.clone(),
+            service,
+            scheduler.clone(),
+            cipher.clone(),
+            "USD".to_string(),
+        );
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let app = routes::router(app_state);
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        Self {
+            pool,
+            cipher,
+            scheduler,
+            base_url,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Sends `body` and returns the status code and the response envelope.
+    pub async fn post(&self, path: &str, body: Value) -> (u16, Value) {
+        let response = self
+            .client
+            .post(format!("{}{}", self.base_url, path))
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        (response.status().as_u16(), response.json().await.unwrap())
+    }
+
+    pub async fn patch(&self, path: &str, body: Value) -> (u16, Value) {
+        let response = self
+            .client
+            .patch(format!("{}{}", self.base_url, path))
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        (response.status().as_u16(), response.json().await.unwrap())
+    }
+
+    pub async fn get(&self, path: &str) -> (u16, Value) {
+        let response = self
+            .client
+            .get(format!("{}{}", self.base_url, path))
+            .send()
+            .await
+            .unwrap();
+        (response.status().as_u16(), response.json().await.unwrap())
+    }
+}
+
+/// Fields named in a validation error envelope, in order.
+pub fn invalid_fields(envelope: &Value) -> Vec<String> {
+    envelope["error"]["details"]
+        .as_array()
+        .map(|details| {
+            details
+                .iter()
+                .map(|detail| detail["field"].as_str().unwrap().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
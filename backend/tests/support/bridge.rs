@@ -0,0 +1,248 @@
+//! An in-process SimpleFin bridge.
+
+use std::sync::{Arc, Mutex};
+
+use axum::Router;
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode, header::AUTHORIZATION};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chrono::{Duration, Utc};
+use serde_json::{Value, json};
+use tokio::net::TcpListener;
+
+const USERNAME: &str = "demo";
+const PASSWORD: &str = "secret";
+
+/// What the bridge answers to `GET /simplefin/accounts`.
+#[derive(Debug, Clone)]
+pub enum Reply {
+    /// An account set. Like SimpleFin, only the requested accounts and the
+    /// transactions posted in the requested range are returned.
+    Accounts(Value),
+    /// An error status with a plain-text body
+    Status(StatusCode, &'static str),
+}
+
+/// One request the bridge received.
+#[derive(Debug, Clone)]
+pub struct AccountsRequest {
+    pub start_date: Option<i64>,
+    pub end_date: Option<i64>,
+    pub account_ids: Vec<String>,
+}
+
+#[derive(Default)]
+struct Script {
+    reply: Option<Reply>,
+    requests: Vec<AccountsRequest>,
+    /// Answer to a claim, the access URL with valid credentials
+    access_url: String,
+    /// Claim tokens issued and not claimed yet
+    unclaimed: Vec<String>,
+    issued: usize,
+}
+
+pub struct MockSimplefin {
+    base_url: String,
+    script: Arc<Mutex<Script>>,
+}
+
+impl MockSimplefin {
+    /// Starts a bridge on a free local port answering with an empty account set.
+    pub async fn start() -> Self {
+        let script = Arc::new(Mutex::new(Script::default()));
+        let app = Router::new()
+            .route("/simplefin/accounts", get(accounts))
+            .route("/simplefin/claim/:token", post(claim))
+            .with_state(script.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}/simplefin", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let bridge = Self { base_url, script };
+        bridge.script.lock().unwrap().access_url = bridge.access_url();
+        bridge
+    }
+
+    /// A new setup token, the base64-encoded claim URL, that can be claimed once.
+    pub fn setup_token(&self) -> String {
+        let mut script = self.script.lock().unwrap();
+        script.issued += 1;
+        let token = format!("token-{}", script.issued);
+        script.unclaimed.push(token.clone());
+        STANDARD.encode(format!("{}/claim/{}", self.base_url, token))
+    }
+
+    /// The bridge's root URL, without credentials.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Access URL with the credentials the bridge accepts.
+    pub fn access_url(&self) -> String {
+        self.base_url
+            .replacen("http://", &format!("http://{}:{}@", USERNAME, PASSWORD), 1)
+    }
+
+    /// Access URL with credentials the bridge rejects.
+    pub fn revoked_access_url(&self) -> String {
+        self.base_url
+            .replacen("http://", &format!("http://{}:revoked@", USERNAME), 1)
+    }
+
+    /// Answers following claims with `access_url` instead.
+    pub fn answer_claims_with(&self, access_url: &str) {
+        self.script.lock().unwrap().access_url = access_url.to_string();
+    }
+
+    /// Answers every following request with `reply`.
+    pub fn reply(&self, reply: Reply) {
+        self.script.lock().unwrap().reply = Some(reply);
+    }
+
+    /// Answers every following request with `account_set`.
+    pub fn reply_accounts(&self, account_set: Value) {
+        self.reply(Reply::Accounts(account_set));
+    }
+
+    /// Every request received so far, oldest first.
+    pub fn requests(&self) -> Vec<AccountsRequest> {
+        self.script.lock().unwrap().requests.clone()
+    }
+}
+
+async fn accounts(
+    State(script): State<Arc<Mutex<Script>>>,
+    headers: HeaderMap,
+    Query(params): Query<Vec<(String, String)>>,
+) -> Response {
+    let expected = format!(
+        "Basic {}",
+        STANDARD.encode(format!("{}:{}", USERNAME, PASSWORD))
+    );
+    if headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        != Some(expected.as_str())
+    {
+        return (StatusCode::FORBIDDEN, "Access denied").into_response();
+    }
+
+    let param = |name: &str| {
+        params
+            .iter()
+            .find(|(key, _)| key == name)
+            .and_then(|(_, value)| value.parse::<i64>().ok())
+    };
+    let request = AccountsRequest {
+        start_date: param("start-date"),
+        end_date: param("end-date"),
+        account_ids: params
+            .iter()
+            .filter(|(key, _)| key == "account")
+            .map(|(_, value)| value.clone())
+            .collect(),
+    };
+
+    let mut script = script.lock().unwrap();
+    script.requests.push(request.clone());
+    match script.reply.clone() {
+        None => axum::Json(json!({ "errors": [], "accounts": [] })).into_response(),
+        Some(Reply::Status(status, body)) => (status, body).into_response(),
+        Some(Reply::Accounts(account_set)) => {
+            axum::Json(filter(account_set, &request)).into_response()
+        }
+    }
+}
+
+/// Answers a claim of an issued, unclaimed token with the access URL, and
+/// any other with 403 like SimpleFin.
+async fn claim(State(script): State<Arc<Mutex<Script>>>, Path(token): Path<String>) -> Response {
+    let mut script = script.lock().unwrap();
+    match script
+        .unclaimed
+        .iter()
+        .position(|unclaimed| *unclaimed == token)
+    {
+        Some(index) => {
+            script.unclaimed.remove(index);
+            script.access_url.clone().into_response()
+        }
+        None => (StatusCode::FORBIDDEN, "Forbidden").into_response(),
+    }
+}
+
+/// Drops the accounts and transactions `request` did not ask for.
+fn filter(mut account_set: Value, request: &AccountsRequest) -> Value {
+    let in_range = |transaction: &Value| {
+        let posted = match transaction["posted"].as_i64() {
+            Some(posted) if posted > 0 => posted,
+            _ => transaction["transacted_at"].as_i64().unwrap_or_default(),
+        };
+        request.start_date.is_none_or(|start| posted >= start)
+            && request.end_date.is_none_or(|end| posted < end)
+    };
+
+    let accounts = account_set["accounts"].as_array_mut().unwrap();
+    accounts.retain(|account| {
+        request.account_ids.is_empty()
+            || request
+                .account_ids
+                .iter()
+                .any(|id| account["id"] == id.as_str())
+    });
+    for account in accounts {
+        if let Some(transactions) = account["transactions"].as_array_mut() {
+            transactions.retain(in_range);
+        }
+    }
+    account_set
+}
+
+/// Unix time `days` days ago.
+pub fn days_ago(days: i64) -> i64 {
+    (Utc::now() - Duration::days(days)).timestamp()
+}
+
+/// A SimpleFin account at "Mock Bank".
+pub fn account(id: &str, name: &str, balance: &str, transactions: Vec<Value>) -> Value {
+    json!({
+        "org": { "name": "Mock Bank", "domain": "mockbank.example" },
+        "id": id,
+        "name": name,
+        "currency": "USD",
+        "balance": balance,
+        "available-balance": balance,
+        "balance-date": Utc::now().timestamp(),
+        "transactions": transactions,
+    })
+}
+
+/// A posted SimpleFin transaction.
+pub fn posted(id: &str, amount: &str, description: &str, days: i64) -> Value {
+    json!({
+        "id": id,
+        "posted": days_ago(days),
+        "amount": amount,
+        "description": description,
+        "transacted_at": days_ago(days),
+    })
+}
+
+/// A pending SimpleFin transaction; SimpleFin reports `posted` as 0.
+pub fn pending(id: &str, amount: &str, description: &str, days: i64) -> Value {
+    json!({
+        "id": id,
+        "posted": 0,
+        "amount": amount,
+        "description": description,
+        "transacted_at": days_ago(days),
+        "pending": true,
+    })
+}
+
+/// An account set with no errors.
+pub fn account_set(accounts: Vec<Value>) -> Value {
+    json!({ "errors": [], "accounts": accounts })
+}
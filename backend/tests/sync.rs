@@ -0,0 +1,744 @@
+//! Runs `SyncService::sync_all` against the mock SimpleFin bridge in
+//! `support::bridge` and an in-memory database.
+
+mod support;
+
+use std::sync::Arc;
+
+use axum::http::StatusCode;
+use budget_tracker_backend::connections;
+use budget_tracker_backend::credentials::{CredentialCipher, KEY_LEN, Secret};
+use budget_tracker_backend::database;
+use budget_tracker_backend::models::{Account, Connection, Holding, Transaction};
+use budget_tracker_backend::money::Money;
+use budget_tracker_backend::provider::{
+    MockProvider, ProviderAccount, ProviderData, ProviderHolding, ProviderTransaction,
+};
+use budget_tracker_backend::simplefin::AuthenticationError;
+use budget_tracker_backend::sync::SyncService;
+use budget_tracker_backend::sync_runs::{self, SyncTrigger};
+use chrono::{Duration, Utc};
+use serde_json::json;
+use sqlx::SqlitePool;
+
+use support::bridge::{
+    MockSimplefin, Reply, account, account_set, days_ago, pending, posted,
+};
+
+struct Harness {
+    pool: SqlitePool,
+    cipher: Arc<CredentialCipher>,
+    service: Arc<SyncService>,
+    bridge: MockSimplefin,
+}
+
+impl Harness {
+    /// A fresh database with one connection to a fresh bridge.
+    async fn new() -> Self {
+        let pool = database::create_pool("sqlite::memory:").await.unwrap();
+        let cipher = Arc::new(CredentialCipher::new(&[7; KEY_LEN]).unwrap());
+        let service = Arc::new(SyncService::new(pool.clone(), cipher.clone()));
+        let bridge = MockSimplefin::start().await;
+        let harness = Self {
+            pool,
+            cipher,
+            service,
+            bridge,
+        };
+        harness
+            .connect("Mock Bank", &harness.bridge.access_url())
+            .await;
+        harness
+    }
+
+    async fn connect(&self, name: &str, access_url: &str) -> Connection {
+        connections::insert(
+            &self.pool,
+            &self.cipher,
+            Some(name),
+            &Secret::new(access_url.to_string()),
+        )
+        .await
+        .unwrap()
+    }
+
+    async fn accounts(&self) -> Vec<Account> {
+        sqlx::query_as("SELECT * FROM accounts ORDER BY external_id")
+            .fetch_all(&self.pool)
+            .await
+            .unwrap()
+    }
+
+    async fn transactions(&self) -> Vec<Transaction> {
+        sqlx::query_as("SELECT * FROM transactions ORDER BY external_id")
+            .fetch_all(&self.pool)
+            .await
+            .unwrap()
+    }
+
+    async fn run_statuses(&self) -> Vec<String> {
+        sqlx::query_scalar("SELECT status FROM sync_runs ORDER BY started_at")
+            .fetch_all(&self.pool)
+            .await
+            .unwrap()
+    }
+}
+
+/// An amount in US dollars, the bridge's default currency.
+fn money(amount: &str) -> Money {
+    Money::parse(amount, "USD").unwrap()
+}
+
+#[tokio::test]
+async fn first_sync_imports_accounts_and_transactions() {
+    let harness = Harness::new().await;
+    harness.bridge.reply_accounts(account_set(vec![
+        account(
+            "acc-1",
+            "Checking",
+            "1500.25",
+            vec![
+                posted("tx-1", "-42.10", "GROCERY MART", 3),
+                posted("tx-2", "2000.00", "PAYROLL", 10),
+            ],
+        ),
+        account(
+            "acc-2",
+            "Savings",
+            "10000.00",
+            vec![posted("tx-3", "5.12", "INTEREST", 400)],
+        ),
+    ]));
+
+    let stats = harness.service.sync_all(SyncTrigger::Manual).await.unwrap();
+
+    assert_eq!(stats.accounts_created, 2);
+    assert_eq!(stats.accounts_updated, 0);
+    assert_eq!(stats.transactions_created, 3);
+    assert_eq!(stats.balance_records_created, 2);
+    assert!(stats.issues.is_empty());
+    assert!(stats.failed_connections.is_empty());
+
+    let accounts = harness.accounts().await;
+    assert_eq!(accounts.len(), 2);
+    assert_eq!(accounts[0].provider.as_deref(), Some("simplefin"));
+    assert_eq!(accounts[0].external_id.as_deref(), Some("acc-1"));
+    assert_eq!(accounts[0].institution, "Mock Bank");
+    assert_eq!(accounts[0].balance, money("1500.25"));
+    assert!(accounts[0].last_synced_at.is_some());
+
+    let transactions = harness.transactions().await;
+    assert_eq!(transactions.len(), 3);
+    assert_eq!(transactions[0].external_id.as_deref(), Some("tx-1"));
+    assert_eq!(transactions[0].account_id, accounts[0].id);
+    assert_eq!(transactions[0].amount, money("-42.10"));
+    assert_eq!(transactions[0].pending, Some(false));
+    assert_eq!(transactions[2].account_id, accounts[1].id);
+
+    assert_eq!(harness.run_statuses().await, ["succeeded"]);
+
+    // Two years of history, requested in windows of at most 60 days; the
+    // last one is open-ended
+    let requests = harness.bridge.requests();
+    assert!(requests.len() > 1);
+    assert!(requests[0].start_date.unwrap() <= days_ago(729));
+    let (last, earlier) = requests.split_last().unwrap();
+    for request in earlier {
+        let (start, end) = (request.start_date.unwrap(), request.end_date.unwrap());
+        assert!(end - start <= Duration::days(60).num_seconds());
+    }
+    assert!(last.start_date.unwrap() >= days_ago(60));
+    assert_eq!(last.end_date, None);
+}
+
+#[tokio::test]
+async fn later_syncs_update_rows_and_request_recent_history() {
+    let harness = Harness::new().await;
+    harness.bridge.reply_accounts(account_set(vec![account(
+        "acc-1",
+        "Checking",
+        "100.00",
+        vec![posted("tx-1", "-10.00", "COFFEE", 1)],
+    )]));
+    harness.service.sync_all(SyncTrigger::Manual).await.unwrap();
+    let first_requests = harness.bridge.requests().len();
+
+    harness.bridge.reply_accounts(account_set(vec![account(
+        "acc-1",
+        "Checking",
+        "80.00",
+        vec![
+            posted("tx-1", "-10.00", "COFFEE", 1),
+            posted("tx-2", "-10.00", "COFFEE", 0),
+        ],
+    )]));
+    let stats = harness
+        .service
+        .sync_all(SyncTrigger::Scheduled)
+        .await
+        .unwrap();
+
+    assert_eq!(stats.accounts_created, 0);
+    assert_eq!(stats.accounts_updated, 1);
+    assert_eq!(stats.transactions_created, 1);
+    assert_eq!(stats.transactions_updated, 0);
+    assert_eq!(harness.accounts().await[0].balance, money("80.00"));
+    assert_eq!(harness.transactions().await.len(), 2);
+
+    // Only the overlap before the last sync is fetched again
+    let requests = harness.bridge.requests();
+    assert_eq!(requests.len(), first_requests + 1);
+    let start = requests.last().unwrap().start_date.unwrap();
+    assert!(start >= days_ago(8) && start <= days_ago(6));
+}
+
+#[tokio::test]
+async fn account_marks_are_kept_per_connection() {
+    let harness = Harness::new().await;
+    let first = connections::enabled(&harness.pool).await.unwrap().remove(0);
+    let checking = || {
+        account_set(vec![account(
+            "acc-1",
+            "Checking",
+            "100.00",
+            vec![posted("tx-1", "-10.00", "COFFEE", 1)],
+        )])
+    };
+    harness.bridge.reply_accounts(checking());
+    harness.service.sync_all(SyncTrigger::Manual).await.unwrap();
+
+    // The account moves to a second bridge whose own last sync was two weeks ago
+    let bridge = MockSimplefin::start().await;
+    bridge.reply_accounts(checking());
+    let second = harness.connect("New Bank", &bridge.access_url()).await;
+    sqlx::query("UPDATE connections SET enabled = FALSE WHERE id = ?")
+        .bind(&first.id)
+        .execute(&harness.pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE connections SET last_synced_at = ? WHERE id = ?")
+        .bind(Utc::now() - Duration::days(14))
+        .bind(&second.id)
+        .execute(&harness.pool)
+        .await
+        .unwrap();
+    harness.service.sync_all(SyncTrigger::Manual).await.unwrap();
+
+    // The first connection's recent mark does not count for the second, so
+    // the account's older history is requested from the second bridge
+    let requests = bridge.requests();
+    let start = requests[0].start_date.unwrap();
+    assert!(start >= days_ago(22) && start <= days_ago(20));
+    let older = &requests[1..];
+    assert!(!older.is_empty());
+    assert!(older.iter().all(|request| request.account_ids == ["acc-1"]));
+    let oldest = older.iter().filter_map(|request| request.start_date).min();
+    assert!(oldest.unwrap() <= days_ago(729));
+
+    let account = harness.accounts().await.remove(0);
+    assert_eq!(account.connection_id.as_deref(), Some(second.id.as_str()));
+}
+
+#[tokio::test]
+async fn pending_transaction_that_posts_under_its_id_is_updated() {
+    let harness = Harness::new().await;
+    harness.bridge.reply_accounts(account_set(vec![account(
+        "acc-1",
+        "Checking",
+        "100.00",
+        vec![pending("tx-1", "-25.00", "RESTAURANT", 2)],
+    )]));
+    harness.service.sync_all(SyncTrigger::Manual).await.unwrap();
+    assert_eq!(harness.transactions().await[0].pending, Some(true));
+
+    harness.bridge.reply_accounts(account_set(vec![account(
+        "acc-1",
+        "Checking",
+        "75.00",
+        vec![posted("tx-1", "-25.00", "RESTAURANT", 1)],
+    )]));
+    let stats = harness.service.sync_all(SyncTrigger::Manual).await.unwrap();
+
+    assert_eq!(stats.transactions_created, 0);
+    assert_eq!(stats.transactions_updated, 1);
+    let transactions = harness.transactions().await;
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(transactions[0].pending, Some(false));
+    assert!(transactions[0].posted_date.is_some());
+}
+
+#[tokio::test]
+async fn pending_transaction_that_posts_under_a_new_id_is_merged() {
+    let harness = Harness::new().await;
+    harness.bridge.reply_accounts(account_set(vec![account(
+        "acc-1",
+        "Checking",
+        "100.00",
+        vec![pending("pending-1", "-20.00", "TAXI", 2)],
+    )]));
+    harness.service.sync_all(SyncTrigger::Manual).await.unwrap();
+    let pending_row = harness.transactions().await.remove(0);
+
+    // The tip was added when it posted
+    harness.bridge.reply_accounts(account_set(vec![account(
+        "acc-1",
+        "Checking",
+        "76.00",
+        vec![posted("posted-1", "-24.00", "TAXI", 1)],
+    )]));
+    let stats = harness.service.sync_all(SyncTrigger::Manual).await.unwrap();
+
+    assert_eq!(stats.transactions_created, 0);
+    assert_eq!(stats.pending_merges.len(), 1);
+    let merge = &stats.pending_merges[0];
+    assert_eq!(merge.transaction_id, pending_row.id);
+    assert_eq!(merge.pending_external_id, "pending-1");
+    assert_eq!(merge.posted_external_id, "posted-1");
+    assert_eq!(merge.pending_amount, money("-20.00"));
+    assert_eq!(merge.posted_amount, money("-24.00"));
+
+    let transactions = harness.transactions().await;
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(transactions[0].id, pending_row.id);
+    assert_eq!(transactions[0].external_id.as_deref(), Some("posted-1"));
+    assert_eq!(transactions[0].amount, money("-24.00"));
+    assert_eq!(transactions[0].pending, Some(false));
+
+    // The pending version is not imported again if the bridge still lists it
+    harness.bridge.reply_accounts(account_set(vec![account(
+        "acc-1",
+        "Checking",
+        "76.00",
+        vec![
+            pending("pending-1", "-20.00", "TAXI", 2),
+            posted("posted-1", "-24.00", "TAXI", 1),
+        ],
+    )]));
+    let stats = harness.service.sync_all(SyncTrigger::Manual).await.unwrap();
+    assert_eq!(stats.transactions_created, 0);
+    assert_eq!(harness.transactions().await.len(), 1);
+}
+
+#[tokio::test]
+async fn pending_transaction_that_vanishes_is_removed() {
+    let harness = Harness::new().await;
+    harness.bridge.reply_accounts(account_set(vec![account(
+        "acc-1",
+        "Checking",
+        "100.00",
+        vec![
+            pending("tx-1", "-60.00", "HOTEL HOLD", 2),
+            posted("tx-2", "-5.00", "PARKING", 2),
+        ],
+    )]));
+    harness.service.sync_all(SyncTrigger::Manual).await.unwrap();
+
+    harness.bridge.reply_accounts(account_set(vec![account(
+        "acc-1",
+        "Checking",
+        "95.00",
+        vec![posted("tx-2", "-5.00", "PARKING", 2)],
+    )]));
+    let stats = harness.service.sync_all(SyncTrigger::Manual).await.unwrap();
+
+    assert_eq!(stats.pending_removed, 1);
+    let transactions = harness.transactions().await;
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(transactions[0].external_id.as_deref(), Some("tx-2"));
+}
+
+#[tokio::test]
+async fn pending_transaction_with_a_malformed_amount_is_kept() {
+    let harness = Harness::new().await;
+    harness.bridge.reply_accounts(account_set(vec![account(
+        "acc-1",
+        "Checking",
+        "100.00",
+        vec![pending("tx-1", "-60.00", "HOTEL HOLD", 2)],
+    )]));
+    harness.service.sync_all(SyncTrigger::Manual).await.unwrap();
+
+    // Still listed, but unreadable this time
+    harness.bridge.reply_accounts(account_set(vec![account(
+        "acc-1",
+        "Checking",
+        "100.00",
+        vec![pending("tx-1", "sixty", "HOTEL HOLD", 2)],
+    )]));
+    let stats = harness.service.sync_all(SyncTrigger::Manual).await.unwrap();
+
+    assert_eq!(stats.pending_removed, 0);
+    let transactions = harness.transactions().await;
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(transactions[0].external_id.as_deref(), Some("tx-1"));
+    assert_eq!(transactions[0].amount, money("-60.00"));
+}
+
+#[tokio::test]
+async fn vanished_pending_transaction_with_user_edits_is_kept() {
+    let harness = Harness::new().await;
+    harness.bridge.reply_accounts(account_set(vec![account(
+        "acc-1",
+        "Checking",
+        "100.00",
+        vec![
+            pending("tx-1", "-60.00", "HOTEL HOLD", 2),
+            pending("tx-2", "-8.00", "COFFEE", 2),
+        ],
+    )]));
+    harness.service.sync_all(SyncTrigger::Manual).await.unwrap();
+    sqlx::query("UPDATE transactions SET note = 'Deposit for the trip' WHERE external_id = 'tx-1'")
+        .execute(&harness.pool)
+        .await
+        .unwrap();
+
+    harness.bridge.reply_accounts(account_set(vec![account(
+        "acc-1",
+        "Checking",
+        "100.00",
+        vec![],
+    )]));
+    let stats = harness.service.sync_all(SyncTrigger::Manual).await.unwrap();
+
+    assert_eq!(stats.pending_removed, 1);
+    let transactions = harness.transactions().await;
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(transactions[0].external_id.as_deref(), Some("tx-1"));
+    assert_eq!(
+        transactions[0].note.as_deref(),
+        Some("Deposit for the trip")
+    );
+
+    let account = harness.accounts().await.remove(0);
+    assert_eq!(stats.issues.len(), 1);
+    assert_eq!(
+        stats.issues[0].account_id.as_deref(),
+        Some(account.id.as_str())
+    );
+    assert!(stats.issues[0].message.contains("HOTEL HOLD"));
+    assert_eq!(account.sync_error.as_ref(), Some(&stats.issues[0].message));
+}
+
+#[tokio::test]
+async fn reported_errors_are_attributed_to_accounts() {
+    let harness = Harness::new().await;
+    harness.bridge.reply_accounts(json!({
+        "errors": [
+            "Mock Bank needs you to log in again",
+            "Scheduled maintenance tonight",
+        ],
+        "errlist": [
+            { "code": "act.unavailable", "msg": "Savings is unavailable", "account_id": "acc-2" },
+        ],
+        "accounts": [
+            account("acc-1", "Checking", "100.00", vec![]),
+            account("acc-2", "Savings", "200.00", vec![]),
+        ],
+    }));
+
+    let stats = harness.service.sync_all(SyncTrigger::Manual).await.unwrap();
+
+    let accounts = harness.accounts().await;
+    let (checking, savings) = (&accounts[0], &accounts[1]);
+    let issue_accounts = |message: &str| {
+        stats
+            .issues
+            .iter()
+            .filter(|issue| issue.message == message)
+            .map(|issue| issue.account_id.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(stats.issues.len(), 3);
+    assert_eq!(
+        issue_accounts("Savings is unavailable"),
+        [Some(savings.id.clone())]
+    );
+    // Messages without an account ID belong to the connection, even when
+    // they mention an account's institution or name
+    assert_eq!(issue_accounts("Mock Bank needs you to log in again"), [None]);
+    assert_eq!(issue_accounts("Scheduled maintenance tonight"), [None]);
+
+    assert_eq!(checking.sync_error, None);
+    assert_eq!(
+        savings.sync_error.as_deref(),
+        Some("Savings is unavailable")
+    );
+    let connection = connections::enabled(&harness.pool).await.unwrap().remove(0);
+    assert_eq!(
+        connection.sync_error.as_deref(),
+        Some("Mock Bank needs you to log in again; Scheduled maintenance tonight")
+    );
+
+    // Cleared once the bridge stops reporting them
+    harness.bridge.reply_accounts(account_set(vec![
+        account("acc-1", "Checking", "100.00", vec![]),
+        account("acc-2", "Savings", "200.00", vec![]),
+    ]));
+    let stats = harness.service.sync_all(SyncTrigger::Manual).await.unwrap();
+    assert!(stats.issues.is_empty());
+    assert!(
+        harness
+            .accounts()
+            .await
+            .iter()
+            .all(|account| account.sync_error.is_none())
+    );
+    let connection = connections::enabled(&harness.pool).await.unwrap().remove(0);
+    assert_eq!(connection.sync_error, None);
+}
+
+#[tokio::test]
+async fn rejected_credentials_fail_the_sync() {
+    let harness = Harness::new().await;
+    harness
+        .bridge
+        .reply(Reply::Status(StatusCode::FORBIDDEN, "Access revoked"));
+
+    let error = harness
+        .service
+        .sync_all(SyncTrigger::Scheduled)
+        .await
+        .unwrap_err();
+
+    let authentication = error.downcast_ref::<AuthenticationError>().unwrap();
+    assert_eq!(authentication.0.as_u16(), StatusCode::FORBIDDEN.as_u16());
+    assert!(harness.accounts().await.is_empty());
+    assert_eq!(harness.run_statuses().await, ["failed"]);
+}
+
+#[tokio::test]
+async fn server_errors_fail_the_sync_without_storing_anything() {
+    let harness = Harness::new().await;
+    harness.bridge.reply_accounts(account_set(vec![account(
+        "acc-1",
+        "Checking",
+        "100.00",
+        vec![posted("tx-1", "-10.00", "COFFEE", 1)],
+    )]));
+    harness.service.sync_all(SyncTrigger::Manual).await.unwrap();
+
+    harness.bridge.reply(Reply::Status(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Bridge is down",
+    ));
+    let error = harness
+        .service
+        .sync_all(SyncTrigger::Manual)
+        .await
+        .unwrap_err();
+
+    assert!(format!("{:#}", error).contains("Bridge is down"));
+    assert!(error.downcast_ref::<AuthenticationError>().is_none());
+    assert_eq!(harness.accounts().await[0].balance, money("100.00"));
+    assert_eq!(harness.run_statuses().await, ["succeeded", "failed"]);
+}
+
+#[tokio::test]
+async fn malformed_amounts_are_skipped() {
+    let harness = Harness::new().await;
+    harness.bridge.reply_accounts(account_set(vec![
+        account(
+            "acc-1",
+            "Checking",
+            "100.00",
+            vec![
+                posted("tx-1", "-10.00", "COFFEE", 1),
+                posted("tx-2", "ten dollars", "TEA", 1),
+            ],
+        ),
+        account(
+            "acc-2",
+            "Broken",
+            "n/a",
+            vec![posted("tx-3", "-1.00", "FEE", 1)],
+        ),
+    ]));
+
+    let stats = harness.service.sync_all(SyncTrigger::Manual).await.unwrap();
+
+    assert_eq!(stats.accounts_created, 1);
+    assert_eq!(stats.transactions_created, 1);
+    let accounts = harness.accounts().await;
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].external_id.as_deref(), Some("acc-1"));
+    let transactions = harness.transactions().await;
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(transactions[0].external_id.as_deref(), Some("tx-1"));
+}
+
+#[tokio::test]
+async fn amounts_use_the_minor_units_of_their_currency() {
+    let harness = Harness::new().await;
+    let mut yen = account(
+        "acc-1",
+        "Yen",
+        "1234",
+        vec![posted("tx-1", "-500", "RAMEN", 1)],
+    );
+    yen["currency"] = json!("JPY");
+    let mut dinar = account(
+        "acc-2",
+        "Dinar",
+        "1.234",
+        vec![
+            posted("tx-2", "-0.125", "FEE", 1),
+            posted("tx-3", "-0.1255", "ROUNDED", 1),
+        ],
+    );
+    dinar["currency"] = json!("BHD");
+    harness.bridge.reply_accounts(account_set(vec![yen, dinar]));
+
+    let stats = harness.service.sync_all(SyncTrigger::Manual).await.unwrap();
+
+    assert_eq!(stats.accounts_created, 2);
+    let accounts = harness.accounts().await;
+    assert_eq!(accounts[0].balance, Money::from_minor(1234));
+    assert_eq!(accounts[1].balance, Money::from_minor(1234));
+    // Precision the currency does not have is never rounded away
+    let transactions = harness.transactions().await;
+    assert_eq!(transactions.len(), 2);
+    assert_eq!(transactions[0].amount, Money::from_minor(-500));
+    assert_eq!(transactions[1].amount, Money::from_minor(-125));
+
+    let json = serde_json::to_value(&accounts).unwrap();
+    assert_eq!(json[0]["balance"], "1234");
+    assert_eq!(json[1]["balance"], "1.234");
+    assert_eq!(
+        serde_json::to_value(&transactions[1]).unwrap()["amount"],
+        "-0.125"
+    );
+}
+
+#[tokio::test]
+async fn failing_connection_does_not_block_the_others() {
+    let harness = Harness::new().await;
+    let revoked = harness
+        .connect("Old Bank", &harness.bridge.revoked_access_url())
+        .await;
+    harness.bridge.reply_accounts(account_set(vec![account(
+        "acc-1",
+        "Checking",
+        "100.00",
+        vec![posted("tx-1", "-10.00", "COFFEE", 1)],
+    )]));
+
+    let stats = harness.service.sync_all(SyncTrigger::Manual).await.unwrap();
+
+    assert_eq!(stats.accounts_created, 1);
+    assert_eq!(stats.transactions_created, 1);
+    assert_eq!(stats.failed_connections.len(), 1);
+    assert_eq!(stats.failed_connections[0].connection_id, revoked.id);
+    assert!(
+        stats.failed_connections[0]
+            .error
+            .contains("rejected the access credentials")
+    );
+    assert_eq!(harness.run_statuses().await, ["succeeded"]);
+}
+
+#[tokio::test]
+async fn rejected_connection_is_skipped_until_retried() {
+    let harness = Harness::new().await;
+    let revoked = harness
+        .connect("Old Bank", &harness.bridge.revoked_access_url())
+        .await;
+    harness.bridge.reply_accounts(account_set(vec![account(
+        "acc-1",
+        "Checking",
+        "100.00",
+        vec![],
+    )]));
+
+    let stats = harness
+        .service
+        .sync_all(SyncTrigger::Scheduled)
+        .await
+        .unwrap();
+    assert_eq!(stats.failed_connections.len(), 1);
+    let status = sync_runs::status(&harness.pool).await.unwrap();
+    assert_eq!(status.reauth_required.len(), 1);
+    assert_eq!(status.reauth_required[0].connection_id, revoked.id);
+    assert!(
+        status.reauth_required[0]
+            .error
+            .contains("rejected the access credentials")
+    );
+
+    // Scheduled syncs leave it alone
+    let stats = harness
+        .service
+        .sync_all(SyncTrigger::Scheduled)
+        .await
+        .unwrap();
+    assert_eq!(stats.accounts_updated, 1);
+    assert!(stats.failed_connections.is_empty());
+
+    // Manual syncs try it again
+    let stats = harness.service.sync_all(SyncTrigger::Manual).await.unwrap();
+    assert_eq!(stats.failed_connections.len(), 1);
+    assert_eq!(stats.failed_connections[0].connection_id, revoked.id);
+
+    connections::clear_auth_failure(&harness.pool, Some(&revoked.id))
+        .await
+        .unwrap();
+    let status = sync_runs::status(&harness.pool).await.unwrap();
+    assert!(status.reauth_required.is_empty());
+}
+
+#[tokio::test]
+async fn mock_provider_data_is_stored_like_simplefin_data() {
+    let harness = Harness::new().await;
+    let connection = connections::enabled(&harness.pool).await.unwrap().remove(0);
+    let provider = MockProvider::new(ProviderData {
+        accounts: vec![ProviderAccount {
+            external_id: "brokerage".to_string(),
+            name: "Brokerage".to_string(),
+            institution: "Mock Invest".to_string(),
+            currency: "USD".to_string(),
+            balance: money("1500.00"),
+            available_balance: Some(money("250.00")),
+            is_credit_card: false,
+            transactions: Some(vec![ProviderTransaction {
+                external_id: "dividend-1".to_string(),
+                amount: money("12.34"),
+                posted_at: Some(Utc::now() - Duration::days(1)),
+                description: "DIVIDEND".to_string(),
+                payee: None,
+                memo: None,
+                pending: false,
+            }]),
+            unreadable_transaction_ids: Vec::new(),
+            holdings: Some(vec![ProviderHolding {
+                external_id: "vti".to_string(),
+                symbol: Some("VTI".to_string()),
+                description: "Total Stock Market ETF".to_string(),
+                shares: 5.0,
+                market_value: money("1250.00"),
+                cost_basis: Some(money("1000.00")),
+                currency: "USD".to_string(),
+            }]),
+        }],
+        messages: Vec::new(),
+    });
+
+    let stats = harness
+        .service
+        .sync_provider(&connection, &provider)
+        .await
+        .unwrap();
+
+    assert_eq!(stats.accounts_created, 1);
+    assert_eq!(stats.transactions_created, 1);
+    assert!(!provider.requests().is_empty());
+    let accounts = harness.accounts().await;
+    assert_eq!(accounts[0].provider.as_deref(), Some("mock"));
+    assert_eq!(accounts[0].external_id.as_deref(), Some("brokerage"));
+    let holdings: Vec<Holding> = sqlx::query_as("SELECT * FROM holdings")
+        .fetch_all(&harness.pool)
+        .await
+        .unwrap();
+    assert_eq!(holdings.len(), 1);
+    assert_eq!(holdings[0].account_id, accounts[0].id);
+    assert_eq!(holdings[0].symbol.as_deref(), Some("VTI"));
+    assert_eq!(holdings[0].market_value, money("1250.00"));
+}
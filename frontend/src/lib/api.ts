@@ -19,6 +19,12 @@ export class ApiError extends Error {
 
 // Helper function to handle API errors
 function handleApiError(error: any): never {
+  // Prefer the structured error envelope returned by the backend
+  const body = error?.body?.error
+  if (body?.code && body?.message) {
+    throw new ApiError(body.message, error.status, body.code)
+  }
+
   if (error?.status === 503) {
     throw new ApiError('SimpleFin sync service is currently unavailable', 503, 'SERVICE_UNAVAILABLE')
   }